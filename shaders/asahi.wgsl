@@ -3,7 +3,7 @@ struct TimeUniform {
 };
 struct ResolutionUniform {
     dimensions: vec2<f32>,
-    _padding: vec2<f32>,
+    tile_offset: vec2<f32>,
 };
 struct Params {
     // Colors
@@ -64,7 +64,7 @@ fn draw_petal_polar(uv: vec2<f32>, pos: vec2<f32>, size: f32, dir: vec2<f32>, co
 @fragment
 fn fs_main(@builtin(position) FragCoord: vec4<f32>, @location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
     let dimensions = u_resolution.dimensions;
-    let uv = 1.3 * (FragCoord.xy - 0.5 * dimensions) / dimensions.y;
+    let uv = 1.3 * (FragCoord.xy + u_resolution.tile_offset - 0.5 * dimensions) / dimensions.y;
     var fragColor = vec4<f32>(params.bg_color, 1.0);
     
     let petalSize = params.petal_size;
@@ -27,6 +27,8 @@ struct BuddhabrotParams {
     color2_b: f32,           
     sample_density: f32,     
     dithering: f32,          
+    offset_lo_x: f32,
+    offset_lo_y: f32,
 }
 @group(1) @binding(0) var<uniform> params: BuddhabrotParams;
 
@@ -72,7 +74,8 @@ fn screen_to_complex(uv: v2) -> v2 {
     var p = (uv - 0.5) * 2.0;
     p.x *= R.x / R.y;
     p = rot(params.rotation) * p;
-    p = p / params.zoom + v2(params.offset_x, params.offset_y);
+    // center = hi + lo, adding the small parts first keeps the low bits of a deep zoom
+    p = v2(params.offset_x, params.offset_y) + (v2(params.offset_lo_x, params.offset_lo_y) + p / params.zoom);
     
     return p;
 }
@@ -145,7 +148,7 @@ fn get_iteration_buffers(c: v2, max_iters: u32) -> array<u32, 3> {
 }
 
 fn complex_to_screen(p: v2) -> v2 {
-    var uv = (p - v2(params.offset_x, params.offset_y)) * params.zoom;
+    var uv = (p - v2(params.offset_x, params.offset_y) - v2(params.offset_lo_x, params.offset_lo_y)) * params.zoom;
     
     uv = rot(-params.rotation) * uv;
     
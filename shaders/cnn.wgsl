@@ -32,23 +32,29 @@ struct CNNParams {
     mouse_click_x: f32,
     mouse_click_y: f32,
     mouse_buttons: u32,
-    _padding1: f32,
-    _padding2: f32,
-    _padding3: f32,
+    prev_mouse_x: f32,
+    prev_mouse_y: f32,
+    _padding: f32,
 }
-@group(1) @binding(0) var<uniform> params: CNNParams;
+// Output of the compute shader, shown on screen
+@group(1) @binding(0) var output_texture: texture_storage_2d<rgba16float, write>;
 
-@group(2) @binding(0) var input_texture: texture_2d<f32>;
-@group(2) @binding(1) var input_sampler: sampler;
-@group(2) @binding(2) var output_texture: texture_storage_2d<rgba16float, write>;
-
-@group(3) @binding(0) var<storage, read_write> canvas_data: array<f32>;      
-@group(3) @binding(1) var<storage, read_write> conv1_data: array<f32>;       
-@group(3) @binding(2) var<storage, read_write> conv2_data: array<f32>;       
-@group(3) @binding(3) var<storage, read_write> fc_data: array<f32>;
+// Font atlas of the compute shader, the ASCII grid holds the digits. The atlas can grow,
+// so cells are addressed in texels (char_size) rather than as a 16th of its size.
+struct FontUniforms {
+    atlas_size: vec2<f32>,
+    char_size: vec2<f32>,
+    screen_size: vec2<f32>,
+};
+@group(2) @binding(0) var<uniform> u_font: FontUniforms;
+@group(2) @binding(1) var font_atlas: texture_2d<f32>;
 
-@group(2) @binding(3) var font_atlas: texture_2d<f32>;
-@group(2) @binding(4) var font_sampler: sampler;          
+// Stage resources, the same for every layer
+@group(3) @binding(0) var<uniform> params: CNNParams;
+@group(3) @binding(1) var<storage, read_write> canvas_data: array<f32>;      
+@group(3) @binding(2) var<storage, read_write> conv1_data: array<f32>;       
+@group(3) @binding(3) var<storage, read_write> conv2_data: array<f32>;       
+@group(3) @binding(4) var<storage, read_write> fc_data: array<f32>;
 
 const INPUT_SIZE: u32 = 28u;
 const CONV1_SIZE: u32 = 12u;  
@@ -81,13 +87,8 @@ fn render_digit(pos: vec2<f32>, char_pos: vec2<f32>, digit: u32, size: f32) -> f
     let grid_x = ascii_code % grid_size;
     let grid_y = ascii_code / grid_size;
     
-    let atlas_uv = vec2<f32>(
-        (f32(grid_x) + uv.x) / f32(grid_size),
-        (f32(grid_y) + uv.y) / f32(grid_size)
-    );
-    
-    let atlas_dimensions = textureDimensions(font_atlas);
-    let pixel_coord = vec2<i32>(atlas_uv * vec2<f32>(atlas_dimensions));
+    let cell = vec2<f32>(f32(grid_x), f32(grid_y)) + uv;
+    let pixel_coord = vec2<i32>(cell * u_font.char_size);
     let font_sample = textureLoad(font_atlas, pixel_coord, 0);
     return font_sample.a;
 }
@@ -114,6 +115,19 @@ fn screen_to_canvas(p: vec2<f32>, res: vec2<f32>) -> vec2<i32> {
     return min(vec2<i32>(rel), vec2(i32(params.input_resolution - 1.)));
 }
 
+// canvas position without the bounds check, for brush strokes
+fn screen_to_canvas_f(p: vec2<f32>) -> vec2<f32> {
+    let q = vec2(p.x, 1.-p.y);
+    let start = vec2(params.canvas_offset_x, params.canvas_offset_y);
+    return (q - start) / params.canvas_size * params.input_resolution;
+}
+
+fn segment_distance(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = clamp(dot(pa, ba) / max(dot(ba, ba), 1e-6), 0., 1.);
+    return length(pa - ba * h);
+}
 
 fn sample_canvas(pos: vec2<i32>) -> f32 {
     if (pos.x < 0 || pos.x >= i32(INPUT_SIZE) || pos.y < 0 || pos.y >= i32(INPUT_SIZE)) {
@@ -955,7 +969,9 @@ fn canvas_update(@builtin(global_invocation_id) id: vec3<u32>) {
         let mouse_pos = vec2<f32>(params.mouse_x, params.mouse_y);
         let canvas_pos = screen_to_canvas(mouse_pos, vec2<f32>(textureDimensions(output_texture)));
         if canvas_pos.x >= 0 {
-            let dist = length(vec2<f32>(canvas_pos - pos));
+            // stroke from last frame's position so fast moves don't leave gaps
+            let prev_pos = vec2<f32>(params.prev_mouse_x, params.prev_mouse_y);
+            let dist = segment_distance(vec2<f32>(pos), screen_to_canvas_f(prev_pos), screen_to_canvas_f(mouse_pos));
             let radius = params.brush_size * params.input_resolution * 10.;
             let intensity = 1. - smoothstep(0., radius, dist);
             if intensity > 0. {
@@ -0,0 +1,72 @@
+// Used by cuneus::OffscreenCapture to resolve supersampled exports.
+// Each output pixel covers factor x factor source texels.
+// fs_resample scales float outputs to the export size for EXR/HDR/16-bit PNG.
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+struct DownsampleParams {
+    factor: u32,
+    // 0 = box, 1 = tent
+    filter_mode: u32,
+    // overlap texels in front of the tile, the tent filter reads into them
+    source_offset: vec2<u32>,
+};
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(1) @binding(0) var<uniform> params: DownsampleParams;
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    out.tex_coords = vec2<f32>(position.x * 0.5 + 0.5, 1.0 - (position.y * 0.5 + 0.5));
+    return out;
+}
+
+fn load_clamped(p: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(source));
+    return textureLoad(source, clamp(p, vec2<i32>(0), size - 1), 0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let n = i32(params.factor);
+    let base = vec2<i32>(floor(position.xy)) * n + vec2<i32>(params.source_offset);
+    var sum = vec4<f32>(0.0);
+    var weight = 0.0;
+    if (params.filter_mode == 0u) {
+        for (var y = 0; y < n; y++) {
+            for (var x = 0; x < n; x++) {
+                sum += load_clamped(base + vec2<i32>(x, y));
+            }
+        }
+        weight = f32(n * n);
+    } else {
+        // triangle filter one output pixel wide on each side
+        let center = vec2<f32>(base) + f32(n) * 0.5;
+        for (var y = -n / 2; y < n + n / 2; y++) {
+            for (var x = -n / 2; x < n + n / 2; x++) {
+                let p = base + vec2<i32>(x, y);
+                let d = abs(vec2<f32>(p) + 0.5 - center) / f32(n);
+                let w = max(1.0 - d.x, 0.0) * max(1.0 - d.y, 0.0);
+                sum += load_clamped(p) * w;
+                weight += w;
+            }
+        }
+    }
+    return sum / max(weight, 1e-6);
+}
+
+// bilinear by hand, Rgba32Float sources can't use a filtering sampler
+@fragment
+fn fs_resample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(source));
+    let p = in.tex_coords * size - 0.5;
+    let base = vec2<i32>(floor(p));
+    let f = fract(p);
+    let top = mix(load_clamped(base), load_clamped(base + vec2<i32>(1, 0)), f.x);
+    let bottom = mix(load_clamped(base + vec2<i32>(0, 1)), load_clamped(base + vec2<i32>(1, 1)), f.x);
+    return mix(top, bottom, f.y);
+}
@@ -27,15 +27,15 @@ struct FFTParams {
     _padding1: u32,
     _padding2: u32,
 };
-@group(1) @binding(0) var<uniform> params: FFTParams;
-
-// Textures
-@group(2) @binding(0) var input_texture: texture_2d<f32>;
-@group(2) @binding(1) var tex_sampler: sampler;
-@group(2) @binding(2) var output: texture_storage_2d<rgba16float, write>;
+// Output of the compute shader, shown on screen
+@group(1) @binding(0) var output: texture_storage_2d<rgba16float, write>;
 
+// Stage resources, the same for every pass
+@group(2) @binding(0) var<uniform> params: FFTParams;
+@group(2) @binding(1) var input_texture: texture_2d<f32>;
+@group(2) @binding(2) var tex_sampler: sampler;
 // Storage buffer for FFT data
-@group(3) @binding(0) var<storage, read_write> image_data: array<vec2f>;
+@group(2) @binding(3) var<storage, read_write> image_data: array<vec2f>;
 
 const PI = 3.1415927;
 const LOG2_N_MAX = 11;
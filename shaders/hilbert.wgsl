@@ -15,13 +15,16 @@ struct ResolutionUniform {
 struct Params {
     iterations: u32,
     num_rays: f32,
-    _pad1: vec2<f32>,      
+    center_hi: vec2<f32>,
     scale: f32,             
     time_scale: f32,        
     vignette_radius: f32,   
     vignette_softness: f32, 
     color_offset: vec3<f32>,
     flanc: f32,            
+    center_lo: vec2<f32>,
+    view_scale: f32,
+    _pad2: f32,
 };
 @group(0) @binding(0) var<uniform> u_time: TimeUniform;
 @group(1) @binding(0) var<uniform> u_resolution: ResolutionUniform;
@@ -203,7 +206,12 @@ fn fs_main(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
     for (var i = 0u; i < MAX_ANGLES; i++) {
         angles[i] = 0.0;
     }
-    let pixel_pos = vec2<f32>(frag_coord.x, u_resolution.dimensions.y - frag_coord.y);
+    let dims = u_resolution.dimensions;
+    // pan/zoom: world = center_hi + (center_lo + p * view_scale), then back to the pixel
+    // space the curve is laid out in
+    let p = (2.0 * vec2<f32>(frag_coord.x, dims.y - frag_coord.y) - dims) / dims.y;
+    let world = params.center_hi + (params.center_lo + p * params.view_scale);
+    let pixel_pos = (world * dims.y + dims) * 0.5;
     for (var i = 0u; i < num_segments; i++) {
         segments[i].p0 = get_point(i) - pixel_pos;
         segments[i].p1 = get_point(i + 1u) - pixel_pos;
@@ -252,7 +260,8 @@ fn fs_main(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
         frag_color = draw_sdf(
             frag_color, 
             vec4<f32>(segment_color, 1.0),
-            sdf(segments[i], vec2<f32>(0.0)) / thickness
+            // in screen pixels, lines keep their width when zooming
+            sdf(segments[i], vec2<f32>(0.0)) / (thickness * params.view_scale)
         );
     }
     frag_color = vec4<f32>(gamma(frag_color.rgb, params.vignette_radius), frag_color.a);
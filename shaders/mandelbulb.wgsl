@@ -18,8 +18,17 @@ struct TimeUniform {
 @group(0) @binding(0) var<uniform> time_data: TimeUniform;
 
 struct MandelbulbParams {
-    mouse_x: f32,
-    mouse_y: f32,
+    camera_pos_x: f32,
+    camera_pos_y: f32,
+    camera_pos_z: f32,
+    camera_target_x: f32,
+    camera_target_y: f32,
+    camera_target_z: f32,
+    lens_center_hi_x: f32,
+    lens_center_hi_y: f32,
+    lens_center_lo_x: f32,
+    lens_center_lo_y: f32,
+    lens_scale: f32,
     power: f32,
     max_bounces: u32,
     samples_per_pixel: u32,
@@ -49,10 +58,8 @@ struct MandelbulbParams {
     manual_rotation_x: f32,
     manual_rotation_y: f32,
     manual_rotation_z: f32,
-    use_mouse_rotation: u32,
     
     gamma: f32,
-    zoom: f32,
     
     background_r: f32,
     background_g: f32,
@@ -332,27 +339,19 @@ fn draw(frag_coord: v2, frame: u32) -> v4 {
     
     let jitter = 2.0 * (initial_seed - 0.5) / R;
     let jittered_p = p + jitter;
-    let scaled_p = jittered_p * 1.5;
+    // lens zoom, center = hi + lo with the small parts added first
+    let lens_hi = v2(params.lens_center_hi_x, params.lens_center_hi_y);
+    let lens_lo = v2(params.lens_center_lo_x, params.lens_center_lo_y);
+    let scaled_p = (lens_hi + (lens_lo + jittered_p * params.lens_scale)) * 1.5;
 
     var col = v3(0.0);
 
-    let base_cam_pos = v3(5.1, 1.0, 2.0);
-    let cam_pos = base_cam_pos * params.zoom;
-    let focus_point = v3(-0.7, -0.25, -0.3);
+    let cam_pos = v3(params.camera_pos_x, params.camera_pos_y, params.camera_pos_z);
+    // the orbit target stays in focus
+    let focus_point = v3(params.camera_target_x, params.camera_target_y, params.camera_target_z);
     let cam_tar = focus_point;
     
-    var current_rotation: v3;
-    
-    if (params.use_mouse_rotation > 0) {
-        let mouse_sensitivity = 3.0;
-        current_rotation = v3(
-            (params.mouse_y - 0.5) * mouse_sensitivity,
-            (params.mouse_x - 0.5) * mouse_sensitivity,
-            0.0
-        );
-    } else {
-        current_rotation = v3(params.manual_rotation_x, params.manual_rotation_y, params.manual_rotation_z);
-    }
+    let current_rotation = v3(params.manual_rotation_x, params.manual_rotation_y, params.manual_rotation_z);
     
     let rotation = rotation_z(current_rotation.z) * rotation_y(current_rotation.y) * rotation_x(current_rotation.x);
     
@@ -0,0 +1,117 @@
+// Helper passes used by cuneus::compute::{PrefixSum, StreamCompaction}.
+// Exclusive prefix sum over u32 values in three steps:
+// scan_blocks -> scan_block_sums -> add_block_offsets
+struct ScanParams {
+    len: u32,
+    block_count: u32,
+    out_workgroup_size: u32,
+    _padding: u32,
+};
+@group(0) @binding(0) var<uniform> params: ScanParams;
+@group(0) @binding(1) var<storage, read_write> data: array<u32>;
+@group(0) @binding(2) var<storage, read_write> block_sums: array<u32>;
+
+// only used by compact_scatter
+@group(1) @binding(0) var<storage, read> flags: array<u32>;
+@group(1) @binding(1) var<storage, read_write> indices: array<u32>;
+@group(1) @binding(2) var<storage, read_write> dispatch_args: array<u32>;
+
+const BLOCK_SIZE: u32 = 256u;
+var<workgroup> scratch: array<u32, 256>;
+var<workgroup> carry: u32;
+
+// Hillis-Steele inclusive scan of scratch
+fn scan_scratch(l: u32) {
+    for (var offset = 1u; offset < BLOCK_SIZE; offset = offset * 2u) {
+        var t = 0u;
+        if (l >= offset) {
+            t = scratch[l - offset];
+        }
+        workgroupBarrier();
+        scratch[l] = scratch[l] + t;
+        workgroupBarrier();
+    }
+}
+
+@compute @workgroup_size(256, 1, 1)
+fn scan_blocks(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+    @builtin(local_invocation_id) lid: vec3<u32>,
+    @builtin(workgroup_id) wid: vec3<u32>,
+) {
+    let i = gid.x;
+    let l = lid.x;
+    var v = 0u;
+    if (i < params.len) {
+        v = data[i];
+    }
+    scratch[l] = v;
+    workgroupBarrier();
+    scan_scratch(l);
+    if (i < params.len) {
+        data[i] = scratch[l] - v;
+    }
+    if (l == BLOCK_SIZE - 1u) {
+        block_sums[wid.x] = scratch[l];
+    }
+}
+
+// single workgroup, walks over all block sums in chunks and carries the total
+@compute @workgroup_size(256, 1, 1)
+fn scan_block_sums(@builtin(local_invocation_id) lid: vec3<u32>) {
+    let l = lid.x;
+    if (l == 0u) {
+        carry = 0u;
+    }
+    workgroupBarrier();
+    let chunks = (params.block_count + BLOCK_SIZE - 1u) / BLOCK_SIZE;
+    for (var c = 0u; c < chunks; c = c + 1u) {
+        let i = c * BLOCK_SIZE + l;
+        var v = 0u;
+        if (i < params.block_count) {
+            v = block_sums[i];
+        }
+        scratch[l] = v;
+        workgroupBarrier();
+        scan_scratch(l);
+        let base = carry;
+        if (i < params.block_count) {
+            block_sums[i] = base + scratch[l] - v;
+        }
+        workgroupBarrier();
+        if (l == BLOCK_SIZE - 1u) {
+            carry = base + scratch[l];
+        }
+        workgroupBarrier();
+    }
+}
+
+@compute @workgroup_size(256, 1, 1)
+fn add_block_offsets(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+    @builtin(workgroup_id) wid: vec3<u32>,
+) {
+    if (gid.x < params.len) {
+        data[gid.x] = data[gid.x] + block_sums[wid.x];
+    }
+}
+
+// data holds the exclusive scan of flags here
+@compute @workgroup_size(256, 1, 1)
+fn compact_scatter(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let i = gid.x;
+    if (i >= params.len) {
+        return;
+    }
+    let keep = flags[i] != 0u;
+    if (keep) {
+        indices[data[i]] = i;
+    }
+    if (i == params.len - 1u) {
+        let count = data[i] + select(0u, 1u, keep);
+        dispatch_args[0] = (count + params.out_workgroup_size - 1u) / params.out_workgroup_size;
+        dispatch_args[1] = 1u;
+        dispatch_args[2] = 1u;
+        dispatch_args[3] = count;
+    }
+}
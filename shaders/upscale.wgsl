@@ -0,0 +1,44 @@
+// Used by cuneus::Upscaler to draw a reduced-resolution render to the window.
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+struct UpscaleParams {
+    texel_size: vec2<f32>,
+    sharpness: f32,
+    _padding: f32,
+};
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+@group(1) @binding(0) var<uniform> params: UpscaleParams;
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    out.tex_coords = vec2<f32>(position.x * 0.5 + 0.5, 1.0 - (position.y * 0.5 + 0.5));
+    return out;
+}
+
+@fragment
+fn fs_bilinear(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    return textureSample(source, source_sampler, tex_coords);
+}
+
+// unsharp mask on a 5-tap cross, clamped to the local min/max so edges don't ring
+@fragment
+fn fs_sharpen(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let t = params.texel_size;
+    let c = textureSample(source, source_sampler, tex_coords);
+    let n = textureSample(source, source_sampler, tex_coords + vec2<f32>(0.0, -t.y)).rgb;
+    let s = textureSample(source, source_sampler, tex_coords + vec2<f32>(0.0, t.y)).rgb;
+    let e = textureSample(source, source_sampler, tex_coords + vec2<f32>(t.x, 0.0)).rgb;
+    let w = textureSample(source, source_sampler, tex_coords + vec2<f32>(-t.x, 0.0)).rgb;
+    let blur = (n + s + e + w) * 0.25;
+    let sharpened = c.rgb + (c.rgb - blur) * params.sharpness;
+    let lo = min(c.rgb, min(min(n, s), min(e, w)));
+    let hi = max(c.rgb, max(max(n, s), max(e, w)));
+    return vec4<f32>(clamp(sharpened, lo, hi), c.a);
+}
@@ -1,44 +1,224 @@
-use crate::{Core, ShaderManager};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use crate::{CliOptions, Core, CoreConfig, OutputConfig, ShaderManager};
 use winit::{
     event::*,
-    event_loop::{EventLoop, ActiveEventLoop},
-    window::WindowAttributes,
-    dpi::LogicalSize,
+    event_loop::{EventLoop, ActiveEventLoop, ControlFlow},
+    window::{Fullscreen, Icon, WindowAttributes, WindowLevel},
+    dpi::{LogicalSize, PhysicalPosition},
     application::ApplicationHandler,
 };
 
+const CONTROL_WINDOW_SIZE: (u32, u32) = (480, 720);
+
+// a device lost again this soon after recovering waits twice as long as last time, so a
+// shader that keeps hanging the GPU does not reset the driver in a loop while being edited
+const RECOVERY_WINDOW: Duration = Duration::from_secs(10);
+const MIN_RECOVERY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECOVERY_DELAY: Duration = Duration::from_secs(8);
+
+/// How a transparent window blends with the desktop. Shaders write the alpha channel, with
+/// `PreMultiplied` the color has to be multiplied by it already.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WindowTransparency {
+    #[default]
+    Opaque,
+    PreMultiplied,
+    PostMultiplied,
+}
+
+/// Look and placement of the main window, set with `ShaderApp::set_window_options`.
+#[derive(Clone, Debug)]
+pub struct WindowOptions {
+    pub decorations: bool,
+    pub resizable: bool,
+    pub transparency: WindowTransparency,
+    pub always_on_top: bool,
+    // physical pixels, relative to `monitor` when set
+    pub position: Option<(i32, i32)>,
+    // index into the monitors winit reports, also used by --fullscreen
+    pub monitor: Option<usize>,
+    // PNG, e.g. 64x64
+    pub icon: Option<PathBuf>,
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        Self {
+            decorations: true,
+            resizable: true,
+            transparency: WindowTransparency::Opaque,
+            always_on_top: true,
+            position: None,
+            monitor: None,
+            icon: None,
+        }
+    }
+}
+
+impl WindowOptions {
+    /// Applies `--borderless`, `--transparent`, `--always-on-top`, `--position` and `--monitor`
+    pub fn with_cli(mut self, options: &CliOptions) -> Self {
+        if options.borderless {
+            self.decorations = false;
+        }
+        if options.transparent {
+            self.transparency = WindowTransparency::PreMultiplied;
+        }
+        if let Some(on_top) = options.always_on_top {
+            self.always_on_top = on_top;
+        }
+        if options.position.is_some() {
+            self.position = options.position;
+        }
+        if options.monitor.is_some() {
+            self.monitor = options.monitor;
+        }
+        self
+    }
+
+    fn alpha_mode(&self) -> Option<wgpu::CompositeAlphaMode> {
+        match self.transparency {
+            WindowTransparency::Opaque => None,
+            WindowTransparency::PreMultiplied => Some(wgpu::CompositeAlphaMode::PreMultiplied),
+            WindowTransparency::PostMultiplied => Some(wgpu::CompositeAlphaMode::PostMultiplied),
+        }
+    }
+}
+
+/// When `ShaderApp` draws frames. By default it draws continuously at the rate the present
+/// mode allows and stops while the window is hidden.
+#[derive(Clone, Debug)]
+pub struct FramePacing {
+    pub max_fps: Option<f32>,
+    // cap while none of the app's windows has focus, None keeps max_fps
+    pub unfocused_fps: Option<f32>,
+    // no frames while the window is minimized or covered, unless output windows are open
+    pub pause_when_occluded: bool,
+    // frames only after input, egui animations or `Core::request_frame`, for static shaders
+    pub on_demand: bool,
+    // the same while `ShaderControls` is paused, turn it off for shaders that keep refining
+    // while paused, such as path tracers
+    pub idle_when_paused: bool,
+}
+
+impl Default for FramePacing {
+    fn default() -> Self {
+        Self {
+            max_fps: None,
+            unfocused_fps: None,
+            pause_when_occluded: true,
+            on_demand: false,
+            idle_when_paused: true,
+        }
+    }
+}
+
+impl FramePacing {
+    /// Applies `--max-fps`, `--unfocused-fps`, `--on-demand` and `--idle-when-paused`
+    pub fn with_cli(mut self, options: &CliOptions) -> Self {
+        if options.max_fps.is_some() {
+            self.max_fps = options.max_fps;
+        }
+        if options.unfocused_fps.is_some() {
+            self.unfocused_fps = options.unfocused_fps;
+        }
+        self.on_demand |= options.on_demand;
+        if let Some(idle) = options.idle_when_paused {
+            self.idle_when_paused = idle;
+        }
+        self
+    }
+}
+
+fn load_icon(path: &std::path::Path) -> Result<Icon, String> {
+    let image = image::open(path).map_err(|e| e.to_string())?.into_rgba8();
+    let (width, height) = image.dimensions();
+    Icon::from_rgba(image.into_raw(), width, height).map_err(|e| e.to_string())
+}
+
 pub struct ShaderApp {
     window_title: String,
     window_size: (u32, u32),
+    core_config: CoreConfig,
+    window_options: WindowOptions,
+    frame_pacing: FramePacing,
+    outputs: Vec<OutputConfig>,
+    control_window: bool,
     core: Option<Core>,
 }
 
 impl ShaderApp {
+    // Parses the command line (see `CliOptions`), `--size` overrides width and height
     pub fn new(window_title: &str, width: u32, height: u32) -> (Self, EventLoop<()>) {
+        let options = CliOptions::from_env().install();
+        let (width, height) = options.size.unwrap_or((width, height));
         let event_loop = EventLoop::builder()
             .build()
             .expect("Failed to create event loop");
+        crate::hot::set_reload_waker(event_loop.create_proxy());
 
         //note: No window creation here - will happen in resumed event
         let app = Self {
             window_title: String::from(window_title),
             window_size: (width, height),
+            core_config: CoreConfig::default(),
+            window_options: WindowOptions::default(),
+            frame_pacing: FramePacing::default(),
+            outputs: Vec::new(),
+            control_window: options.control_window,
             core: None,
         };
         
         (app, event_loop)
     }
 
+    // Backend, adapter, features and limits for the device, the command line still applies on top
+    pub fn set_core_config(&mut self, config: CoreConfig) {
+        self.core_config = config;
+    }
+
+    // Decorations, transparency, always on top, placement and icon, the command line still applies on top
+    pub fn set_window_options(&mut self, options: WindowOptions) {
+        self.window_options = options;
+    }
+
+    // FPS caps and idling, the command line still applies on top
+    pub fn set_frame_pacing(&mut self, pacing: FramePacing) {
+        self.frame_pacing = pacing;
+    }
+
+    // Extra window showing a region of the frame on the same device, opened with the main one
+    pub fn add_output(&mut self, output: OutputConfig) {
+        self.outputs.push(output);
+    }
+
+    // egui panels in a separate window so the main one shows only the shader, also `--control-window`
+    pub fn set_control_window(&mut self, enabled: bool) {
+        self.control_window = enabled;
+    }
+
     pub fn run<S: ShaderManager + 'static>(
         self,
         event_loop: EventLoop<()>,
         shader_creator: impl FnOnce(&Core) -> S + 'static,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let pacing = self.frame_pacing.clone().with_cli(CliOptions::current());
         let mut handler = ShaderAppHandler {
             app: self,
+            pacing,
+            focused: true,
+            occluded: false,
+            minimized: false,
+            frame_pending: true,
+            next_request: None,
+            last_frame: Instant::now(),
             shader_creator: Some(Box::new(shader_creator)),
             shader: None,
             first_render: true,
+            next_recovery: None,
+            last_recovery: None,
+            recovery_delay: Duration::ZERO,
         };
         
         Ok(event_loop.run_app(&mut handler)?)
@@ -52,22 +232,208 @@ impl ShaderApp {
 // This struct implements ApplicationHandler to handle winit events
 struct ShaderAppHandler<S: ShaderManager> {
     app: ShaderApp,
+    pacing: FramePacing,
+    focused: bool,
+    occluded: bool,
+    minimized: bool,
+    // input arrived since the last frame
+    frame_pending: bool,
+    // earliest Core::request_frame not drawn yet
+    next_request: Option<Instant>,
+    last_frame: Instant,
     shader_creator: Option<Box<dyn FnOnce(&Core) -> S + 'static>>,
     shader: Option<S>,
     first_render: bool,
+    next_recovery: Option<Instant>,
+    last_recovery: Option<Instant>,
+    recovery_delay: Duration,
+}
+
+impl<S: ShaderManager> ShaderAppHandler<S> {
+    fn control_flow() -> ControlFlow {
+        if CliOptions::current().headless() {
+            // hidden windows get no redraws, about_to_wait drives the frames
+            ControlFlow::Poll
+        } else {
+            ControlFlow::Wait
+        }
+    }
+
+    fn schedule_recovery(&mut self, now: Instant) -> Instant {
+        let recent = self.last_recovery.is_some_and(|at| now.duration_since(at) < RECOVERY_WINDOW);
+        self.recovery_delay = if recent {
+            (self.recovery_delay * 2).clamp(MIN_RECOVERY_DELAY, MAX_RECOVERY_DELAY)
+        } else {
+            Duration::ZERO
+        };
+        let at = now + self.recovery_delay;
+        self.next_recovery = Some(at);
+        at
+    }
+
+    fn open_outputs(&self, event_loop: &ActiveEventLoop, core: &mut Core) {
+        for (i, output) in self.app.outputs.iter().enumerate() {
+            let title = output
+                .title
+                .clone()
+                .unwrap_or_else(|| format!("{} (output {})", self.app.window_title, i + 1));
+            let mut attributes = WindowAttributes::default()
+                .with_inner_size(LogicalSize::new(output.size.0, output.size.1))
+                .with_title(title);
+            let monitor = output.monitor.and_then(|index| {
+                let monitor = event_loop.available_monitors().nth(index);
+                if monitor.is_none() {
+                    log::warn!("Monitor {} not found, opening output {} on the default one", index, i + 1);
+                }
+                monitor
+            });
+            if let Some(monitor) = &monitor {
+                attributes = attributes.with_position(monitor.position());
+            }
+            if output.fullscreen {
+                attributes = attributes.with_fullscreen(Some(Fullscreen::Borderless(monitor)));
+            }
+            let result = event_loop
+                .create_window(attributes)
+                .map_err(|e| e.to_string())
+                .and_then(|window| core.add_output(window, output.region).map_err(|e| e.to_string()));
+            if let Err(e) = result {
+                log::error!("Failed to open output {}: {}", i + 1, e);
+            }
+        }
+    }
+
+    fn open_control_window(&self, event_loop: &ActiveEventLoop, core: &mut Core) {
+        let attributes = WindowAttributes::default()
+            .with_inner_size(LogicalSize::new(CONTROL_WINDOW_SIZE.0, CONTROL_WINDOW_SIZE.1))
+            .with_title(format!("{} controls", self.app.window_title));
+        let result = event_loop
+            .create_window(attributes)
+            .map_err(|e| e.to_string())
+            .and_then(|window| core.open_control_window(window).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::warn!("Failed to open the control window, keeping the panels in the main one: {}", e);
+        }
+    }
+
+    // Requests the next frame or sleeps until one is due, see FramePacing
+    fn pace_frames(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(core) = &self.app.core else {
+            return;
+        };
+        let now = Instant::now();
+        if let Some(at) = core.take_frame_request() {
+            self.next_request = Some(self.next_request.map_or(at, |pending| pending.min(at)));
+        }
+        let hidden = self.pacing.pause_when_occluded && (self.occluded || self.minimized) && core.outputs.is_empty();
+        let idle = self.pacing.on_demand || (self.pacing.idle_when_paused && !core.animating());
+        let requested = self.frame_pending || self.next_request.is_some_and(|at| at <= now);
+        if !requested && (hidden || idle) {
+            event_loop.set_control_flow(match self.next_request {
+                Some(at) => ControlFlow::WaitUntil(at),
+                None => ControlFlow::Wait,
+            });
+            return;
+        }
+        let fps = if self.focused { self.pacing.max_fps } else { self.pacing.unfocused_fps.or(self.pacing.max_fps) };
+        if let Some(fps) = fps.filter(|fps| *fps > 0.0) {
+            let next = self.last_frame + Duration::from_secs_f32(1.0 / fps);
+            if now < next {
+                event_loop.set_control_flow(ControlFlow::WaitUntil(next));
+                return;
+            }
+        }
+        event_loop.set_control_flow(ControlFlow::Wait);
+        core.window().request_redraw();
+    }
+
+    // Recreates the device and the shader once the backoff has passed
+    fn recover_device(&mut self, event_loop: &ActiveEventLoop) {
+        let now = Instant::now();
+        let at = match self.next_recovery {
+            Some(at) => at,
+            None => self.schedule_recovery(now),
+        };
+        if now < at {
+            event_loop.set_control_flow(ControlFlow::WaitUntil(at));
+            return;
+        }
+        let (Some(core), Some(shader)) = (&mut self.app.core, &mut self.shader) else {
+            return;
+        };
+        self.next_recovery = None;
+        self.last_recovery = Some(now);
+        match pollster::block_on(core.recreate_device()) {
+            Ok(()) => {
+                shader.recreate(core);
+                event_loop.set_control_flow(Self::control_flow());
+                core.window().request_redraw();
+            }
+            Err(e) => {
+                log::error!("Failed to recreate the device: {}", e);
+                let at = self.schedule_recovery(now);
+                event_loop.set_control_flow(ControlFlow::WaitUntil(at));
+            }
+        }
+    }
 }
 
 impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window_attributes = WindowAttributes::default()
+        let options = CliOptions::current();
+        let window_options = self.app.window_options.clone().with_cli(options);
+        let mut window_attributes = WindowAttributes::default()
             .with_inner_size(LogicalSize::new(self.app.window_size.0, self.app.window_size.1))
             .with_title(&self.app.window_title)
-            .with_resizable(true);
+            .with_resizable(window_options.resizable)
+            .with_decorations(window_options.decorations)
+            .with_transparent(window_options.transparency != WindowTransparency::Opaque)
+            .with_window_level(if window_options.always_on_top { WindowLevel::AlwaysOnTop } else { WindowLevel::Normal })
+            .with_visible(!options.headless());
+        let monitor = window_options.monitor.and_then(|index| {
+            let monitor = event_loop.available_monitors().nth(index);
+            if monitor.is_none() {
+                log::warn!("Monitor {} not found, using the default one", index);
+            }
+            monitor
+        });
+        let origin = monitor.as_ref().map_or(PhysicalPosition::new(0, 0), |monitor| monitor.position());
+        if let Some((x, y)) = window_options.position {
+            window_attributes = window_attributes.with_position(PhysicalPosition::new(origin.x + x, origin.y + y));
+        } else if monitor.is_some() {
+            window_attributes = window_attributes.with_position(origin);
+        }
+        if let Some(path) = &window_options.icon {
+            match load_icon(path) {
+                Ok(icon) => window_attributes = window_attributes.with_window_icon(Some(icon)),
+                Err(e) => log::warn!("Failed to load window icon {}: {}", path.display(), e),
+            }
+        }
+        if options.fullscreen {
+            window_attributes = window_attributes.with_fullscreen(Some(Fullscreen::Borderless(monitor)));
+        }
+        event_loop.set_control_flow(Self::control_flow());
         let window = event_loop
             .create_window(window_attributes)
             .expect("Failed to create window");
-        window.set_window_level(winit::window::WindowLevel::AlwaysOnTop);
-        let core = pollster::block_on(Core::new(window));
+        let mut config = self.app.core_config.clone().with_cli(options);
+        if let Some(alpha_mode) = window_options.alpha_mode() {
+            config.alpha_mode = Some(alpha_mode);
+        }
+        let mut core = match pollster::block_on(Core::with_config(window, &config)) {
+            Ok(core) => core,
+            Err(e) => {
+                log::error!("{}", e);
+                event_loop.exit();
+                return;
+            }
+        };
+        if !options.headless() {
+            self.open_outputs(event_loop, &mut core);
+            if self.app.control_window {
+                self.open_control_window(event_loop, &mut core);
+            }
+        }
         // Initialize the shader with the core if it hasn't been initialized yet
         if let Some(shader_creator) = self.shader_creator.take() {
             let shader = shader_creator(&core);
@@ -83,6 +449,41 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
         window_id: winit::window::WindowId,
         event: WindowEvent,
     ) {
+        let is_main = self.app.core.as_ref().is_some_and(|core| core.window().id() == window_id);
+        match &event {
+            WindowEvent::RedrawRequested => {}
+            WindowEvent::Focused(focused) => self.focused = *focused,
+            WindowEvent::Occluded(occluded) if is_main => self.occluded = *occluded,
+            WindowEvent::Resized(size) if is_main => self.minimized = size.width == 0 || size.height == 0,
+            _ => {}
+        }
+        if !matches!(event, WindowEvent::RedrawRequested) {
+            self.frame_pending = true;
+        }
+        if let Some(core) = &mut self.app.core {
+            if core.is_control_window(window_id) {
+                match event {
+                    WindowEvent::Resized(size) => {
+                        core.resize_control_window(size);
+                        core.push_ui_event(WindowEvent::Resized(size));
+                    }
+                    // the panels go back onto the main window, its egui state picks them up
+                    WindowEvent::CloseRequested => core.close_control_window(),
+                    WindowEvent::RedrawRequested => {}
+                    event => core.push_ui_event(event),
+                }
+                return;
+            }
+            if let Some(index) = core.output_index(window_id) {
+                // input goes through the main window, outputs only follow the frame
+                match event {
+                    WindowEvent::Resized(size) => core.resize_output(index, size),
+                    WindowEvent::CloseRequested => core.remove_output(index),
+                    _ => {}
+                }
+                return;
+            }
+        }
         // Only process events if core and shader are initialized
         if let (Some(core), Some(shader)) = (&self.app.core, &mut self.shader) {
             if window_id == core.window().id() {
@@ -97,20 +498,33 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
                                 shader.resize(core);
                             }
                         }
+                        // nothing to draw with until about_to_wait recreates the device
+                        WindowEvent::RedrawRequested if core.device_lost() => {}
                         WindowEvent::RedrawRequested => {
+                            let now = Instant::now();
+                            self.last_frame = now;
+                            self.frame_pending = false;
+                            if self.next_request.is_some_and(|at| at <= now) {
+                                self.next_request = None;
+                            }
                             shader.update(core);
                             match shader.render(core) {
                                 Ok(_) => {
                                     if self.first_render {
                                         self.first_render = false;
                                     }
+                                    core.present_outputs();
                                 }
-                                Err(wgpu::SurfaceError::Lost) => {
+                                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
                                     if let Some(core) = &mut self.app.core {
                                         core.resize(core.size);
                                     }
                                 }
+                                // the next frame tries again
+                                Err(wgpu::SurfaceError::Timeout) => {}
                                 Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
+                                // a lost device is picked up in about_to_wait
+                                Err(_) if core.device_lost() => {}
                                 Err(e) => eprintln!("Render error: {:?}", e),
                             }
                         }
@@ -121,12 +535,29 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        if let Some(core) = &self.app.core {
-            core.window().request_redraw();
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(core) = &self.app.core else {
+            return;
+        };
+        if core.exit_requested() {
+            event_loop.exit();
+        } else if core.device_lost() {
+            self.recover_device(event_loop);
+        } else if CliOptions::current().headless() {
+            // exports run in update, no surface needed
+            if let Some(shader) = &mut self.shader {
+                shader.update(core);
+            }
+        } else {
+            self.pace_frames(event_loop);
         }
     }
     
+    // a watched shader file changed, draw so the hot reload is picked up under on demand pacing
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: ()) {
+        self.frame_pending = true;
+    }
+
     fn new_events(&mut self, _event_loop: &ActiveEventLoop, _cause: StartCause) {
         // No special handling needed for new events
     }
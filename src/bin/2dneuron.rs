@@ -1,4 +1,4 @@
-use cuneus::{Core, ShaderManager, UniformProvider, UniformBinding, RenderKit, TextureManager, create_feedback_texture_pair, ExportManager, ShaderHotReload, ShaderControls};
+use cuneus::{Core, ShaderManager, UniformProvider, UniformBinding, RenderKit, TextureManager, create_feedback_texture_pair, ExportManager, ShaderHotReload, ShaderControls, FrameCapture};
 use winit::event::WindowEvent;
 use cuneus::ShaderApp;
 use cuneus::Renderer;
@@ -74,26 +74,23 @@ impl Shader {
             label: Some("multi_texture_bind_group"),
         })
     }
+}
 
-    fn capture_frame(&mut self, core: &Core, time: f32) -> Result<Vec<u8>, wgpu::SurfaceError> {
-        let settings = self.base.export_manager.settings();
-        let (capture_texture, output_buffer) = self.base.create_capture_texture(
-            &core.device,
-            settings.width,
-            settings.height
-        );
-        
-        let align = 256;
-        let unpadded_bytes_per_row = settings.width * 4;
-        let padding = (align - unpadded_bytes_per_row % align) % align;
-        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
+impl FrameCapture for Shader {
+    fn render_kit(&mut self) -> &mut RenderKit {
+        &mut self.base
+    }
 
-        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Capture Encoder"),
-        });
-        
-        self.base.time_uniform.data.time = time;
+    cuneus::impl_frame_capture_presets!(params_uniform, hot_reload);
+
+    fn render_capture(
+        &mut self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        _time: f32,
+        _frame: u32,
+    ) {
         self.base.time_uniform.update(&core.queue);
         
         self.base.time_uniform.data.frame = self.frame_count;
@@ -107,7 +104,7 @@ impl Shader {
 
         {
             let mut render_pass = Renderer::begin_render_pass(
-                &mut encoder,
+                encoder,
                 &temp_tex1.view,
                 wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                 Some("Capture Pass 1"),
@@ -130,7 +127,7 @@ impl Shader {
 
         {
             let mut render_pass = Renderer::begin_render_pass(
-                &mut encoder,
+                encoder,
                 &temp_tex2.view,
                 wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                 Some("Capture Pass 2"),
@@ -167,7 +164,7 @@ impl Shader {
             );
             
             let mut render_pass = Renderer::begin_render_pass(
-                &mut encoder,
+                encoder,
                 &temp_tex3.view,
                 wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                 Some("Capture Pass 3"),
@@ -184,8 +181,8 @@ impl Shader {
         // Fourth Pass (Image) - Final output
         {
             let mut render_pass = Renderer::begin_render_pass(
-                &mut encoder,
-                &capture_view,
+                encoder,
+                view,
                 wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                 Some("Capture Pass 4"),
             );
@@ -197,61 +194,6 @@ impl Shader {
             render_pass.set_bind_group(2, &self.params_uniform.bind_group, &[]);
             render_pass.draw(0..4, 0..1);
         }
-
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture: &capture_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &output_buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(settings.height),
-                },
-            },
-            wgpu::Extent3d {
-                width: settings.width,
-                height: settings.height,
-                depth_or_array_layers: 1,
-            },
-        );
-
-        core.queue.submit(Some(encoder.finish()));
-
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-        
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
-        core.device.poll(wgpu::Maintain::Wait);
-        
-        rx.recv().unwrap().unwrap();
-        let padded_data = buffer_slice.get_mapped_range().to_vec();
-
-        let mut unpadded_data = Vec::with_capacity((settings.width * settings.height * 4) as usize);
-        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
-            unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
-        }
-        
-        Ok(unpadded_data)
-    }
-
-    fn handle_export(&mut self, core: &Core) {
-        if let Some((frame, time)) = self.base.export_manager.try_get_next_frame() {
-            if let Ok(data) = self.capture_frame(core, time) {
-                let settings = self.base.export_manager.settings();
-                if let Err(e) = cuneus::save_frame(data, frame, settings) {
-                    eprintln!("Error saving frame: {:?}", e);
-                }
-            }
-        } else {
-            self.base.export_manager.complete_export();
-        }
     }
 }
 
@@ -560,6 +502,8 @@ impl ShaderManager for Shader {
         if self.base.export_manager.is_exporting() {
             self.handle_export(core);
         }
+        self.handle_screenshot(core);
+        self.handle_preset_request(core);
         self.base.fps_tracker.update();
 
     }
@@ -579,7 +523,7 @@ impl ShaderManager for Shader {
             &self.base.start_time,
             &core.size
         );
-        controls_request.current_fps = Some(self.base.fps_tracker.fps());
+        self.base.add_frame_stats(&mut controls_request);
         let full_output = if self.base.key_handler.show_ui {
             self.base.render_ui(core, |ctx| {
                 ctx.style_mut(|style| {
@@ -809,8 +753,12 @@ impl ShaderManager for Shader {
         Ok(())
     }
 
+    fn recreate(&mut self, core: &Core) {
+        self.recreate_keeping_state(core);
+    }
+
     fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
-        if self.base.egui_state.on_window_event(core.window(), event).consumed {
+        if self.base.handle_egui_event(core, event) {
             return true;
         }
         if let WindowEvent::KeyboardInput { event, .. } = event {
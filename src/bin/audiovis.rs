@@ -1,4 +1,4 @@
-use cuneus::{Core,Renderer,ShaderApp, ShaderManager, UniformProvider, UniformBinding, RenderKit,ExportManager,ShaderHotReload,ShaderControls};
+use cuneus::{Core,Renderer,ShaderApp, ShaderManager, UniformProvider, UniformBinding, RenderKit,ExportManager,ShaderHotReload,ShaderControls, FrameCapture};
 use winit::event::*;
 use std::path::PathBuf;
 #[repr(C)]
@@ -37,32 +37,26 @@ struct AudioVis {
     resolution_bind_group_layout: wgpu::BindGroupLayout,
     params_bind_group_layout: wgpu::BindGroupLayout,
 }
-impl AudioVis {
+impl FrameCapture for AudioVis {
+    fn render_kit(&mut self) -> &mut RenderKit {
+        &mut self.base
+    }
 
-    fn capture_frame(&mut self, core: &Core, time: f32) -> Result<Vec<u8>, wgpu::SurfaceError> {
-        let settings = self.base.export_manager.settings();
-        let (capture_texture, output_buffer) = self.base.create_capture_texture(
-            &core.device,
-            settings.width,
-            settings.height
-        );
-        let align = 256;
-        let unpadded_bytes_per_row = settings.width * 4;
-        let padding = (align - unpadded_bytes_per_row % align) % align;
-        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
-        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Capture Encoder"),
-        });
-        self.base.time_uniform.data.time = time;
-        self.base.time_uniform.update(&core.queue);
-        self.base.resolution_uniform.data.dimensions = [settings.width as f32, settings.height as f32];
-        self.base.resolution_uniform.update(&core.queue);
+    cuneus::impl_frame_capture_presets!(params_uniform, hot_reload);
+
+    fn render_capture(
+        &mut self,
+        _core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        _time: f32,
+        _frame: u32,
+    ) {
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Capture Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &capture_view,
+                    view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -87,56 +81,9 @@ impl AudioVis {
             render_pass.set_bind_group(3, &self.base.resolution_uniform.bind_group, &[]);
             render_pass.draw(0..4, 0..1);
         }
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture: &capture_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &output_buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(settings.height),
-                },
-            },
-            wgpu::Extent3d {
-                width: settings.width,
-                height: settings.height,
-                depth_or_array_layers: 1,
-            },
-        );
-        core.queue.submit(Some(encoder.finish()));
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
-        core.device.poll(wgpu::Maintain::Wait);
-        rx.recv().unwrap().unwrap();
-        let padded_data = buffer_slice.get_mapped_range().to_vec();
-        let mut unpadded_data = Vec::with_capacity((settings.width * settings.height * 4) as usize);
-        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
-            unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
-        }
-        Ok(unpadded_data)
-    }
-
-    fn handle_export(&mut self, core: &Core) {
-        if let Some((frame, time)) = self.base.export_manager.try_get_next_frame() {
-            if let Ok(data) = self.capture_frame(core, time) {
-                let settings = self.base.export_manager.settings();
-                if let Err(e) = cuneus::save_frame(data, frame, settings) {
-                    eprintln!("Error saving frame: {:?}", e);
-                }
-            }
-        } else {
-            self.base.export_manager.complete_export();
-        }
     }
 }
+
 impl ShaderManager for AudioVis {
     fn init(core: &cuneus::Core) -> Self {
         let time_bind_group_layout = core.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -244,13 +191,14 @@ impl ShaderManager for AudioVis {
             vs_module,
             fs_module,
         ).expect("Failed to initialize hot reload");
-        let base = RenderKit::new(
+        let mut base = RenderKit::new(
             core,
             include_str!("../../shaders/vertex.wgsl"),
             include_str!("../../shaders/audiovis.wgsl"),
             &bind_group_layouts,
             None,
         );
+        base.render_scale_enabled = true;
         Self {
             base,
             params_uniform,
@@ -287,7 +235,10 @@ impl ShaderManager for AudioVis {
         if self.base.export_manager.is_exporting() {
             self.handle_export(core);
         }
+        self.handle_screenshot(core);
+        self.handle_preset_request(core);
         self.base.fps_tracker.update();
+        self.base.update_render_scale(core);
     }
     
     fn render(&mut self, core: &Core) -> Result<(), wgpu::SurfaceError> {
@@ -306,7 +257,7 @@ impl ShaderManager for AudioVis {
         let using_hdri_texture = self.base.using_hdri_texture;
         let video_info = self.base.get_video_info();
         let hdri_info = self.base.get_hdri_info();
-        controls_request.current_fps = Some(self.base.fps_tracker.fps());
+        self.base.add_frame_stats(&mut controls_request);
         let full_output = if self.base.key_handler.show_ui {
             self.base.render_ui(core, |ctx| {
                 ctx.style_mut(|style| {
@@ -355,6 +306,7 @@ impl ShaderManager for AudioVis {
             self.base.export_manager.start_export();
         }
         
+        let render_target = self.base.render_target_view(core, &view);
         let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
@@ -362,7 +314,7 @@ impl ShaderManager for AudioVis {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Main Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: &render_target,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -388,6 +340,7 @@ impl ShaderManager for AudioVis {
             render_pass.set_bind_group(3, &self.base.resolution_uniform.bind_group, &[]);
             render_pass.draw(0..4, 0..1);
         }
+        self.base.present_scaled(core, &mut encoder, &view);
         self.base.handle_render_output(core, &view, full_output, &mut encoder);
         core.queue.submit(Some(encoder.finish()));
         output.present();
@@ -395,11 +348,15 @@ impl ShaderManager for AudioVis {
     }
     
     fn resize(&mut self, core: &Core) {
-        self.base.update_resolution(&core.queue, core.size);
+        self.base.resize_scaled(core);
     }
     
+    fn recreate(&mut self, core: &Core) {
+        self.recreate_keeping_state(core);
+    }
+
     fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
-        if self.base.egui_state.on_window_event(core.window(), event).consumed {
+        if self.base.handle_egui_event(core, event) {
             return true;
         }
         if let WindowEvent::KeyboardInput { event, .. } = event {
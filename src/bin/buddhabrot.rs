@@ -1,4 +1,4 @@
-use cuneus::{Core, ShaderManager, UniformProvider, UniformBinding, RenderKit, ShaderControls, ExportManager};
+use cuneus::{Core, ShaderManager, UniformProvider, UniformBinding, RenderKit, ShaderControls, ExportManager, FrameCapture, PanZoomCamera, join_f64};
 use cuneus::compute::{create_bind_group_layout, BindGroupLayoutType};
 use winit::event::WindowEvent;
 use std::path::PathBuf;
@@ -24,6 +24,9 @@ struct BuddhabrotParams {
     color2_b: f32,            
     sample_density: f32,      
     dithering: f32,           
+    // low parts of the f64 view center, offset_x/offset_y are the high parts
+    offset_lo_x: f32,
+    offset_lo_y: f32,
 }
 
 impl UniformProvider for BuddhabrotParams {
@@ -65,9 +68,37 @@ struct BuddhabrotShader {
     
     // Accumulated rendering flag
     accumulated_rendering: bool,
+    
+    // wheel zoom around the cursor and drag to pan
+    view: PanZoomCamera,
 }
 
 impl BuddhabrotShader {
+    // moves the pan/zoom view to the center and zoom in the params
+    fn sync_view(&mut self) {
+        let params = &self.params_uniform.data;
+        let center = [join_f64(params.offset_x, params.offset_lo_x), join_f64(params.offset_y, params.offset_lo_y)];
+        self.view.set_view(center, params.zoom as f64);
+    }
+    
+    // Turns the accumulated histogram into the output texture
+    fn resolve_output(&self, encoder: &mut wgpu::CommandEncoder, core: &Core) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Render Pass"),
+            timestamp_writes: None,
+        });
+        
+        compute_pass.set_pipeline(&self.compute_pipeline_render);
+        compute_pass.set_bind_group(0, &self.compute_time_uniform.bind_group, &[]);
+        compute_pass.set_bind_group(1, &self.params_uniform.bind_group, &[]);
+        compute_pass.set_bind_group(2, &self.compute_bind_group, &[]);
+        compute_pass.set_bind_group(3, &self.atomic_buffer.bind_group, &[]);
+        
+        let width = core.size.width.div_ceil(16);
+        let height = core.size.height.div_ceil(16);
+        compute_pass.dispatch_workgroups(width, height, 1);
+    }
+
     fn recreate_compute_resources(&mut self, core: &Core) {
         self.output_texture = cuneus::compute::create_output_texture(
             &core.device,
@@ -103,31 +134,58 @@ impl BuddhabrotShader {
         self.accumulated_rendering = false;
     }
     
-    fn capture_frame(&mut self, core: &Core, time: f32) -> Result<Vec<u8>, wgpu::SurfaceError> {
-        let settings = self.base.export_manager.settings();
-        let (capture_texture, output_buffer) = self.base.create_capture_texture(
-            &core.device,
-            settings.width,
-            settings.height
-        );
-        
-        let align = 256;
-        let unpadded_bytes_per_row = settings.width * 4;
-        let padding = (align - unpadded_bytes_per_row % align) % align;
-        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
-        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        
-        let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Capture Encoder"),
+    fn clear_buffers(&mut self, core: &Core) {
+        let encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Clear Buffers Encoder"),
         });
+        let buffer_size = core.size.width * core.size.height * 3;
+        let clear_data = vec![0u32; buffer_size as usize];
         
-        self.base.time_uniform.data.time = time;
-        self.base.time_uniform.update(&core.queue);
+        core.queue.write_buffer(
+            &self.atomic_buffer.buffer,
+            0,
+            bytemuck::cast_slice(&clear_data),
+        );
         
+        core.queue.submit(Some(encoder.finish()));
+
+        self.accumulated_rendering = false;
+    }
+}
+
+impl FrameCapture for BuddhabrotShader {
+    fn render_kit(&mut self) -> &mut RenderKit {
+        &mut self.base
+    }
+
+    fn preset_params(&self) -> Option<Vec<u8>> {
+        Some(self.params_uniform.data.as_bytes().to_vec())
+    }
+
+    fn apply_preset_params(&mut self, core: &Core, params: &[u8]) -> bool {
+        if !self.params_uniform.set_from_bytes(&core.queue, params) {
+            return false;
+        }
+        self.sync_view();
+        true
+    }
+
+    fn source_hash(&self) -> Option<u64> {
+        Some(self.hot_reload.source_hash())
+    }
+
+    fn render_capture(
+        &mut self,
+        _core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        _time: f32,
+        _frame: u32,
+    ) {
         {
             let mut render_pass = cuneus::Renderer::begin_render_pass(
-                &mut encoder,
-                &capture_view,
+                encoder,
+                view,
                 wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                 Some("Capture Pass"),
             );
@@ -137,78 +195,19 @@ impl BuddhabrotShader {
             render_pass.set_bind_group(0, &self.output_texture.bind_group, &[]);
             render_pass.draw(0..4, 0..1);
         }
-        
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture: &capture_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &output_buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(settings.height),
-                },
-            },
-            wgpu::Extent3d {
-                width: settings.width,
-                height: settings.height,
-                depth_or_array_layers: 1,
-            },
-        );
-        
-        core.queue.submit(Some(encoder.finish()));
-        
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
-        
-        core.device.poll(wgpu::Maintain::Wait);
-        rx.recv().unwrap().unwrap();
-        
-        let padded_data = buffer_slice.get_mapped_range().to_vec();
-        let mut unpadded_data = Vec::with_capacity((settings.width * settings.height * 4) as usize);
-        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
-            unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
-        }
-        
-        Ok(unpadded_data)
     }
-    
-    fn handle_export(&mut self, core: &Core) {
-        if let Some((frame, time)) = self.base.export_manager.try_get_next_frame() {
-            if let Ok(data) = self.capture_frame(core, time) {
-                let settings = self.base.export_manager.settings();
-                if let Err(e) = cuneus::save_frame(data, frame, settings) {
-                    eprintln!("Error saving frame: {:?}", e);
-                }
-            }
-        } else {
-            self.base.export_manager.complete_export();
-        }
+
+    // the accumulated Rgba16Float output, unclamped for grading
+    fn float_output(&self) -> Option<&wgpu::Texture> {
+        Some(&self.output_texture.texture)
     }
-    
-    fn clear_buffers(&mut self, core: &Core) {
-        let encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Clear Buffers Encoder"),
-        });
-        let buffer_size = core.size.width * core.size.height * 3;
-        let clear_data = vec![0u32; buffer_size as usize];
-        
-        core.queue.write_buffer(
-            &self.atomic_buffer.buffer,
-            0,
-            bytemuck::cast_slice(&clear_data),
-        );
-        
-        core.queue.submit(Some(encoder.finish()));
 
-        self.accumulated_rendering = false;
+    // tone maps the histogram gathered so far with the export frame's time
+    fn render_float_capture(&mut self, core: &Core, encoder: &mut wgpu::CommandEncoder, time: f32, frame: u32) {
+        self.compute_time_uniform.data.time = time;
+        self.compute_time_uniform.data.frame = frame;
+        self.compute_time_uniform.update(&core.queue);
+        self.resolve_output(encoder, core);
     }
 }
 
@@ -300,6 +299,8 @@ impl ShaderManager for BuddhabrotShader {
                 color2_b: 1.0,
                 sample_density: 0.5,
                 dithering: 0.2,
+                offset_lo_x: 0.0,
+                offset_lo_y: 0.0,
             },
             &params_bind_group_layout,
             0,
@@ -409,7 +410,12 @@ impl ShaderManager for BuddhabrotShader {
             frame_count: 0,
             hot_reload,
             accumulated_rendering: false,
+            view: PanZoomCamera::default(),
         };
+        // screen y grows downwards in the shader
+        result.view.flip_y = false;
+        result.view.set_viewport(core.size);
+        result.sync_view();
         
         result.recreate_compute_resources(core);
         
@@ -455,11 +461,14 @@ impl ShaderManager for BuddhabrotShader {
         if self.base.export_manager.is_exporting() {
             self.handle_export(core);
         }
+        self.handle_screenshot(core);
+        self.handle_preset_request(core);
         self.base.fps_tracker.update();
     }
     
     fn resize(&mut self, core: &Core) {
         println!("Resizing to {:?}", core.size);
+        self.view.set_viewport(core.size);
         self.recreate_compute_resources(core);
     }
     
@@ -478,7 +487,7 @@ impl ShaderManager for BuddhabrotShader {
             &self.base.start_time,
             &core.size
         );
-        controls_request.current_fps = Some(self.base.fps_tracker.fps());
+        self.base.add_frame_stats(&mut controls_request);
         let full_output = if self.base.key_handler.show_ui {
             self.base.render_ui(core, |ctx| {
                 ctx.style_mut(|style| {
@@ -502,7 +511,7 @@ impl ShaderManager for BuddhabrotShader {
                         egui::CollapsingHeader::new("View Controls")
                             .default_open(true)
                             .show(ui, |ui| {
-                                changed |= ui.add(egui::Slider::new(&mut params.zoom, 0.1..=5.0).logarithmic(true).text("Zoom")).changed();
+                                changed |= ui.add(egui::Slider::new(&mut params.zoom, 0.1..=1000.0).logarithmic(true).text("Zoom")).changed();
                                 changed |= ui.add(egui::Slider::new(&mut params.offset_x, -2.0..=1.0).text("X")).changed();
                                 changed |= ui.add(egui::Slider::new(&mut params.offset_y, -1.5..=1.5).text("Y")).changed();
                                 changed |= ui.add(egui::Slider::new(&mut params.rotation, -3.14159..=3.14159).text("rot")).changed();
@@ -578,6 +587,21 @@ impl ShaderManager for BuddhabrotShader {
         self.compute_time_uniform.data.frame = self.frame_count;
         self.compute_time_uniform.update(&core.queue);
         
+        // the sliders win over the mouse when both moved
+        if changed {
+            self.params_uniform.data = params;
+            self.sync_view();
+        }
+        self.view.rotation = -params.rotation;
+        if self.view.update() {
+            // the shader adds the hi/lo center back together, the zoom only needs f32
+            let uniform = self.view.uniform();
+            [params.offset_x, params.offset_y] = uniform.center_hi;
+            [params.offset_lo_x, params.offset_lo_y] = uniform.center_lo;
+            params.zoom = self.view.zoom as f32;
+            changed = true;
+        }
+        
         if changed {
             if !self.accumulated_rendering {
                 self.clear_buffers(core);
@@ -613,22 +637,7 @@ impl ShaderManager for BuddhabrotShader {
             }
         }
         
-        {
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Render Pass"),
-                timestamp_writes: None,
-            });
-            
-            compute_pass.set_pipeline(&self.compute_pipeline_render);
-            compute_pass.set_bind_group(0, &self.compute_time_uniform.bind_group, &[]);
-            compute_pass.set_bind_group(1, &self.params_uniform.bind_group, &[]);
-            compute_pass.set_bind_group(2, &self.compute_bind_group, &[]);
-            compute_pass.set_bind_group(3, &self.atomic_buffer.bind_group, &[]);
-            
-            let width = core.size.width.div_ceil(16);
-            let height = core.size.height.div_ceil(16);
-            compute_pass.dispatch_workgroups(width, height, 1);
-        }
+        self.resolve_output(&mut encoder, core);
         
         {
             let mut render_pass = cuneus::Renderer::begin_render_pass(
@@ -653,8 +662,16 @@ impl ShaderManager for BuddhabrotShader {
         Ok(())
     }
     
+    fn recreate(&mut self, core: &Core) {
+        self.recreate_keeping_state(core);
+    }
+
     fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
-        if self.base.egui_state.on_window_event(core.window(), event).consumed {
+        if self.base.handle_egui_event(core, event) {
+            return true;
+        }
+        
+        if self.view.handle_event(event) {
             return true;
         }
         
@@ -668,7 +685,12 @@ impl ShaderManager for BuddhabrotShader {
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
-    let (app, event_loop) = cuneus::ShaderApp::new("Buddhabrot", 800, 600);
+    let (mut app, event_loop) = cuneus::ShaderApp::new("Buddhabrot", 800, 600);
+    // samples keep accumulating while time is paused
+    app.set_frame_pacing(cuneus::FramePacing {
+        idle_when_paused: false,
+        ..Default::default()
+    });
     
     app.run(event_loop, |core| {
         BuddhabrotShader::init(core)
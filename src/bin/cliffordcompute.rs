@@ -1,4 +1,4 @@
-use cuneus::{Core, ShaderManager, UniformProvider, UniformBinding, RenderKit, ShaderControls, ExportManager};
+use cuneus::{Core, ShaderManager, UniformProvider, UniformBinding, RenderKit, ShaderControls, ExportManager, FrameCapture};
 use cuneus::compute::{create_bind_group_layout, BindGroupLayoutType};
 use winit::event::WindowEvent;
 use std::path::PathBuf;
@@ -94,27 +94,27 @@ impl CliffordShader {
             ],
         });
     }
-    fn capture_frame(&mut self, core: &Core, time: f32) -> Result<Vec<u8>, wgpu::SurfaceError> {
-        let settings = self.base.export_manager.settings();
-        let (capture_texture, output_buffer) = self.base.create_capture_texture(
-            &core.device,
-            settings.width,
-            settings.height
-        );
-        let align = 256;
-        let unpadded_bytes_per_row = settings.width * 4;
-        let padding = (align - unpadded_bytes_per_row % align) % align;
-        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
-        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Capture Encoder"),
-        });
-        self.base.time_uniform.data.time = time;
-        self.base.time_uniform.update(&core.queue);
+}
+
+impl FrameCapture for CliffordShader {
+    fn render_kit(&mut self) -> &mut RenderKit {
+        &mut self.base
+    }
+
+    cuneus::impl_frame_capture_presets!(params_uniform, hot_reload);
+
+    fn render_capture(
+        &mut self,
+        _core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        _time: f32,
+        _frame: u32,
+    ) {
         {
             let mut render_pass = cuneus::Renderer::begin_render_pass(
-                &mut encoder,
-                &capture_view,
+                encoder,
+                view,
                 wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                 Some("Capture Pass"),
             );
@@ -123,54 +123,6 @@ impl CliffordShader {
             render_pass.set_bind_group(0, &self.output_texture.bind_group, &[]);
             render_pass.draw(0..4, 0..1);
         }
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture: &capture_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &output_buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(settings.height),
-                },
-            },
-            wgpu::Extent3d {
-                width: settings.width,
-                height: settings.height,
-                depth_or_array_layers: 1,
-            },
-        );
-        
-        core.queue.submit(Some(encoder.finish()));
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
-        core.device.poll(wgpu::Maintain::Wait);
-        rx.recv().unwrap().unwrap();
-        let padded_data = buffer_slice.get_mapped_range().to_vec();
-        let mut unpadded_data = Vec::with_capacity((settings.width * settings.height * 4) as usize);
-        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
-            unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
-        }
-        Ok(unpadded_data)
-    }
-    fn handle_export(&mut self, core: &Core) {
-        if let Some((frame, time)) = self.base.export_manager.try_get_next_frame() {
-            if let Ok(data) = self.capture_frame(core, time) {
-                let settings = self.base.export_manager.settings();
-                if let Err(e) = cuneus::save_frame(data, frame, settings) {
-                    eprintln!("Error saving frame: {:?}", e);
-                }
-            }
-        } else {
-            self.base.export_manager.complete_export();
-        }
     }
 }
 
@@ -408,6 +360,8 @@ impl ShaderManager for CliffordShader {
         if self.base.export_manager.is_exporting() {
             self.handle_export(core);
         }
+        self.handle_screenshot(core);
+        self.handle_preset_request(core);
         self.base.fps_tracker.update();
     }
     
@@ -431,7 +385,7 @@ impl ShaderManager for CliffordShader {
             &self.base.start_time,
             &core.size
         );
-        controls_request.current_fps = Some(self.base.fps_tracker.fps());
+        self.base.add_frame_stats(&mut controls_request);
         let full_output = if self.base.key_handler.show_ui {
             self.base.render_ui(core, |ctx| {
                 ctx.style_mut(|style| {
@@ -618,8 +572,12 @@ impl ShaderManager for CliffordShader {
         Ok(())
     }
     
+    fn recreate(&mut self, core: &Core) {
+        self.recreate_keeping_state(core);
+    }
+
     fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
-        if self.base.egui_state.on_window_event(core.window(), event).consumed {
+        if self.base.handle_egui_event(core, event) {
             return true;
         }
         
@@ -1,4 +1,4 @@
-use cuneus::{Core, ShaderApp, ShaderManager, RenderKit, ShaderControls};
+use cuneus::{Core, ShaderApp, ShaderManager, RenderKit, ShaderControls, GpuProfiler, GlyphMode, ExportManager, FrameCapture};
 use cuneus::compute::{ComputeShaderConfig, COMPUTE_TEXTURE_FORMAT_RGBA16};
 use winit::event::*;
 use std::path::PathBuf;
@@ -7,6 +7,37 @@ struct ComputeExample {
     base: RenderKit,
 }
 
+impl FrameCapture for ComputeExample {
+    fn render_kit(&mut self) -> &mut RenderKit {
+        &mut self.base
+    }
+
+    fn source_hash(&self) -> Option<u64> {
+        self.base.compute_shader.as_ref()?.hot_reload.as_ref().map(|hot_reload| hot_reload.source_hash())
+    }
+
+    fn render_capture(
+        &mut self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        _time: f32,
+        _frame: u32,
+    ) {
+        self.base.dispatch_compute_shader(encoder, core);
+        self.base.present_compute_output(core, encoder, view);
+    }
+
+    // the Rgba16Float compute output, for 16-bit PNG and EXR exports
+    fn float_output(&self) -> Option<&wgpu::Texture> {
+        self.base.compute_shader.as_ref().map(|compute| &compute.output_texture.texture)
+    }
+
+    fn render_float_capture(&mut self, core: &Core, encoder: &mut wgpu::CommandEncoder, _time: f32, _frame: u32) {
+        self.base.dispatch_compute_shader(encoder, core);
+    }
+}
+
 impl ShaderManager for ComputeExample {
     fn init(core: &Core) -> Self {
         let texture_bind_group_layout = core.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -37,6 +68,8 @@ impl ShaderManager for ComputeExample {
             &[&texture_bind_group_layout],
             None,
         );
+        // present_compute_output upscales, so the render scale controls can be shown
+        base.render_scale_enabled = true;
         
         let mouse_bind_group_layout = core.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[wgpu::BindGroupLayoutEntry {
@@ -68,6 +101,7 @@ impl ShaderManager for ComputeExample {
             workgroup_count: None,  // Auto-determine from texture size
             dispatch_once: false,   // Run every frame
             storage_texture_format: COMPUTE_TEXTURE_FORMAT_RGBA16,
+            storage_texture_access: wgpu::StorageTextureAccess::WriteOnly,
             enable_atomic_buffer: false,  // Not needed for this simple shader
             atomic_buffer_multiples: 4,
             entry_points: vec!["main".to_string()],  // Single entry point
@@ -76,6 +110,13 @@ impl ShaderManager for ComputeExample {
             label: "Basic Compute".to_string(),
             mouse_bind_group_layout: Some(mouse_bind_group_layout),
             enable_fonts: true,
+            font_glyph_mode: GlyphMode::Coverage,  // compute_basic.wgsl reads plain coverage alpha
+            enable_indirect_dispatch: false,  // Workgroup counts come from the window size
+            indirect_entry_points: Vec::new(),
+            indirect_initial_count: [1, 1, 1],
+            stages: Vec::new(),  // Single pass, so no per-stage setup
+            textures: Vec::new(),
+            buffers: Vec::new(),
         };
         
         // Create compute shader with our backend
@@ -112,18 +153,23 @@ impl ShaderManager for ComputeExample {
     }
 
     fn update(&mut self, core: &Core) {
+        if self.base.export_manager.is_exporting() {
+            self.handle_export(core);
+        }
+        self.handle_screenshot(core);
+        self.handle_preset_request(core);
         // Update compute shader time
         let current_time = self.base.controls.get_time(&self.base.start_time);
         let delta = 1.0/60.0; // Approximate delta time
         self.base.update_compute_shader_time(current_time, delta, &core.queue);
         self.base.update_mouse_uniform(&core.queue);
         self.base.fps_tracker.update();
+        // Dynamic render scale may shrink/grow the compute output
+        self.base.update_render_scale(core);
     }
     fn resize(&mut self, core: &Core) {
-        // Update resolution uniform
-        self.base.update_resolution(&core.queue, core.size);
-        // Resize compute shader resources
-        self.base.resize_compute_shader(core);
+        // Update resolution uniform and compute shader resources at the scaled size
+        self.base.resize_scaled(core);
     }
     fn render(&mut self, core: &Core) -> Result<(), wgpu::SurfaceError> {
         let output = core.surface.get_current_texture()?;
@@ -132,7 +178,10 @@ impl ShaderManager for ComputeExample {
             &self.base.start_time,
             &core.size
         );
-        controls_request.current_fps = Some(self.base.fps_tracker.fps());
+        self.base.add_frame_stats(&mut controls_request);
+        let mut profiler_request = self.base.gpu_profiler.get_ui_request();
+        let mut export_request = self.base.export_manager.get_ui_request();
+        let mut should_start_export = false;
         let mouse_pos = self.base.mouse_tracker.uniform.position;
         let raw_pos = self.base.mouse_tracker.raw_position;
         let mouse_buttons = self.base.mouse_tracker.uniform.buttons[0];
@@ -148,6 +197,8 @@ impl ShaderManager for ComputeExample {
                         // Time controls (play/pause/reset)
                         ui.heading("Controls");
                         ShaderControls::render_controls_widget(ui, &mut controls_request);
+                        GpuProfiler::render_profiler_ui_widget(ui, &mut profiler_request);
+                        should_start_export = ExportManager::render_export_ui_widget(ui, &mut export_request);
                         
                         ui.separator();
                         ui.heading("Mouse Debug");
@@ -170,6 +221,11 @@ impl ShaderManager for ComputeExample {
         
         // Apply control requests (play/pause/etc)
         self.base.apply_control_request(controls_request);
+        self.base.gpu_profiler.apply_ui_request(profiler_request);
+        self.base.export_manager.apply_ui_request(export_request);
+        if should_start_export {
+            self.base.export_manager.start_export();
+        }
         
         // Create command encoder
         let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -179,30 +235,8 @@ impl ShaderManager for ComputeExample {
         // Run compute shader
         self.base.dispatch_compute_shader(&mut encoder, core);
         
-        // Render compute output to screen
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Main Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-            // Draw the compute shader output
-            if let Some(compute_texture) = self.base.get_compute_output_texture() {
-                render_pass.set_pipeline(&self.base.renderer.render_pipeline);
-                render_pass.set_vertex_buffer(0, self.base.renderer.vertex_buffer.slice(..));
-                render_pass.set_bind_group(0, &compute_texture.bind_group, &[]);
-                render_pass.draw(0..4, 0..1);
-            }
-        }
+        // Render compute output to screen, upscaled if a render scale is set
+        self.base.present_compute_output(core, &mut encoder, &view);
         self.base.handle_render_output(core, &view, full_output, &mut encoder);
         // Submit work and present
         core.queue.submit(Some(encoder.finish()));
@@ -210,9 +244,13 @@ impl ShaderManager for ComputeExample {
         Ok(())
     }
 
+    fn recreate(&mut self, core: &Core) {
+        self.recreate_keeping_state(core);
+    }
+
     fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
         // Handle egui events
-        let ui_handled = self.base.egui_state.on_window_event(core.window(), event).consumed;
+        let ui_handled = self.base.handle_egui_event(core, event);
         
         // Handle mouse input for shader if UI didn't consume it
         if self.base.handle_mouse_input(core, event, ui_handled) {
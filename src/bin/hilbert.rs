@@ -1,4 +1,4 @@
-use cuneus::{Core,Renderer,ShaderApp, ShaderManager, UniformProvider, UniformBinding, RenderKit,ExportManager,ShaderHotReload,ShaderControls};
+use cuneus::{Core,Renderer,ShaderApp, ShaderManager, UniformProvider, UniformBinding, RenderKit,ExportManager,ShaderHotReload,ShaderControls, FrameCapture, PanZoomCamera, join_f64};
 use winit::event::*;
 use std::path::PathBuf;
 #[repr(C)]
@@ -7,7 +7,8 @@ struct ShaderParams {
     // Hilbert curve parameters
     iterations: u32,
     num_rays: f32,
-    _pad1: [f32; 2],
+    // pan/zoom view, see PanZoomUniform
+    center_hi: [f32; 2],
     
     // Animation and scaling stuff
     scale: f32,
@@ -18,6 +19,10 @@ struct ShaderParams {
     // Color parameters
     color_offset: [f32; 3],
     flanc: f32, 
+    
+    center_lo: [f32; 2],
+    view_scale: f32,
+    _pad2: f32,
 }
 impl UniformProvider for ShaderParams {
     fn as_bytes(&self) -> &[u8] {
@@ -32,6 +37,22 @@ struct Shader {
     time_bind_group_layout: wgpu::BindGroupLayout,    
     resolution_bind_group_layout: wgpu::BindGroupLayout,
     params_bind_group_layout: wgpu::BindGroupLayout,
+    // wheel/pinch zoom around the cursor, drag to pan
+    view: PanZoomCamera,
+}
+impl Shader {
+    fn sync_view(&mut self) {
+        let params = &self.params_uniform.data;
+        let center = [join_f64(params.center_hi[0], params.center_lo[0]), join_f64(params.center_hi[1], params.center_lo[1])];
+        self.view.set_view(center, 1.0 / params.view_scale as f64);
+    }
+    fn upload_view(&mut self, core: &Core) {
+        let view = self.view.uniform();
+        self.params_uniform.data.center_hi = view.center_hi;
+        self.params_uniform.data.center_lo = view.center_lo;
+        self.params_uniform.data.view_scale = view.scale;
+        self.params_uniform.update(&core.queue);
+    }
 }
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
@@ -40,31 +61,40 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Shader::init(core)
     })
 }
-impl Shader {
-    fn capture_frame(&mut self, core: &Core, time: f32) -> Result<Vec<u8>, wgpu::SurfaceError> {
-        let settings = self.base.export_manager.settings();
-        let (capture_texture, output_buffer) = self.base.create_capture_texture(
-            &core.device,
-            settings.width,
-            settings.height
-        );
-        let align = 256;
-        let unpadded_bytes_per_row = settings.width * 4;
-        let padding = (align - unpadded_bytes_per_row % align) % align;
-        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
-        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Capture Encoder"),
-        });
-        self.base.time_uniform.data.time = time;
-        self.base.time_uniform.update(&core.queue);
-        self.base.resolution_uniform.data.dimensions = [settings.width as f32, settings.height as f32];
-        self.base.resolution_uniform.update(&core.queue);
+impl FrameCapture for Shader {
+    fn render_kit(&mut self) -> &mut RenderKit {
+        &mut self.base
+    }
+
+    fn preset_params(&self) -> Option<Vec<u8>> {
+        Some(self.params_uniform.data.as_bytes().to_vec())
+    }
+
+    fn apply_preset_params(&mut self, core: &Core, params: &[u8]) -> bool {
+        if !self.params_uniform.set_from_bytes(&core.queue, params) {
+            return false;
+        }
+        self.sync_view();
+        true
+    }
+
+    fn source_hash(&self) -> Option<u64> {
+        Some(self.hot_reload.source_hash())
+    }
+
+    fn render_capture(
+        &mut self,
+        _core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        _time: f32,
+        _frame: u32,
+    ) {
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Capture Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &capture_view,
+                    view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -82,55 +112,9 @@ impl Shader {
             render_pass.set_bind_group(2, &self.params_uniform.bind_group, &[]);
             render_pass.draw(0..4, 0..1);
         }
-        encoder.copy_texture_to_buffer( 
-            wgpu::TexelCopyTextureInfo {
-                texture: &capture_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &output_buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(settings.height),
-                },
-            },
-            wgpu::Extent3d {
-                width: settings.width,
-                height: settings.height,
-                depth_or_array_layers: 1,
-            },
-        );
-        core.queue.submit(Some(encoder.finish()));
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
-        core.device.poll(wgpu::Maintain::Wait);
-        rx.recv().unwrap().unwrap();
-        let padded_data = buffer_slice.get_mapped_range().to_vec();
-        let mut unpadded_data = Vec::with_capacity((settings.width * settings.height * 4) as usize);
-        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
-            unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
-        }
-        Ok(unpadded_data)
-    }
-    fn handle_export(&mut self, core: &Core) {
-        if let Some((frame, time)) = self.base.export_manager.try_get_next_frame() {
-            if let Ok(data) = self.capture_frame(core, time) {
-                let settings = self.base.export_manager.settings();
-                if let Err(e) = cuneus::save_frame(data, frame, settings) {
-                    eprintln!("Error saving frame: {:?}", e);
-                }
-            }
-        } else {
-            self.base.export_manager.complete_export();
-        }
     }
 }
+
 impl ShaderManager for Shader {
     fn init(core: &Core) -> Self {
         let time_bind_group_layout = core.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -180,7 +164,7 @@ impl ShaderManager for Shader {
             ShaderParams {
                 iterations: 2, 
                 num_rays: 6.0,   
-                _pad1: [0.0; 2],
+                center_hi: [0.0; 2],
                 
                 scale: 2.0,          
                 time_scale: 1.5,    
@@ -189,6 +173,10 @@ impl ShaderManager for Shader {
                 
                 color_offset: [0.0, 0.0, 0.0],
                 flanc: 1.0,
+                
+                center_lo: [0.0; 2],
+                view_scale: 1.0,
+                _pad2: 0.0,
             },
             &params_bind_group_layout,
             0,
@@ -214,13 +202,14 @@ impl ShaderManager for Shader {
             PathBuf::from("shaders/hilbert.wgsl"),
         ];
 
-        let base = RenderKit::new(
+        let mut base = RenderKit::new(
             core,
             include_str!("../../shaders/vertex.wgsl"),
             include_str!("../../shaders/hilbert.wgsl"),
             &bind_group_layouts,
             None,
         );
+        base.render_scale_enabled = true;
 
         let hot_reload = ShaderHotReload::new(
             core.device.clone(),
@@ -229,6 +218,8 @@ impl ShaderManager for Shader {
             fs_module,
         ).expect("Failed to initialize hot reload");
 
+        let mut view = PanZoomCamera::default();
+        view.set_viewport(core.size);
         Self {
             base,
             params_uniform,
@@ -236,6 +227,7 @@ impl ShaderManager for Shader {
             time_bind_group_layout,
             resolution_bind_group_layout,
             params_bind_group_layout,
+            view,
         }
     }
 
@@ -264,7 +256,13 @@ impl ShaderManager for Shader {
         if self.base.export_manager.is_exporting() {
             self.handle_export(core);
         }
+        self.handle_screenshot(core);
+        self.handle_preset_request(core);
         self.base.fps_tracker.update();
+        self.base.update_render_scale(core);
+        if self.view.update() {
+            self.upload_view(core);
+        }
     }
     fn render(&mut self, core: &Core) -> Result<(), wgpu::SurfaceError> {
         let output = core.surface.get_current_texture()?;
@@ -277,7 +275,8 @@ impl ShaderManager for Shader {
             &self.base.start_time,
             &core.size
         );
-        controls_request.current_fps = Some(self.base.fps_tracker.fps());
+        self.base.add_frame_stats(&mut controls_request);
+        let mut reset_view = false;
         let full_output = if self.base.key_handler.show_ui {
             self.base.render_ui(core, |ctx| {
                 ctx.style_mut(|style| {
@@ -303,6 +302,11 @@ impl ShaderManager for Shader {
                             .text("flanc")).changed();
                     });
         
+                    ui.collapsing("View", |ui| {
+                        ui.label("Wheel/pinch - Zoom, Drag - Pan");
+                        reset_view = ui.button("Reset View").clicked();
+                    });
+        
                     ui.collapsing("Color Settings", |ui| {
                         changed |= ui.color_edit_button_rgb(&mut params.color_offset).changed();
                         ui.label("Color Offset");
@@ -326,11 +330,16 @@ impl ShaderManager for Shader {
             self.params_uniform.data = params;
             self.params_uniform.update(&core.queue);
         }
+        if reset_view {
+            self.view.set_view([0.0, 0.0], 1.0);
+            self.upload_view(core);
+        }
 
         if should_start_export {
             self.base.export_manager.start_export();
         }
 
+        let render_target = self.base.render_target_view(core, &view);
         let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
@@ -339,7 +348,7 @@ impl ShaderManager for Shader {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Main Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: &render_target,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -359,6 +368,7 @@ impl ShaderManager for Shader {
             render_pass.draw(0..4, 0..1);
         }
 
+        self.base.present_scaled(core, &mut encoder, &view);
         self.base.handle_render_output(core, &view, full_output, &mut encoder);
         core.queue.submit(Some(encoder.finish()));
         output.present();
@@ -366,10 +376,18 @@ impl ShaderManager for Shader {
         Ok(())
     }
     fn resize(&mut self, core: &Core) {
-        self.base.update_resolution(&core.queue, core.size);
+        self.base.resize_scaled(core);
+        self.view.set_viewport(core.size);
+    }
+    fn recreate(&mut self, core: &Core) {
+        self.recreate_keeping_state(core);
     }
+
     fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
-        if self.base.egui_state.on_window_event(core.window(), event).consumed {
+        if self.base.handle_egui_event(core, event) {
+            return true;
+        }
+        if self.view.handle_event(event) {
             return true;
         }
         if let WindowEvent::KeyboardInput { event, .. } = event {
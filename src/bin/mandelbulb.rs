@@ -1,11 +1,22 @@
 use cuneus::prelude::*;
+use cuneus::{CameraController, CameraPose, OrbitCamera, PanZoomCamera, join_f64};
 use winit::event::WindowEvent;
 use std::path::PathBuf;
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct MandelbulbParams {
-    mouse_x: f32,
-    mouse_y: f32,
+    camera_pos_x: f32,
+    camera_pos_y: f32,
+    camera_pos_z: f32,
+    camera_target_x: f32,
+    camera_target_y: f32,
+    camera_target_z: f32,
+    // lens zoom on the image plane, see PanZoomUniform
+    lens_center_hi_x: f32,
+    lens_center_hi_y: f32,
+    lens_center_lo_x: f32,
+    lens_center_lo_y: f32,
+    lens_scale: f32,
     power: f32,
     max_bounces: u32,
     samples_per_pixel: u32,
@@ -35,10 +46,8 @@ struct MandelbulbParams {
     manual_rotation_x: f32,
     manual_rotation_y: f32,
     manual_rotation_z: f32,
-    use_mouse_rotation: u32,
     
     gamma: f32,
-    zoom: f32,
     
     background_r: f32,
     background_g: f32,
@@ -60,69 +69,6 @@ impl UniformProvider for MandelbulbParams {
     }
 }
 
-struct MouseRotation {
-    last_mouse_x: f32,
-    last_mouse_y: f32,
-    mouse_initialized: bool,
-    mouse_enabled: bool,
-    rotation_x: f32,
-    rotation_y: f32,
-    mouse_sensitivity: f32,
-}
-
-impl Default for MouseRotation {
-    fn default() -> Self {
-        Self {
-            last_mouse_x: 0.0,
-            last_mouse_y: 0.0,
-            mouse_initialized: false,
-            mouse_enabled: true,
-            rotation_x: 0.0,
-            rotation_y: 0.0,
-            mouse_sensitivity: 0.005,
-        }
-    }
-}
-
-impl MouseRotation {
-    fn handle_mouse_movement(&mut self, x: f32, y: f32) -> bool {
-        if !self.mouse_enabled {
-            return false;
-        }
-        
-        if !self.mouse_initialized {
-            self.last_mouse_x = x;
-            self.last_mouse_y = y;
-            self.mouse_initialized = true;
-            return false;
-        }
-        
-        let dx = x - self.last_mouse_x;
-        let dy = y - self.last_mouse_y;
-        
-        self.last_mouse_x = x;
-        self.last_mouse_y = y;
-        
-        self.rotation_y += dx * self.mouse_sensitivity;
-        self.rotation_x += dy * self.mouse_sensitivity;
-        
-        self.rotation_x = self.rotation_x.clamp(-std::f32::consts::PI, std::f32::consts::PI);
-        
-        true
-    }
-    
-    fn toggle_mouse_rotation(&mut self) {
-        self.mouse_enabled = !self.mouse_enabled;
-        self.mouse_initialized = false;
-    }
-    
-    fn get_normalized_position(&self, window_size: &winit::dpi::PhysicalSize<u32>) -> (f32, f32) {
-        let norm_x = (self.last_mouse_x / window_size.width as f32).clamp(0.0, 1.0);
-        let norm_y = (self.last_mouse_y / window_size.height as f32).clamp(0.0, 1.0);
-        (norm_x, norm_y)
-    }
-}
-
 struct MandelbulbShader {
     base: RenderKit,
     params_uniform: UniformBinding<MandelbulbParams>,
@@ -147,15 +93,28 @@ struct MandelbulbShader {
     
     should_reset_accumulation: bool,
     
-    mouse_rotation: MouseRotation,
+    camera: OrbitCamera,
+    // the wheel zooms into the image around the cursor instead of moving the camera
+    lens: PanZoomCamera,
 }
 
 impl MandelbulbShader {
+    fn sync_lens(&mut self) {
+        let params = &self.params_uniform.data;
+        let center = [
+            join_f64(params.lens_center_hi_x, params.lens_center_lo_x),
+            join_f64(params.lens_center_hi_y, params.lens_center_lo_y),
+        ];
+        self.lens.set_view(center, 1.0 / params.lens_scale as f64);
+    }
+    
+    // output and accumulation buffer at the render size, present_output upscales to the window
     fn recreate_compute_resources(&mut self, core: &Core) {
+        let size = self.base.render_size(core);
         self.output_texture = cuneus::compute::create_output_texture(
             &core.device,
-            core.size.width,
-            core.size.height,
+            size.width,
+            size.height,
             wgpu::TextureFormat::Rgba16Float,
             &self.base.texture_bind_group_layout,
             wgpu::AddressMode::ClampToEdge,
@@ -163,7 +122,7 @@ impl MandelbulbShader {
             "Mandelbulb Output Texture",
         );
 
-        let buffer_size = core.size.width * core.size.height * 3;
+        let buffer_size = size.width * size.height * 3;
         
         self.atomic_buffer = cuneus::AtomicBuffer::new(
             &core.device,
@@ -187,31 +146,65 @@ impl MandelbulbShader {
         self.should_reset_accumulation = true;
     }
     
-    fn capture_frame(&mut self, core: &Core, time: f32) -> Result<Vec<u8>, wgpu::SurfaceError> {
-        let settings = self.base.export_manager.settings();
-        let (capture_texture, output_buffer) = self.base.create_capture_texture(
-            &core.device,
-            settings.width,
-            settings.height
-        );
-        
-        let align = 256;
-        let unpadded_bytes_per_row = settings.width * 4;
-        let padding = (align - unpadded_bytes_per_row % align) % align;
-        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
-        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        
-        let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Capture Encoder"),
-        });
+    fn clear_atomic_buffer(&mut self, core: &Core) {
+        let size = self.output_texture.texture.size();
+        let buffer_size = size.width * size.height * 3;
+        let clear_data = vec![0u32; buffer_size as usize];
         
-        self.base.time_uniform.data.time = time;
-        self.base.time_uniform.update(&core.queue);
+        core.queue.write_buffer(
+            &self.atomic_buffer.buffer,
+            0,
+            bytemuck::cast_slice(&clear_data),
+        );
         
+        self.should_reset_accumulation = false;
+        self.frame_count = 0;
+    }
+}
+
+impl FrameCapture for MandelbulbShader {
+    fn render_kit(&mut self) -> &mut RenderKit {
+        &mut self.base
+    }
+
+    fn preset_params(&self) -> Option<Vec<u8>> {
+        Some(self.params_uniform.data.as_bytes().to_vec())
+    }
+
+    fn apply_preset_params(&mut self, core: &Core, params: &[u8]) -> bool {
+        if !self.params_uniform.set_from_bytes(&core.queue, params) {
+            return false;
+        }
+        self.sync_lens();
+        true
+    }
+
+    fn source_hash(&self) -> Option<u64> {
+        Some(self.hot_reload.source_hash())
+    }
+
+    fn capture_metadata(&self) -> Vec<(String, String)> {
+        vec![self.camera.pose().to_metadata()]
+    }
+
+    fn apply_preset_metadata(&mut self, _core: &Core, metadata: &cuneus::FrameMetadata) {
+        if let Some(pose) = CameraPose::from_metadata(metadata) {
+            self.camera.set_pose(pose);
+        }
+    }
+
+    fn render_capture(
+        &mut self,
+        _core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        _time: f32,
+        _frame: u32,
+    ) {
         {
             let mut render_pass = cuneus::Renderer::begin_render_pass(
-                &mut encoder,
-                &capture_view,
+                encoder,
+                view,
                 wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                 Some("Capture Pass"),
             );
@@ -221,74 +214,6 @@ impl MandelbulbShader {
             render_pass.set_bind_group(0, &self.output_texture.bind_group, &[]);
             render_pass.draw(0..4, 0..1);
         }
-        
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture: &capture_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &output_buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(settings.height),
-                },
-            },
-            wgpu::Extent3d {
-                width: settings.width,
-                height: settings.height,
-                depth_or_array_layers: 1,
-            },
-        );
-        
-        core.queue.submit(Some(encoder.finish()));
-        
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
-        
-        core.device.poll(wgpu::Maintain::Wait);
-        rx.recv().unwrap().unwrap();
-        
-        let padded_data = buffer_slice.get_mapped_range().to_vec();
-        let mut unpadded_data = Vec::with_capacity((settings.width * settings.height * 4) as usize);
-        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
-            unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
-        }
-        
-        Ok(unpadded_data)
-    }
-    
-    fn handle_export(&mut self, core: &Core) {
-        if let Some((frame, time)) = self.base.export_manager.try_get_next_frame() {
-            if let Ok(data) = self.capture_frame(core, time) {
-                let settings = self.base.export_manager.settings();
-                if let Err(e) = cuneus::save_frame(data, frame, settings) {
-                    eprintln!("Error saving frame: {:?}", e);
-                }
-            }
-        } else {
-            self.base.export_manager.complete_export();
-        }
-    }
-    
-    fn clear_atomic_buffer(&mut self, core: &Core) {
-        let buffer_size = core.size.width * core.size.height * 3;
-        let clear_data = vec![0u32; buffer_size as usize];
-        
-        core.queue.write_buffer(
-            &self.atomic_buffer.buffer,
-            0,
-            bytemuck::cast_slice(&clear_data),
-        );
-        
-        self.should_reset_accumulation = false;
-        self.frame_count = 0;
     }
 }
 
@@ -362,8 +287,17 @@ impl ShaderManager for MandelbulbShader {
             &core.device,
             "Mandelbulb Params",
             MandelbulbParams {
-                mouse_x: 0.5,
-                mouse_y: 0.5,
+                camera_pos_x: 5.1,
+                camera_pos_y: 1.0,
+                camera_pos_z: 2.0,
+                camera_target_x: -0.7,
+                camera_target_y: -0.25,
+                camera_target_z: -0.3,
+                lens_center_hi_x: 0.0,
+                lens_center_hi_y: 0.0,
+                lens_center_lo_x: 0.0,
+                lens_center_lo_y: 0.0,
+                lens_scale: 1.0,
                 power: 8.0,
                 max_bounces: 6,
                 samples_per_pixel: 2,
@@ -385,10 +319,8 @@ impl ShaderManager for MandelbulbShader {
                 manual_rotation_x: 0.0,
                 manual_rotation_y: 0.0,
                 manual_rotation_z: 0.0,
-                use_mouse_rotation: 1,
                 
                 gamma: 1.1,
-                zoom: 1.0,
                 
                 background_r: 0.1, background_g: 0.1, background_b: 0.15,
                 sun_color_r: 8.10, sun_color_g: 6.00, sun_color_b: 4.20,
@@ -429,13 +361,14 @@ impl ShaderManager for MandelbulbShader {
             "main",
         ).expect("Failed to initialize hot reload");
         
-        let base = RenderKit::new(
+        let mut base = RenderKit::new(
             core,
             include_str!("../../shaders/vertex.wgsl"),
             include_str!("../../shaders/blit.wgsl"),
             &[&texture_bind_group_layout],
             None,
         );
+        base.render_scale_enabled = true;
         
         let output_texture = cuneus::compute::create_output_texture(
             &core.device,
@@ -480,6 +413,18 @@ impl ShaderManager for MandelbulbShader {
             label: Some("Mandelbulb Compute Bind Group"),
         });
         
+        let mut camera = OrbitCamera::default();
+        camera.set_pose(CameraPose {
+            position: [5.1, 1.0, 2.0],
+            target: [-0.7, -0.25, -0.3],
+            ..Default::default()
+        });
+        
+        let mut lens = PanZoomCamera::default();
+        // screen y grows downwards in the shader
+        lens.flip_y = false;
+        lens.set_viewport(core.size);
+        
         let mut result = Self {
             base,
             params_uniform,
@@ -495,7 +440,8 @@ impl ShaderManager for MandelbulbShader {
             frame_count: 0,
             hot_reload,
             should_reset_accumulation: true,
-            mouse_rotation: MouseRotation::default(),
+            camera,
+            lens,
         };
         
         result.recreate_compute_resources(core);
@@ -533,12 +479,39 @@ impl ShaderManager for MandelbulbShader {
         if self.base.export_manager.is_exporting() {
             self.handle_export(core);
         }
+        self.handle_screenshot(core);
+        self.handle_preset_request(core);
+        
+        if self.base.update_render_scale(core) {
+            self.recreate_compute_resources(core);
+        }
+        
+        if self.camera.update(&self.base.key_handler.input) {
+            let pose = self.camera.pose();
+            let params = &mut self.params_uniform.data;
+            [params.camera_pos_x, params.camera_pos_y, params.camera_pos_z] = pose.position;
+            [params.camera_target_x, params.camera_target_y, params.camera_target_z] = pose.target;
+            self.params_uniform.update(&core.queue);
+            self.should_reset_accumulation = true;
+        }
+        
+        if self.lens.update() {
+            let lens = self.lens.uniform();
+            let params = &mut self.params_uniform.data;
+            [params.lens_center_hi_x, params.lens_center_hi_y] = lens.center_hi;
+            [params.lens_center_lo_x, params.lens_center_lo_y] = lens.center_lo;
+            params.lens_scale = lens.scale;
+            self.params_uniform.update(&core.queue);
+            self.should_reset_accumulation = true;
+        }
         
         self.base.fps_tracker.update();
     }
     
     fn resize(&mut self, core: &Core) {
         println!("Resizing to {:?}", core.size);
+        self.lens.set_viewport(core.size);
+        self.base.resize_scaled(core);
         self.recreate_compute_resources(core);
     }
     
@@ -557,7 +530,7 @@ impl ShaderManager for MandelbulbShader {
             &self.base.start_time,
             &core.size
         );
-        controls_request.current_fps = Some(self.base.fps_tracker.fps());
+        self.base.add_frame_stats(&mut controls_request);
         
         let current_fps = self.base.fps_tracker.fps();
         
@@ -573,32 +546,22 @@ impl ShaderManager for MandelbulbShader {
                     .default_width(350.0)
                     .show(ctx, |ui| {
                         ui.label("Mouse Controls:");
-                        ui.label("Left Click + Drag - Rotate bulb");
-                        ui.label("Right: - Toggle mouse");
+                        ui.label("Left Drag - Orbit");
+                        ui.label("Right Drag - Pan");
+                        ui.label("Wheel - Zoom at cursor");
                         ui.separator();
                         
                         egui::CollapsingHeader::new("Camera&View")
                             .default_open(true)
                             .show(ui, |ui| {
-                                changed |= ui.add(egui::Slider::new(&mut params.zoom, 0.1..=5.0).text("Zoom")).changed();
                                 changed |= ui.add(egui::Slider::new(&mut params.focal_length, 2.0..=20.0).text("Focal Length")).changed();
                                 changed |= ui.add(egui::Slider::new(&mut params.dof_strength, 0.0..=1.0).text("DoF")).changed();
                                 
                                 ui.separator();
                                 ui.label("Rotation:");
-                                let mut use_mouse = params.use_mouse_rotation > 0;
-                                if ui.checkbox(&mut use_mouse, "Use Mouse Rotation").changed() {
-                                    params.use_mouse_rotation = if use_mouse { 1 } else { 0 };
-                                    changed = true;
-                                    self.should_reset_accumulation = true;
-                                }
-                                
-                                if !use_mouse {
-                                    ui.label("Manual");
-                                    changed |= ui.add(egui::Slider::new(&mut params.manual_rotation_x, -std::f32::consts::PI..=std::f32::consts::PI).text("X")).changed();
-                                    changed |= ui.add(egui::Slider::new(&mut params.manual_rotation_y, -std::f32::consts::PI..=std::f32::consts::PI).text("Y")).changed();
-                                    changed |= ui.add(egui::Slider::new(&mut params.manual_rotation_z, -std::f32::consts::PI..=std::f32::consts::PI).text("Z")).changed();
-                                }
+                                changed |= ui.add(egui::Slider::new(&mut params.manual_rotation_x, -std::f32::consts::PI..=std::f32::consts::PI).text("X")).changed();
+                                changed |= ui.add(egui::Slider::new(&mut params.manual_rotation_y, -std::f32::consts::PI..=std::f32::consts::PI).text("Y")).changed();
+                                changed |= ui.add(egui::Slider::new(&mut params.manual_rotation_z, -std::f32::consts::PI..=std::f32::consts::PI).text("Z")).changed();
                             });
                         
                         egui::CollapsingHeader::new("Mandelbulb")
@@ -766,9 +729,9 @@ impl ShaderManager for MandelbulbShader {
 
                         ui.separator();
                         ui.label(format!("Accumulated Samples: {}", self.frame_count));
-                        ui.label(format!("Resolution: {}x{}", core.size.width, core.size.height));
+                        let size = self.output_texture.texture.size();
+                        ui.label(format!("Resolution: {}x{}", size.width, size.height));
                         ui.label(format!("FPS: {:.1}", current_fps));
-                        ui.label(format!("Mouse Rotation: {}", if self.mouse_rotation.mouse_enabled { "ON" } else { "OFF" }));
                     });
             })
         } else {
@@ -793,13 +756,6 @@ impl ShaderManager for MandelbulbShader {
         self.compute_time_uniform.data.frame = self.frame_count;
         self.compute_time_uniform.update(&core.queue);
         
-        if params.use_mouse_rotation > 0 {
-            let (norm_x, norm_y) = self.mouse_rotation.get_normalized_position(&core.size);
-            params.mouse_x = norm_x;
-            params.mouse_y = norm_y;
-            changed = true;
-        }
-        
         if changed {
             self.params_uniform.data = params;
             self.params_uniform.update(&core.queue);
@@ -821,25 +777,11 @@ impl ShaderManager for MandelbulbShader {
             compute_pass.set_bind_group(2, &self.compute_bind_group, &[]);
             compute_pass.set_bind_group(3, &self.atomic_buffer.bind_group, &[]);
             
-            let width = core.size.width.div_ceil(16);
-            let height = core.size.height.div_ceil(16);
-            compute_pass.dispatch_workgroups(width, height, 1);
+            let size = self.output_texture.texture.size();
+            compute_pass.dispatch_workgroups(size.width.div_ceil(16), size.height.div_ceil(16), 1);
         }
         
-        {
-            let mut render_pass = cuneus::Renderer::begin_render_pass(
-                &mut encoder,
-                &view,
-                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                Some("Display Pass"),
-            );
-            
-            render_pass.set_pipeline(&self.base.renderer.render_pipeline);
-            render_pass.set_vertex_buffer(0, self.base.renderer.vertex_buffer.slice(..));
-            render_pass.set_bind_group(0, &self.output_texture.bind_group, &[]);
-            
-            render_pass.draw(0..4, 0..1);
-        }
+        self.base.present_output(core, &mut encoder, &self.output_texture, &view);
         
         self.base.handle_render_output(core, &view, full_output, &mut encoder);
         core.queue.submit(Some(encoder.finish()));
@@ -852,28 +794,28 @@ impl ShaderManager for MandelbulbShader {
         Ok(())
     }
     
+    fn recreate(&mut self, core: &Core) {
+        self.recreate_keeping_state(core);
+    }
+
     fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
-        if self.base.egui_state.on_window_event(core.window(), event).consumed {
+        if self.base.handle_egui_event(core, event) {
             return true;
         }
         
-        if let WindowEvent::CursorMoved { position, .. } = event {
-            let x = position.x as f32;
-            let y = position.y as f32;
-            
-            if self.mouse_rotation.handle_mouse_movement(x, y) && self.params_uniform.data.use_mouse_rotation > 0 {
-                self.should_reset_accumulation = true;
-                return true;
+        // wheel, pinch and touch zoom the lens, the cursor position is needed by both
+        match event {
+            WindowEvent::MouseWheel { .. } | WindowEvent::PinchGesture { .. } | WindowEvent::Touch(_) => {
+                return self.lens.handle_event(event);
             }
+            WindowEvent::CursorMoved { .. } => {
+                self.lens.handle_event(event);
+            }
+            _ => {}
         }
         
-        if let WindowEvent::MouseInput { state, button, .. } = event {
-            if *button == winit::event::MouseButton::Right {
-                if *state == winit::event::ElementState::Released {
-                    self.mouse_rotation.toggle_mouse_rotation();
-                    return true;
-                }
-            }
+        if self.camera.handle_event(event) {
+            return true;
         }
         
         if let WindowEvent::KeyboardInput { event, .. } = event {
@@ -904,7 +846,12 @@ impl ShaderManager for MandelbulbShader {
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
-    let (app, event_loop) = cuneus::ShaderApp::new("Mandelbulb Path Tracer", 800, 600);
+    let (mut app, event_loop) = cuneus::ShaderApp::new("Mandelbulb Path Tracer", 800, 600);
+    // samples keep accumulating while time is paused
+    app.set_frame_pacing(cuneus::FramePacing {
+        idle_when_paused: false,
+        ..Default::default()
+    });
     
     app.run(event_loop, |core| {
         MandelbulbShader::init(core)
@@ -1,162 +1,8 @@
-use cuneus::{Core, ShaderManager, UniformProvider, UniformBinding, RenderKit, ShaderControls, ExportManager};
+use cuneus::{Core, ShaderManager, UniformProvider, UniformBinding, RenderKit, ShaderControls, ExportManager, FrameCapture, CameraController, CameraPose, FlyCamera};
 use cuneus::compute::{create_bind_group_layout, BindGroupLayoutType};
 use winit::event::WindowEvent;
 use std::path::PathBuf;
 
-struct CameraMovement {
-    forward: bool,
-    backward: bool,
-    left: bool,
-    right: bool,
-    up: bool,
-    down: bool,
-    speed: f32,
-    last_update: std::time::Instant,
-    
-    yaw: f32,
-    pitch: f32,
-    mouse_sensitivity: f32,
-    
-    last_mouse_x: f32,
-    last_mouse_y: f32,
-    mouse_initialized: bool,
-    mouse_look_enabled: bool,
-}
-
-impl Default for CameraMovement {
-    fn default() -> Self {
-        Self {
-            forward: false,
-            backward: false,
-            left: false,
-            right: false,
-            up: false,
-            down: false,
-            speed: 2.0,
-            last_update: std::time::Instant::now(),
-            
-            yaw: 0.0,
-            pitch: 0.0,
-            mouse_sensitivity: 0.005,
-            
-            last_mouse_x: 0.0,
-            last_mouse_y: 0.0,
-            mouse_initialized: false,
-            mouse_look_enabled: true,
-        }
-    }
-}
-
-impl CameraMovement {
-    fn update_camera(&mut self, params: &mut PathTracingParams) -> bool {
-        let now = std::time::Instant::now();
-        let dt = now.duration_since(self.last_update).as_secs_f32();
-        self.last_update = now;
-        
-        let mut changed = false;
-        
-        let forward = [
-            self.pitch.cos() * self.yaw.cos(),
-            self.pitch.sin(),
-            self.pitch.cos() * self.yaw.sin(),
-        ];
-        
-        let world_up = [0.0, 1.0, 0.0];
-        let right = [
-            forward[1] * world_up[2] - forward[2] * world_up[1],
-            forward[2] * world_up[0] - forward[0] * world_up[2],
-            forward[0] * world_up[1] - forward[1] * world_up[0],
-        ];
-        
-        let right_len = (right[0] * right[0] + right[1] * right[1] + right[2] * right[2]).sqrt();
-        let right = [right[0] / right_len, right[1] / right_len, right[2] / right_len];
-        
-        let _up = [
-            right[1] * forward[2] - right[2] * forward[1],
-            right[2] * forward[0] - right[0] * forward[2],
-            right[0] * forward[1] - right[1] * forward[0],
-        ];
-        
-        let delta = self.speed * dt;
-        let mut move_vec = [0.0, 0.0, 0.0];
-        
-        if self.forward {
-            move_vec[0] += forward[0] * delta;
-            move_vec[1] += forward[1] * delta;
-            move_vec[2] += forward[2] * delta;
-            changed = true;
-        }
-        if self.backward {
-            move_vec[0] -= forward[0] * delta;
-            move_vec[1] -= forward[1] * delta;
-            move_vec[2] -= forward[2] * delta;
-            changed = true;
-        }
-        if self.right {
-            move_vec[0] += right[0] * delta;
-            move_vec[1] += right[1] * delta;
-            move_vec[2] += right[2] * delta;
-            changed = true;
-        }
-        if self.left {
-            move_vec[0] -= right[0] * delta;
-            move_vec[1] -= right[1] * delta;
-            move_vec[2] -= right[2] * delta;
-            changed = true;
-        }
-        if self.up {
-            move_vec[1] += delta;
-            changed = true;
-        }
-        if self.down {
-            move_vec[1] -= delta;
-            changed = true;
-        }
-        
-        params.camera_pos_x += move_vec[0];
-        params.camera_pos_y += move_vec[1];
-        params.camera_pos_z += move_vec[2];
-        
-        let look_distance = 1.0;
-        params.camera_target_x = params.camera_pos_x + forward[0] * look_distance;
-        params.camera_target_y = params.camera_pos_y + forward[1] * look_distance;
-        params.camera_target_z = params.camera_pos_z + forward[2] * look_distance;
-        
-        changed
-    }
-    
-    fn handle_mouse_movement(&mut self, x: f32, y: f32) -> bool {
-        if !self.mouse_look_enabled {
-            return false;
-        }
-        
-        if !self.mouse_initialized {
-            self.last_mouse_x = x;
-            self.last_mouse_y = y;
-            self.mouse_initialized = true;
-            return false;
-        }
-        
-        let dx = x - self.last_mouse_x;
-        let dy = y - self.last_mouse_y;
-        
-        self.last_mouse_x = x;
-        self.last_mouse_y = y;
-        
-        self.yaw += dx * self.mouse_sensitivity;
-        self.pitch -= dy * self.mouse_sensitivity; 
-        
-        self.pitch = self.pitch.clamp(-std::f32::consts::PI * 0.49, std::f32::consts::PI * 0.49);
-        
-        true
-    }
-    
-    fn toggle_mouse_look(&mut self) {
-        self.mouse_look_enabled = !self.mouse_look_enabled;
-        self.mouse_initialized = false;
-    }
-}
-
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct PathTracingParams {
@@ -222,15 +68,33 @@ struct PathTracingShader {
     should_reset_accumulation: bool,
     
     // Camera movement
-    camera_movement: CameraMovement,
+    camera: FlyCamera,
 }
 
 impl PathTracingShader {
+    fn trace(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Path Tracing Compute Pass"),
+            timestamp_writes: None,
+        });
+        
+        compute_pass.set_pipeline(&self.compute_pipeline);
+        compute_pass.set_bind_group(0, &self.compute_time_uniform.bind_group, &[]);
+        compute_pass.set_bind_group(1, &self.params_uniform.bind_group, &[]);
+        compute_pass.set_bind_group(2, &self.compute_bind_group, &[]);
+        compute_pass.set_bind_group(3, &self.atomic_buffer.bind_group, &[]);
+        
+        let size = self.output_texture.texture.size();
+        compute_pass.dispatch_workgroups(size.width.div_ceil(16), size.height.div_ceil(16), 1);
+    }
+
+    // output and accumulation buffer at the render size, present_output upscales to the window
     fn recreate_compute_resources(&mut self, core: &Core) {
+        let size = self.base.render_size(core);
         self.output_texture = cuneus::compute::create_output_texture(
             &core.device,
-            core.size.width,
-            core.size.height,
+            size.width,
+            size.height,
             wgpu::TextureFormat::Rgba16Float,
             &self.base.texture_bind_group_layout,
             wgpu::AddressMode::ClampToEdge,
@@ -238,7 +102,7 @@ impl PathTracingShader {
             "Path Tracing Output Texture",
         );
 
-        let buffer_size = core.size.width * core.size.height * 3;
+        let buffer_size = size.width * size.height * 3;
         
         self.atomic_buffer = cuneus::AtomicBuffer::new(
             &core.device,
@@ -291,31 +155,51 @@ impl PathTracingShader {
         self.should_reset_accumulation = true;
     }
     
-    fn capture_frame(&mut self, core: &Core, time: f32) -> Result<Vec<u8>, wgpu::SurfaceError> {
-        let settings = self.base.export_manager.settings();
-        let (capture_texture, output_buffer) = self.base.create_capture_texture(
-            &core.device,
-            settings.width,
-            settings.height
-        );
-        
-        let align = 256;
-        let unpadded_bytes_per_row = settings.width * 4;
-        let padding = (align - unpadded_bytes_per_row % align) % align;
-        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
-        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        
-        let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Capture Encoder"),
-        });
+    fn clear_atomic_buffer(&mut self, core: &Core) {
+        let size = self.output_texture.texture.size();
+        let buffer_size = size.width * size.height * 3;
+        let clear_data = vec![0u32; buffer_size as usize];
         
-        self.base.time_uniform.data.time = time;
-        self.base.time_uniform.update(&core.queue);
+        core.queue.write_buffer(
+            &self.atomic_buffer.buffer,
+            0,
+            bytemuck::cast_slice(&clear_data),
+        );
         
+        self.should_reset_accumulation = false;
+        self.frame_count = 0;
+    }
+}
+
+impl FrameCapture for PathTracingShader {
+    fn render_kit(&mut self) -> &mut RenderKit {
+        &mut self.base
+    }
+
+    cuneus::impl_frame_capture_presets!(params_uniform, hot_reload);
+
+    fn capture_metadata(&self) -> Vec<(String, String)> {
+        vec![self.camera.pose().to_metadata()]
+    }
+
+    fn apply_preset_metadata(&mut self, _core: &Core, metadata: &cuneus::FrameMetadata) {
+        if let Some(pose) = CameraPose::from_metadata(metadata) {
+            self.camera.set_pose(pose);
+        }
+    }
+
+    fn render_capture(
+        &mut self,
+        _core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        _time: f32,
+        _frame: u32,
+    ) {
         {
             let mut render_pass = cuneus::Renderer::begin_render_pass(
-                &mut encoder,
-                &capture_view,
+                encoder,
+                view,
                 wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                 Some("Capture Pass"),
             );
@@ -325,74 +209,19 @@ impl PathTracingShader {
             render_pass.set_bind_group(0, &self.output_texture.bind_group, &[]);
             render_pass.draw(0..4, 0..1);
         }
-        
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture: &capture_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &output_buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(settings.height),
-                },
-            },
-            wgpu::Extent3d {
-                width: settings.width,
-                height: settings.height,
-                depth_or_array_layers: 1,
-            },
-        );
-        
-        core.queue.submit(Some(encoder.finish()));
-        
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
-        
-        core.device.poll(wgpu::Maintain::Wait);
-        rx.recv().unwrap().unwrap();
-        
-        let padded_data = buffer_slice.get_mapped_range().to_vec();
-        let mut unpadded_data = Vec::with_capacity((settings.width * settings.height * 4) as usize);
-        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
-            unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
-        }
-        
-        Ok(unpadded_data)
     }
-    
-    fn handle_export(&mut self, core: &Core) {
-        if let Some((frame, time)) = self.base.export_manager.try_get_next_frame() {
-            if let Ok(data) = self.capture_frame(core, time) {
-                let settings = self.base.export_manager.settings();
-                if let Err(e) = cuneus::save_frame(data, frame, settings) {
-                    eprintln!("Error saving frame: {:?}", e);
-                }
-            }
-        } else {
-            self.base.export_manager.complete_export();
-        }
+
+    // the accumulated Rgba16Float output, unclamped for grading
+    fn float_output(&self) -> Option<&wgpu::Texture> {
+        Some(&self.output_texture.texture)
     }
-    
-    fn clear_atomic_buffer(&mut self, core: &Core) {
-        let buffer_size = core.size.width * core.size.height * 3;
-        let clear_data = vec![0u32; buffer_size as usize];
-        
-        core.queue.write_buffer(
-            &self.atomic_buffer.buffer,
-            0,
-            bytemuck::cast_slice(&clear_data),
-        );
-        
-        self.should_reset_accumulation = false;
-        self.frame_count = 0;
+
+    // one more sample at the export frame's time, on top of what is accumulated
+    fn render_float_capture(&mut self, core: &Core, encoder: &mut wgpu::CommandEncoder, time: f32, _frame: u32) {
+        self.compute_time_uniform.data.time = time;
+        self.compute_time_uniform.data.frame = self.frame_count;
+        self.compute_time_uniform.update(&core.queue);
+        self.trace(encoder);
     }
 }
 
@@ -542,6 +371,7 @@ impl ShaderManager for PathTracingShader {
             &[&texture_bind_group_layout],
             None,
         );
+        base.render_scale_enabled = true;
         
         // Setup mouse tracking
         base.setup_mouse_uniform(core);
@@ -612,7 +442,7 @@ impl ShaderManager for PathTracingShader {
             frame_count: 0,
             hot_reload,
             should_reset_accumulation: true,
-            camera_movement: CameraMovement::default(),
+            camera: FlyCamera::new([0.0, 1.0, 6.0], 0.0, 0.0),
         };
         
         result.recreate_compute_resources(core);
@@ -656,9 +486,20 @@ impl ShaderManager for PathTracingShader {
         if self.base.export_manager.is_exporting() {
             self.handle_export(core);
         }
+        self.handle_screenshot(core);
+        self.handle_preset_request(core);
+        
+        if self.base.update_render_scale(core) {
+            self.recreate_compute_resources(core);
+        }
         
-        if self.camera_movement.update_camera(&mut self.params_uniform.data) {
+        if self.camera.update(&self.base.key_handler.input) {
+            let pose = self.camera.pose();
+            let params = &mut self.params_uniform.data;
+            [params.camera_pos_x, params.camera_pos_y, params.camera_pos_z] = pose.position;
+            [params.camera_target_x, params.camera_target_y, params.camera_target_z] = pose.target;
             self.params_uniform.update(&core.queue);
+            self.should_reset_accumulation = true;
         }
         
         self.base.fps_tracker.update();
@@ -666,6 +507,7 @@ impl ShaderManager for PathTracingShader {
     
     fn resize(&mut self, core: &Core) {
         println!("Resizing to {:?}", core.size);
+        self.base.resize_scaled(core);
         self.recreate_compute_resources(core);
     }
     
@@ -684,7 +526,7 @@ impl ShaderManager for PathTracingShader {
             &self.base.start_time,
             &core.size
         );
-        controls_request.current_fps = Some(self.base.fps_tracker.fps());
+        self.base.add_frame_stats(&mut controls_request);
         
         let current_fps = self.base.fps_tracker.fps();
         
@@ -764,7 +606,8 @@ impl ShaderManager for PathTracingShader {
 
                         ui.separator();
                         ui.label(format!("Accumulated Samples: {}", self.frame_count));
-                        ui.label(format!("Resolution: {}x{}", core.size.width, core.size.height));
+                        let size = self.output_texture.texture.size();
+                        ui.label(format!("Resolution: {}x{}", size.width, size.height));
                         ui.label(format!("FPS: {:.1}", current_fps));
                     });
             })
@@ -814,38 +657,10 @@ impl ShaderManager for PathTracingShader {
         }
         
         // Run the compute shader pass
-        {
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Path Tracing Compute Pass"),
-                timestamp_writes: None,
-            });
-            
-            compute_pass.set_pipeline(&self.compute_pipeline);
-            compute_pass.set_bind_group(0, &self.compute_time_uniform.bind_group, &[]);
-            compute_pass.set_bind_group(1, &self.params_uniform.bind_group, &[]);
-            compute_pass.set_bind_group(2, &self.compute_bind_group, &[]);
-            compute_pass.set_bind_group(3, &self.atomic_buffer.bind_group, &[]);
-            
-            let width = core.size.width.div_ceil(16);
-            let height = core.size.height.div_ceil(16);
-            compute_pass.dispatch_workgroups(width, height, 1);
-        }
+        self.trace(&mut encoder);
         
-        // Render the compute output to the screen
-        {
-            let mut render_pass = cuneus::Renderer::begin_render_pass(
-                &mut encoder,
-                &view,
-                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                Some("Display Pass"),
-            );
-            
-            render_pass.set_pipeline(&self.base.renderer.render_pipeline);
-            render_pass.set_vertex_buffer(0, self.base.renderer.vertex_buffer.slice(..));
-            render_pass.set_bind_group(0, &self.output_texture.bind_group, &[]);
-            
-            render_pass.draw(0..4, 0..1);
-        }
+        // Render the compute output to the screen, upscaled if a render scale is set
+        self.base.present_output(core, &mut encoder, &self.output_texture, &view);
         
         self.base.handle_render_output(core, &view, full_output, &mut encoder);
         core.queue.submit(Some(encoder.finish()));
@@ -859,75 +674,29 @@ impl ShaderManager for PathTracingShader {
         Ok(())
     }
     
+    fn recreate(&mut self, core: &Core) {
+        self.recreate_keeping_state(core);
+    }
+
     fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
-        if self.base.egui_state.on_window_event(core.window(), event).consumed {
+        if self.base.handle_egui_event(core, event) {
             return true;
         }
         
         if let WindowEvent::KeyboardInput { event, .. } = event {
-            if let winit::keyboard::Key::Character(ch) = &event.logical_key {
-                match ch.as_str() {
-                    "w" | "W" => {
-                        self.camera_movement.forward = event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    },
-                    "s" | "S" => {
-                        self.camera_movement.backward = event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    },
-                    "a" | "A" => {
-                        self.camera_movement.left = event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    },
-                    "d" | "D" => {
-                        self.camera_movement.right = event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    },
-                    "q" | "Q" => {
-                        self.camera_movement.down = event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    },
-                    "e" | "E" => {
-                        self.camera_movement.up = event.state == winit::event::ElementState::Pressed;
-                        self.should_reset_accumulation = true;
-                        return true;
-                    },
-                    " " => {
-                        if event.state == winit::event::ElementState::Released {
-                            self.params_uniform.data.accumulate = 1 - self.params_uniform.data.accumulate;
-                            self.should_reset_accumulation = true;
-                            self.params_uniform.update(&core.queue);
-                            return true;
-                        }
-                    },
-                    _ => {}
-                }
-            }
-        }
-        
-        if let WindowEvent::CursorMoved { position, .. } = event {
-            let x = position.x as f32;
-            let y = position.y as f32;
-            
-            if self.camera_movement.handle_mouse_movement(x, y) {
+            if event.logical_key == winit::keyboard::Key::Named(winit::keyboard::NamedKey::Space)
+                && event.state == winit::event::ElementState::Released {
+                self.params_uniform.data.accumulate = 1 - self.params_uniform.data.accumulate;
                 self.should_reset_accumulation = true;
-                return true;
+                self.params_uniform.update(&core.queue);
             }
         }
         
-        if let WindowEvent::MouseInput { state, button, .. } = event {
-            if *button == winit::event::MouseButton::Right {
-                if *state == winit::event::ElementState::Released {
-                    self.camera_movement.toggle_mouse_look();
-                    return true;
-                }
-            }
+        if self.camera.handle_event(event) {
+            self.should_reset_accumulation = true;
+            return true;
         }
+        
         if let WindowEvent::DroppedFile(path) = event {
             if let Err(e) = self.base.load_media(core, path) {
                 eprintln!("Failed to load dropped file: {:?}", e);
@@ -951,7 +720,12 @@ impl ShaderManager for PathTracingShader {
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     cuneus::gst::init()?;
     env_logger::init();
-    let (app, event_loop) = cuneus::ShaderApp::new("Path Tracer", 800, 600);
+    let (mut app, event_loop) = cuneus::ShaderApp::new("Path Tracer", 800, 600);
+    // samples keep accumulating while time is paused
+    app.set_frame_pacing(cuneus::FramePacing {
+        idle_when_paused: false,
+        ..Default::default()
+    });
     
     app.run(event_loop, |core| {
         PathTracingShader::init(core)
@@ -1,154 +1,8 @@
-use cuneus::{Core, ShaderManager, UniformProvider, UniformBinding, RenderKit, ShaderControls, ExportManager};
+use cuneus::{Core, ShaderManager, UniformProvider, UniformBinding, RenderKit, ShaderControls, ExportManager, FrameCapture, CameraController, CameraPath, CameraPose, FlyCamera};
 use cuneus::compute::{create_bind_group_layout, BindGroupLayoutType};
 use winit::event::WindowEvent;
 use std::path::PathBuf;
 
-struct CameraMovement {
-    forward: bool,
-    backward: bool,
-    left: bool,
-    right: bool,
-    up: bool,
-    down: bool,
-    speed: f32,
-    last_update: std::time::Instant,
-    
-    yaw: f32,
-    pitch: f32,
-    mouse_sensitivity: f32,
-    
-    last_mouse_x: f32,
-    last_mouse_y: f32,
-    mouse_initialized: bool,
-    mouse_look_enabled: bool,
-}
-
-impl Default for CameraMovement {
-    fn default() -> Self {
-        Self {
-            forward: false,
-            backward: false,
-            left: false,
-            right: false,
-            up: false,
-            down: false,
-            speed: 1.5,
-            last_update: std::time::Instant::now(),
-            
-            yaw: 0.0,
-            pitch: -0.3,
-            mouse_sensitivity: 0.004,
-            
-            last_mouse_x: 0.0,
-            last_mouse_y: 0.0,
-            mouse_initialized: false,
-            mouse_look_enabled: true,
-        }
-    }
-}
-
-impl CameraMovement {
-    fn update_camera(&mut self, params: &mut WaterParams) -> bool {
-        let now = std::time::Instant::now();
-        let dt = now.duration_since(self.last_update).as_secs_f32();
-        self.last_update = now;
-        
-        let mut changed = false;
-        
-        let forward = [
-            self.pitch.cos() * self.yaw.cos(),
-            self.pitch.sin(),
-            self.pitch.cos() * self.yaw.sin(),
-        ];
-        
-        let world_up = [0.0, 1.0, 0.0];
-        let right = [
-            forward[1] * world_up[2] - forward[2] * world_up[1],
-            forward[2] * world_up[0] - forward[0] * world_up[2],
-            forward[0] * world_up[1] - forward[1] * world_up[0],
-        ];
-        
-        let right_len = (right[0] * right[0] + right[1] * right[1] + right[2] * right[2]).sqrt();
-        let right = [right[0] / right_len, right[1] / right_len, right[2] / right_len];
-        
-        let delta = self.speed * dt;
-        let mut move_vec = [0.0, 0.0, 0.0];
-        
-        if self.forward {
-            move_vec[0] += forward[0] * delta;
-            move_vec[1] += forward[1] * delta;
-            move_vec[2] += forward[2] * delta;
-            changed = true;
-        }
-        if self.backward {
-            move_vec[0] -= forward[0] * delta;
-            move_vec[1] -= forward[1] * delta;
-            move_vec[2] -= forward[2] * delta;
-            changed = true;
-        }
-        if self.right {
-            move_vec[0] += right[0] * delta;
-            move_vec[1] += right[1] * delta;
-            move_vec[2] += right[2] * delta;
-            changed = true;
-        }
-        if self.left {
-            move_vec[0] -= right[0] * delta;
-            move_vec[1] -= right[1] * delta;
-            move_vec[2] -= right[2] * delta;
-            changed = true;
-        }
-        if self.up {
-            move_vec[1] += delta;
-            changed = true;
-        }
-        if self.down {
-            move_vec[1] -= delta;
-            changed = true;
-        }
-        
-        params.camera_pos_x += move_vec[0];
-        params.camera_pos_y += move_vec[1];
-        params.camera_pos_z += move_vec[2];
-        
-        params.camera_yaw = self.yaw;
-        params.camera_pitch = self.pitch;
-        
-        changed
-    }
-    
-    fn handle_mouse_movement(&mut self, x: f32, y: f32) -> bool {
-        if !self.mouse_look_enabled {
-            return false;
-        }
-        
-        if !self.mouse_initialized {
-            self.last_mouse_x = x;
-            self.last_mouse_y = y;
-            self.mouse_initialized = true;
-            return false;
-        }
-        
-        let dx = x - self.last_mouse_x;
-        let dy = y - self.last_mouse_y;
-        
-        self.last_mouse_x = x;
-        self.last_mouse_y = y;
-        
-        self.yaw += dx * self.mouse_sensitivity;
-        self.pitch -= dy * self.mouse_sensitivity;
-        
-        self.pitch = self.pitch.clamp(-std::f32::consts::PI * 0.49, std::f32::consts::PI * 0.49);
-        
-        true
-    }
-    
-    fn toggle_mouse_look(&mut self) {
-        self.mouse_look_enabled = !self.mouse_look_enabled;
-        self.mouse_initialized = false;
-    }
-}
-
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct WaterParams {
@@ -216,7 +70,10 @@ struct WaterShader {
     compute_bind_group: wgpu::BindGroup,
     frame_count: u32,
     hot_reload: cuneus::ShaderHotReload,
-    camera_movement: CameraMovement,
+    camera: FlyCamera,
+    // keyframes the camera follows while follow_path is on, also during export
+    camera_path: CameraPath,
+    follow_path: bool,
     
     export_time: Option<f32>,
     export_frame: Option<u32>,
@@ -249,82 +106,12 @@ impl WaterShader {
         });
     }
     
-    fn capture_frame(&mut self, core: &Core, time: f32, frame: u32) -> Result<Vec<u8>, wgpu::SurfaceError> {
-        let settings = self.base.export_manager.settings();
-        let (capture_texture, output_buffer) = self.base.create_capture_texture(
-            &core.device,
-            settings.width,
-            settings.height
-        );
-        
-        let align = 256;
-        let unpadded_bytes_per_row = settings.width * 4;
-        let padding = (align - unpadded_bytes_per_row % align) % align;
-        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
-        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        
-        let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Capture Encoder"),
-        });
-        
-        self.base.time_uniform.data.time = time;
-        self.base.time_uniform.data.frame = frame;
-        self.base.time_uniform.update(&core.queue);
-        
-        {
-            let mut render_pass = cuneus::Renderer::begin_render_pass(
-                &mut encoder,
-                &capture_view,
-                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                Some("Capture Pass"),
-            );
-            
-            render_pass.set_pipeline(&self.base.renderer.render_pipeline);
-            render_pass.set_vertex_buffer(0, self.base.renderer.vertex_buffer.slice(..));
-            render_pass.set_bind_group(0, &self.output_texture.bind_group, &[]);
-            render_pass.draw(0..4, 0..1);
-        }
-        
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture: &capture_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &output_buffer,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(settings.height),
-                },
-            },
-            wgpu::Extent3d {
-                width: settings.width,
-                height: settings.height,
-                depth_or_array_layers: 1,
-            },
-        );
-        
-        core.queue.submit(Some(encoder.finish()));
-        
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
-        
-        core.device.poll(wgpu::Maintain::Wait);
-        rx.recv().unwrap().unwrap();
-        
-        let padded_data = buffer_slice.get_mapped_range().to_vec();
-        let mut unpadded_data = Vec::with_capacity((settings.width * settings.height * 4) as usize);
-        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
-            unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
-        }
-        
-        Ok(unpadded_data)
+    fn sync_camera(&mut self, core: &Core) {
+        let params = &mut self.params_uniform.data;
+        [params.camera_pos_x, params.camera_pos_y, params.camera_pos_z] = self.camera.position;
+        params.camera_yaw = self.camera.yaw;
+        params.camera_pitch = self.camera.pitch;
+        self.params_uniform.update(&core.queue);
     }
     
     fn handle_export(&mut self, core: &Core) {
@@ -348,6 +135,50 @@ impl WaterShader {
     }
 }
 
+impl FrameCapture for WaterShader {
+    fn render_kit(&mut self) -> &mut RenderKit {
+        &mut self.base
+    }
+
+    cuneus::impl_frame_capture_presets!(params_uniform, hot_reload);
+
+    fn capture_metadata(&self) -> Vec<(String, String)> {
+        vec![self.camera.pose().to_metadata()]
+    }
+
+    fn apply_preset_metadata(&mut self, _core: &Core, metadata: &cuneus::FrameMetadata) {
+        if let Some(pose) = CameraPose::from_metadata(metadata) {
+            self.camera.set_pose(pose);
+        }
+    }
+
+    fn render_capture(
+        &mut self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        _time: f32,
+        frame: u32,
+    ) {
+        self.base.time_uniform.data.frame = frame;
+        self.base.time_uniform.update(&core.queue);
+        
+        {
+            let mut render_pass = cuneus::Renderer::begin_render_pass(
+                encoder,
+                view,
+                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                Some("Capture Pass"),
+            );
+            
+            render_pass.set_pipeline(&self.base.renderer.render_pipeline);
+            render_pass.set_vertex_buffer(0, self.base.renderer.vertex_buffer.slice(..));
+            render_pass.set_bind_group(0, &self.output_texture.bind_group, &[]);
+            render_pass.draw(0..4, 0..1);
+        }
+    }
+}
+
 impl ShaderManager for WaterShader {
     fn init(core: &Core) -> Self {
         let texture_bind_group_layout = core.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -530,6 +361,10 @@ impl ShaderManager for WaterShader {
             label: Some("Arctic Water Compute Bind Group"),
         });
         
+        let mut camera = FlyCamera::new([0.0, 2.5, 3.0], 0.0, -0.3);
+        camera.speed = 1.5;
+        camera.mouse_sensitivity = 0.004;
+        
         let mut result = Self {
             base,
             params_uniform,
@@ -542,7 +377,9 @@ impl ShaderManager for WaterShader {
             compute_bind_group,
             frame_count: 0,
             hot_reload,
-            camera_movement: CameraMovement::default(),
+            camera,
+            camera_path: CameraPath::new(),
+            follow_path: false,
             export_time: None,
             export_frame: None,
         };
@@ -578,9 +415,11 @@ impl ShaderManager for WaterShader {
         if self.base.export_manager.is_exporting() {
             self.handle_export(core);
         }
+        self.handle_screenshot(core);
+        self.handle_preset_request(core);
         
-        if self.camera_movement.update_camera(&mut self.params_uniform.data) {
-            self.params_uniform.update(&core.queue);
+        if self.camera.update(&self.base.key_handler.input) {
+            self.sync_camera(core);
         }
         
         self.base.fps_tracker.update();
@@ -606,9 +445,13 @@ impl ShaderManager for WaterShader {
             &self.base.start_time,
             &core.size
         );
-        controls_request.current_fps = Some(self.base.fps_tracker.fps());
+        self.base.add_frame_stats(&mut controls_request);
         
         let current_fps = self.base.fps_tracker.fps();
+        let camera_keys = self.camera_path.keys().len();
+        let mut follow_path = self.follow_path;
+        let mut add_camera_key = false;
+        let mut clear_camera_path = false;
         
         let full_output = if self.base.key_handler.show_ui {
             self.base.render_ui(core, |ctx| {
@@ -688,6 +531,17 @@ impl ShaderManager for WaterShader {
                                 changed |= ui.add(egui::Slider::new(&mut params.cloud_height, 1.0..=150.0).text("Aurora qual")).changed();
                             });
 
+                        egui::CollapsingHeader::new("Camera Path")
+                            .default_open(false)
+                            .show(ui, |ui| {
+                                ui.horizontal(|ui| {
+                                    add_camera_key = ui.button("Add Key").clicked();
+                                    clear_camera_path = ui.button("Clear").clicked();
+                                    ui.label(format!("{} keys", camera_keys));
+                                });
+                                ui.checkbox(&mut follow_path, "Follow Path");
+                            });
+
                         egui::CollapsingHeader::new("Vis Settings")
                             .default_open(true)
                             .show(ui, |ui| {
@@ -731,6 +585,18 @@ impl ShaderManager for WaterShader {
             self.params_uniform.update(&core.queue);
         }
         
+        if add_camera_key {
+            self.camera_path.insert(current_time, self.camera.pose());
+        }
+        if clear_camera_path {
+            self.camera_path.clear();
+        }
+        self.follow_path = follow_path;
+        if let Some(pose) = self.camera_path.sample(current_time).filter(|_| self.follow_path) {
+            self.camera.set_pose(pose);
+            self.sync_camera(core);
+        }
+        
         if should_start_export {
             self.base.export_manager.start_export();
         }
@@ -786,59 +652,17 @@ impl ShaderManager for WaterShader {
         Ok(())
     }
     
+    fn recreate(&mut self, core: &Core) {
+        self.recreate_keeping_state(core);
+    }
+
     fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
-        if self.base.egui_state.on_window_event(core.window(), event).consumed {
+        if self.base.handle_egui_event(core, event) {
             return true;
         }
         
-        if let WindowEvent::KeyboardInput { event, .. } = event {
-            if let winit::keyboard::Key::Character(ch) = &event.logical_key {
-                match ch.as_str() {
-                    "w" | "W" => {
-                        self.camera_movement.forward = event.state == winit::event::ElementState::Pressed;
-                        return true;
-                    },
-                    "s" | "S" => {
-                        self.camera_movement.backward = event.state == winit::event::ElementState::Pressed;
-                        return true;
-                    },
-                    "a" | "A" => {
-                        self.camera_movement.left = event.state == winit::event::ElementState::Pressed;
-                        return true;
-                    },
-                    "d" | "D" => {
-                        self.camera_movement.right = event.state == winit::event::ElementState::Pressed;
-                        return true;
-                    },
-                    "q" | "Q" => {
-                        self.camera_movement.down = event.state == winit::event::ElementState::Pressed;
-                        return true;
-                    },
-                    "e" | "E" => {
-                        self.camera_movement.up = event.state == winit::event::ElementState::Pressed;
-                        return true;
-                    },
-                    _ => {}
-                }
-            }
-        }
-        
-        if let WindowEvent::CursorMoved { position, .. } = event {
-            let x = position.x as f32;
-            let y = position.y as f32;
-            
-            if self.camera_movement.handle_mouse_movement(x, y) {
-                return true;
-            }
-        }
-        
-        if let WindowEvent::MouseInput { state, button, .. } = event {
-            if *button == winit::event::MouseButton::Right {
-                if *state == winit::event::ElementState::Released {
-                    self.camera_movement.toggle_mouse_look();
-                    return true;
-                }
-            }
+        if self.camera.handle_event(event) {
+            return true;
         }
         
         if let WindowEvent::KeyboardInput { event, .. } = event {
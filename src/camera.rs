@@ -0,0 +1,539 @@
+use std::time::Instant;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use crate::{FrameMetadata, InputState, UniformProvider};
+
+const KEY_CAMERA: &str = "cuneus.camera";
+const WORLD_UP: [f32; 3] = [0.0, 1.0, 0.0];
+// keeps the basis stable, looking straight up or down flips yaw
+const MAX_PITCH: f32 = std::f32::consts::PI * 0.49;
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn length(a: [f32; 3]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let len = length(a);
+    if len > 0.0 { scale(a, 1.0 / len) } else { a }
+}
+
+fn lerp3(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    add(a, scale(sub(b, a), t))
+}
+
+// unit direction for yaw around +Y (0 looks down +X) and pitch above the horizon
+fn direction(yaw: f32, pitch: f32) -> [f32; 3] {
+    [pitch.cos() * yaw.cos(), pitch.sin(), pitch.cos() * yaw.sin()]
+}
+
+// inverse of direction()
+fn yaw_pitch(dir: [f32; 3]) -> (f32, f32) {
+    let dir = normalize(dir);
+    (dir[2].atan2(dir[0]), dir[1].clamp(-1.0, 1.0).asin())
+}
+
+// frame rate independent exponential smoothing factor, 0 smoothing snaps
+pub(crate) fn smoothing_factor(smoothing: f32, dt: f32) -> f32 {
+    if smoothing <= 0.0 { 1.0 } else { 1.0 - (-dt / smoothing).exp() }
+}
+
+/// Where a 3D camera is and what it looks at. This is what gets saved in presets and
+/// interpolated between keyframes; controllers convert it to their own parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraPose {
+    pub position: [f32; 3],
+    pub target: [f32; 3],
+    // vertical field of view in degrees
+    pub fov: f32,
+}
+
+impl Default for CameraPose {
+    fn default() -> Self {
+        Self {
+            position: [0.0, 0.0, 5.0],
+            target: [0.0, 0.0, 0.0],
+            fov: 45.0,
+        }
+    }
+}
+
+impl CameraPose {
+    pub fn forward(&self) -> [f32; 3] {
+        normalize(sub(self.target, self.position))
+    }
+
+    // (right, up, forward), right handed with +Y up
+    pub fn basis(&self) -> ([f32; 3], [f32; 3], [f32; 3]) {
+        let forward = self.forward();
+        let right = normalize(cross(forward, WORLD_UP));
+        let up = cross(right, forward);
+        (right, up, forward)
+    }
+
+    // column major, right handed look-at
+    pub fn view_matrix(&self) -> [[f32; 4]; 4] {
+        let (r, u, f) = self.basis();
+        let e = self.position;
+        [
+            [r[0], u[0], -f[0], 0.0],
+            [r[1], u[1], -f[1], 0.0],
+            [r[2], u[2], -f[2], 0.0],
+            [-dot(r, e), -dot(u, e), dot(f, e), 1.0],
+        ]
+    }
+
+    // column major perspective with wgpu's 0..1 depth range
+    pub fn projection_matrix(&self, aspect: f32, near: f32, far: f32) -> [[f32; 4]; 4] {
+        let f = 1.0 / (self.fov.to_radians() * 0.5).tan();
+        [
+            [f / aspect, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, far / (near - far), -1.0],
+            [0.0, 0.0, near * far / (near - far), 0.0],
+        ]
+    }
+
+    pub fn uniform(&self, aspect: f32, near: f32, far: f32) -> CameraUniform {
+        let (right, up, forward) = self.basis();
+        CameraUniform {
+            view: self.view_matrix(),
+            projection: self.projection_matrix(aspect, near, far),
+            position: self.position,
+            tan_half_fov: (self.fov.to_radians() * 0.5).tan(),
+            forward,
+            aspect,
+            right,
+            near,
+            up,
+            far,
+        }
+    }
+
+    pub fn lerp(&self, other: &CameraPose, t: f32) -> CameraPose {
+        CameraPose {
+            position: lerp3(self.position, other.position, t),
+            target: lerp3(self.target, other.target, t),
+            fov: self.fov + (other.fov - self.fov) * t,
+        }
+    }
+
+    // PNG text entry for FrameCapture::capture_metadata
+    pub fn to_metadata(&self) -> (String, String) {
+        let p = self.position;
+        let t = self.target;
+        (
+            KEY_CAMERA.to_string(),
+            format!("{} {} {} {} {} {} {}", p[0], p[1], p[2], t[0], t[1], t[2], self.fov),
+        )
+    }
+
+    pub fn from_metadata(metadata: &FrameMetadata) -> Option<Self> {
+        let (_, value) = metadata.extra.iter().find(|(key, _)| key == KEY_CAMERA)?;
+        let values: Vec<f32> = value.split_whitespace().map(|v| v.parse().ok()).collect::<Option<_>>()?;
+        let [px, py, pz, tx, ty, tz, fov] = values[..] else {
+            return None;
+        };
+        Some(CameraPose {
+            position: [px, py, pz],
+            target: [tx, ty, tz],
+            fov,
+        })
+    }
+}
+
+/// Standard camera uniform. Matrices are column major; the basis vectors build primary rays:
+/// ```wgsl
+/// struct Camera {
+///     view: mat4x4<f32>,
+///     projection: mat4x4<f32>,
+///     position: vec3<f32>,
+///     tan_half_fov: f32,
+///     forward: vec3<f32>,
+///     aspect: f32,
+///     right: vec3<f32>,
+///     near: f32,
+///     up: vec3<f32>,
+///     far: f32,
+/// };
+/// // ndc in -1..1
+/// let dir = normalize(camera.forward
+///     + ndc.x * camera.aspect * camera.tan_half_fov * camera.right
+///     + ndc.y * camera.tan_half_fov * camera.up);
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CameraUniform {
+    pub view: [[f32; 4]; 4],
+    pub projection: [[f32; 4]; 4],
+    pub position: [f32; 3],
+    pub tan_half_fov: f32,
+    pub forward: [f32; 3],
+    pub aspect: f32,
+    pub right: [f32; 3],
+    pub near: f32,
+    pub up: [f32; 3],
+    pub far: f32,
+}
+
+impl Default for CameraUniform {
+    fn default() -> Self {
+        CameraPose::default().uniform(1.0, 0.1, 1000.0)
+    }
+}
+
+impl UniformProvider for CameraUniform {
+    fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+/// Camera controllers take mouse events from `handle_input` and keys (the `move_*` actions)
+/// from `InputState` once per frame in `update`.
+pub trait CameraController {
+    // Returns true when the event moved the camera
+    fn handle_event(&mut self, event: &WindowEvent) -> bool;
+    // Advances movement and smoothing, true when the pose changed
+    fn update(&mut self, input: &InputState) -> bool;
+    fn pose(&self) -> CameraPose;
+    // Jumps to `pose` without smoothing
+    fn set_pose(&mut self, pose: CameraPose);
+}
+
+/// First person camera: `move_*` actions move, the mouse looks around. Clicking
+/// `look_toggle_button` turns mouse look on and off.
+#[derive(Debug, Clone)]
+pub struct FlyCamera {
+    pub position: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    pub fov: f32,
+    // units per second
+    pub speed: f32,
+    // radians per pixel
+    pub mouse_sensitivity: f32,
+    // seconds to catch up with the input, 0 is immediate
+    pub smoothing: f32,
+    pub mouse_look_enabled: bool,
+    pub look_toggle_button: Option<MouseButton>,
+    target_yaw: f32,
+    target_pitch: f32,
+    velocity: [f32; 3],
+    last_cursor: Option<[f32; 2]>,
+    last_update: Instant,
+}
+
+impl Default for FlyCamera {
+    fn default() -> Self {
+        Self {
+            position: [0.0, 1.0, 5.0],
+            yaw: -std::f32::consts::FRAC_PI_2,
+            pitch: 0.0,
+            fov: 45.0,
+            speed: 2.0,
+            mouse_sensitivity: 0.005,
+            smoothing: 0.0,
+            mouse_look_enabled: true,
+            look_toggle_button: Some(MouseButton::Right),
+            target_yaw: -std::f32::consts::FRAC_PI_2,
+            target_pitch: 0.0,
+            velocity: [0.0; 3],
+            last_cursor: None,
+            last_update: Instant::now(),
+        }
+    }
+}
+
+impl FlyCamera {
+    pub fn new(position: [f32; 3], yaw: f32, pitch: f32) -> Self {
+        Self {
+            position,
+            yaw,
+            pitch,
+            target_yaw: yaw,
+            target_pitch: pitch,
+            ..Default::default()
+        }
+    }
+
+    pub fn forward(&self) -> [f32; 3] {
+        direction(self.yaw, self.pitch)
+    }
+
+    pub fn toggle_mouse_look(&mut self) {
+        self.mouse_look_enabled = !self.mouse_look_enabled;
+        self.last_cursor = None;
+    }
+}
+
+impl CameraController for FlyCamera {
+    fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let cursor = [position.x as f32, position.y as f32];
+                let last = self.last_cursor.replace(cursor);
+                match last {
+                    Some(last) if self.mouse_look_enabled => {
+                        self.target_yaw += (cursor[0] - last[0]) * self.mouse_sensitivity;
+                        self.target_pitch = (self.target_pitch - (cursor[1] - last[1]) * self.mouse_sensitivity)
+                            .clamp(-MAX_PITCH, MAX_PITCH);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            WindowEvent::MouseInput { state: ElementState::Released, button, .. }
+                if Some(*button) == self.look_toggle_button =>
+            {
+                self.toggle_mouse_look();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn update(&mut self, input: &InputState) -> bool {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_update).as_secs_f32();
+        self.last_update = now;
+        let before = (self.position, self.yaw, self.pitch);
+
+        let t = smoothing_factor(self.smoothing, dt);
+        self.yaw += (self.target_yaw - self.yaw) * t;
+        self.pitch += (self.target_pitch - self.pitch) * t;
+
+        let forward = self.forward();
+        let right = normalize(cross(forward, WORLD_UP));
+        let wish = add(
+            add(scale(forward, input.axis("move_z")), scale(right, input.axis("move_x"))),
+            scale(WORLD_UP, input.axis("move_y")),
+        );
+        self.velocity = lerp3(self.velocity, scale(wish, self.speed), t);
+        self.position = add(self.position, scale(self.velocity, dt));
+
+        before != (self.position, self.yaw, self.pitch)
+    }
+
+    fn pose(&self) -> CameraPose {
+        CameraPose {
+            position: self.position,
+            target: add(self.position, self.forward()),
+            fov: self.fov,
+        }
+    }
+
+    fn set_pose(&mut self, pose: CameraPose) {
+        let (yaw, pitch) = yaw_pitch(pose.forward());
+        self.position = pose.position;
+        self.yaw = yaw;
+        self.pitch = pitch.clamp(-MAX_PITCH, MAX_PITCH);
+        self.target_yaw = self.yaw;
+        self.target_pitch = self.pitch;
+        self.fov = pose.fov;
+        self.velocity = [0.0; 3];
+    }
+}
+
+/// Orbits `target`: left drag rotates, right or middle drag pans, the wheel zooms.
+#[derive(Debug, Clone)]
+pub struct OrbitCamera {
+    pub target: [f32; 3],
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub fov: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    // radians per pixel
+    pub rotate_sensitivity: f32,
+    // zoom factor per wheel line
+    pub zoom_speed: f32,
+    // seconds to catch up with the input, 0 is immediate
+    pub smoothing: f32,
+    goal: (f32, f32, f32, [f32; 3]),
+    dragging: Option<MouseButton>,
+    last_cursor: Option<[f32; 2]>,
+    last_update: Instant,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self::new([0.0; 3], 5.0)
+    }
+}
+
+impl OrbitCamera {
+    pub fn new(target: [f32; 3], distance: f32) -> Self {
+        let yaw = std::f32::consts::FRAC_PI_2;
+        let pitch = 0.3;
+        Self {
+            target,
+            distance,
+            yaw,
+            pitch,
+            fov: 45.0,
+            min_distance: 0.01,
+            max_distance: 1000.0,
+            rotate_sensitivity: 0.005,
+            zoom_speed: 0.1,
+            smoothing: 0.0,
+            goal: (yaw, pitch, distance, target),
+            dragging: None,
+            last_cursor: None,
+            last_update: Instant::now(),
+        }
+    }
+
+    pub fn position(&self) -> [f32; 3] {
+        add(self.target, scale(direction(self.yaw, self.pitch), self.distance))
+    }
+}
+
+impl CameraController for OrbitCamera {
+    fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::MouseInput { state, button, .. } => {
+                match state {
+                    ElementState::Pressed if self.dragging.is_none() => self.dragging = Some(*button),
+                    ElementState::Released if self.dragging == Some(*button) => self.dragging = None,
+                    _ => {}
+                }
+                false
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let cursor = [position.x as f32, position.y as f32];
+                let Some(last) = self.last_cursor.replace(cursor) else {
+                    return false;
+                };
+                let (dx, dy) = (cursor[0] - last[0], cursor[1] - last[1]);
+                let (yaw, pitch, distance, target) = &mut self.goal;
+                match self.dragging {
+                    Some(MouseButton::Left) => {
+                        *yaw -= dx * self.rotate_sensitivity;
+                        *pitch = (*pitch + dy * self.rotate_sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+                        true
+                    }
+                    Some(MouseButton::Right | MouseButton::Middle) => {
+                        let forward = scale(direction(*yaw, *pitch), -1.0);
+                        let right = normalize(cross(forward, WORLD_UP));
+                        let up = cross(right, forward);
+                        // about one pixel of the target plane per pixel
+                        let pan = *distance * 0.002;
+                        *target = add(*target, add(scale(right, -dx * pan), scale(up, dy * pan)));
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 100.0,
+                };
+                self.goal.2 = (self.goal.2 * (-lines * self.zoom_speed).exp())
+                    .clamp(self.min_distance, self.max_distance);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn update(&mut self, _input: &InputState) -> bool {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_update).as_secs_f32();
+        self.last_update = now;
+        let before = (self.yaw, self.pitch, self.distance, self.target);
+
+        let t = smoothing_factor(self.smoothing, dt);
+        let (yaw, pitch, distance, target) = self.goal;
+        self.yaw += (yaw - self.yaw) * t;
+        self.pitch += (pitch - self.pitch) * t;
+        self.distance += (distance - self.distance) * t;
+        self.target = lerp3(self.target, target, t);
+
+        before != (self.yaw, self.pitch, self.distance, self.target)
+    }
+
+    fn pose(&self) -> CameraPose {
+        CameraPose {
+            position: self.position(),
+            target: self.target,
+            fov: self.fov,
+        }
+    }
+
+    fn set_pose(&mut self, pose: CameraPose) {
+        let offset = sub(pose.position, pose.target);
+        let (yaw, pitch) = yaw_pitch(offset);
+        self.target = pose.target;
+        self.distance = length(offset).clamp(self.min_distance, self.max_distance);
+        self.yaw = yaw;
+        self.pitch = pitch.clamp(-MAX_PITCH, MAX_PITCH);
+        self.fov = pose.fov;
+        self.goal = (self.yaw, self.pitch, self.distance, self.target);
+    }
+}
+
+/// Camera keyframes over time, linearly interpolated, e.g. to drive an export.
+#[derive(Debug, Clone, Default)]
+pub struct CameraPath {
+    keys: Vec<(f32, CameraPose)>,
+}
+
+impl CameraPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds or replaces the key at `time`
+    pub fn insert(&mut self, time: f32, pose: CameraPose) {
+        match self.keys.binary_search_by(|(t, _)| t.total_cmp(&time)) {
+            Ok(i) => self.keys[i].1 = pose,
+            Err(i) => self.keys.insert(i, (time, pose)),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+
+    pub fn keys(&self) -> &[(f32, CameraPose)] {
+        &self.keys
+    }
+
+    // Pose at `time`, held at the first/last key outside the range
+    pub fn sample(&self, time: f32) -> Option<CameraPose> {
+        let next = self.keys.iter().position(|(t, _)| *t > time);
+        match next {
+            Some(0) => self.keys.first().map(|(_, pose)| *pose),
+            Some(i) => {
+                let (t0, a) = self.keys[i - 1];
+                let (t1, b) = self.keys[i];
+                Some(a.lerp(&b, (time - t0) / (t1 - t0)))
+            }
+            None => self.keys.last().map(|(_, pose)| *pose),
+        }
+    }
+}
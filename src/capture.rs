@@ -0,0 +1,293 @@
+use std::path::Path;
+use crate::{save_frame, save_frame_float, Core, ExportError, ExportManager, ExportSettings, FrameMetadata, OffscreenCapture, RenderKit, ShaderManager, TileInfo};
+
+/// Frame capture for export. A shader only records its passes into an arbitrary view;
+/// the offscreen target, tiling, row padding, readback, BGRA swizzle and the export loop
+/// are handled here.
+pub trait FrameCapture {
+    fn render_kit(&mut self) -> &mut RenderKit;
+
+    /// Record the passes of one export frame into `view`. The time and resolution uniforms
+    /// of the render kit are already set for `time` (and for the current tile).
+    fn render_capture(
+        &mut self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        time: f32,
+        frame: u32,
+    );
+
+    // true when the shader offsets its pixel position by `tile_offset` (see usage.md), so
+    // exports beyond the texture size limit can be rendered in tiles
+    fn supports_tiling(&self) -> bool {
+        false
+    }
+
+    // float texture saved for 16-bit PNG/EXR/HDR exports, e.g. a Rgba16Float compute output
+    fn float_output(&self) -> Option<&wgpu::Texture> {
+        None
+    }
+
+    /// Record the passes that bring `float_output` to the export frame at `time`. The time
+    /// and resolution uniforms are set like for `render_capture`; the texture is resized to
+    /// the export size afterwards if it has another size.
+    fn render_float_capture(&mut self, _core: &Core, _encoder: &mut wgpu::CommandEncoder, _time: f32, _frame: u32) {}
+
+    // extra PNG text entries for screenshots, e.g. the current shader params
+    fn capture_metadata(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    // raw bytes of the params uniform, saved in exported PNGs so a preset can be restored
+    fn preset_params(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    // restores bytes from preset_params, false when they don't fit this shader
+    fn apply_preset_params(&mut self, _core: &Core, _params: &[u8]) -> bool {
+        false
+    }
+
+    // restores state kept outside the params, e.g. a camera pose from capture_metadata
+    fn apply_preset_metadata(&mut self, _core: &Core, _metadata: &FrameMetadata) {}
+
+    // hash of the shader sources, see ShaderHotReload::source_hash
+    fn source_hash(&self) -> Option<u64> {
+        None
+    }
+
+    fn frame_metadata(&self, core: &Core, time: f32, frame: u32, resolution: [u32; 2]) -> FrameMetadata {
+        FrameMetadata {
+            shader: core.window().title(),
+            params: self.preset_params(),
+            time,
+            frame,
+            resolution,
+            source_hash: self.source_hash(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            extra: self.capture_metadata(),
+        }
+    }
+
+    /// Restores the params, time and frame stored in a PNG exported by cuneus. Warns when the
+    /// image comes from another shader or the sources changed since it was saved.
+    fn load_preset(&mut self, core: &Core, path: &Path) -> Result<FrameMetadata, ExportError> {
+        let metadata = FrameMetadata::load(path)?;
+        let title = core.window().title();
+        if metadata.shader != title {
+            log::warn!("Preset was saved from '{}', loading into '{}'", metadata.shader, title);
+        }
+        if let (Some(saved), Some(current)) = (metadata.source_hash, self.source_hash()) {
+            if saved != current {
+                log::warn!("Shader sources changed since the preset was saved, the result may differ");
+            }
+        }
+        restore_state(self, core, &metadata)?;
+        Ok(metadata)
+    }
+
+    /// `ShaderManager::recreate` that keeps the params, the `capture_metadata` state, the time
+    /// and a running export: saves them like a preset, runs `init` on the new device and
+    /// restores them. Bins call it from their `recreate`.
+    fn recreate_keeping_state(&mut self, core: &Core)
+    where
+        Self: ShaderManager + Sized,
+    {
+        let kit = self.render_kit();
+        let (time, frame) = (kit.time_uniform.data.time, kit.time_uniform.data.frame);
+        let export_manager = std::mem::replace(&mut kit.export_manager, ExportManager::new());
+        let metadata = self.frame_metadata(core, time, frame, [core.size.width, core.size.height]);
+        *self = Self::init(core);
+        self.render_kit().export_manager = export_manager;
+        if let Err(e) = restore_state(self, core, &metadata) {
+            log::warn!("Could not restore the shader state after recreating the device: {:?}", e);
+        }
+    }
+
+    // Call once per frame: loads the image picked with "Load Preset from Image" or passed with
+    // --preset, then starts an export queued by --export
+    fn handle_preset_request(&mut self, core: &Core) {
+        if let Some(path) = self.render_kit().export_manager.take_preset_request() {
+            match self.load_preset(core, &path) {
+                Ok(metadata) => log::info!("Loaded preset from {} (time {:.3}, frame {})", path.display(), metadata.time, metadata.frame),
+                Err(e) => log::error!("Error loading preset from {}: {:?}", path.display(), e),
+            }
+        }
+        // a command line export waits for the startup preset
+        if self.render_kit().export_manager.take_queued_export() {
+            self.render_kit().export_manager.start_export();
+        }
+    }
+
+    // Renders one frame at the export settings, returns pixels in CAPTURE_FORMAT order
+    fn capture_frame(&mut self, core: &Core, time: f32, frame: u32) -> Result<Vec<u8>, ExportError> {
+        let settings = self.render_kit().export_manager.settings().clone();
+        capture_with_settings(self, core, time, frame, &settings)
+    }
+
+    /// Call once per frame: takes a screenshot of the current frame (without egui) when F12
+    /// was pressed or `screenshot_manager.request()` was called.
+    fn handle_screenshot(&mut self, core: &Core) {
+        let kit = self.render_kit();
+        let requested = kit.key_handler.take_screenshot_request();
+        if !(kit.screenshot_manager.take_request() || requested) {
+            return;
+        }
+        let [width, height] = kit.screenshot_manager.capture_size(core.size);
+        let time = kit.time_uniform.data.time;
+        let frame = kit.time_uniform.data.frame;
+        let settings = ExportSettings {
+            width,
+            height,
+            supersample: kit.screenshot_manager.settings().supersample,
+            ..kit.export_manager.settings().clone()
+        };
+
+        let metadata = self.frame_metadata(core, time, frame, [width, height]).to_text_chunks();
+
+        let result = capture_with_settings(self, core, time, frame, &settings)
+            .and_then(|data| self.render_kit().screenshot_manager.save(data, width, height, &metadata));
+        match result {
+            Ok(path) => log::info!("Screenshot saved to {}", path.display()),
+            Err(e) => log::error!("Error taking screenshot: {:?}", e),
+        }
+    }
+
+    /// Call once per frame while `export_manager.is_exporting()`: captures and saves the next
+    /// export frame, or completes the export when all frames are done.
+    fn handle_export(&mut self, core: &Core) {
+        let Some((frame, time)) = self.render_kit().export_manager.try_get_next_frame() else {
+            self.render_kit().export_manager.complete_export();
+            if self.render_kit().export_manager.exit_when_done() {
+                log::info!("Export finished");
+                core.request_exit();
+            }
+            return;
+        };
+        let mut settings = self.render_kit().export_manager.settings().clone();
+        settings.metadata = Some(self.frame_metadata(core, time, frame, [settings.width, settings.height]));
+        // without a float output, float formats fall back to the 8-bit PNG capture
+        let result = if settings.format.is_float() && self.float_output().is_some() {
+            capture_float_frame(self, core, time, frame, &settings)
+                .and_then(|pixels| save_frame_float(&pixels, settings.width, settings.height, frame, &settings))
+        } else {
+            self.capture_frame(core, time, frame)
+                .and_then(|data| save_frame(data, frame, &settings))
+        };
+        if let Err(e) = result {
+            log::error!("Error exporting frame {}: {:?}", frame, e);
+        }
+    }
+}
+
+// Applies the params, extra metadata, time and frame of a preset
+fn restore_state<S: FrameCapture + ?Sized>(shader: &mut S, core: &Core, metadata: &FrameMetadata) -> Result<(), ExportError> {
+    if let Some(params) = &metadata.params {
+        if !shader.apply_preset_params(core, params) {
+            return Err(ExportError::InvalidMetadata(format!(
+                "{} bytes of params don't match this shader",
+                params.len()
+            )));
+        }
+    }
+    shader.apply_preset_metadata(core, metadata);
+    let kit = shader.render_kit();
+    kit.controls.seek(&mut kit.start_time, metadata.time, metadata.frame);
+    kit.time_uniform.data.time = metadata.time;
+    kit.time_uniform.data.frame = metadata.frame;
+    kit.time_uniform.update(&core.queue);
+    Ok(())
+}
+
+// Renders float_output for the frame at `time` and reads it back at the export size
+fn capture_float_frame<S: FrameCapture + ?Sized>(
+    shader: &mut S,
+    core: &Core,
+    time: f32,
+    frame: u32,
+    settings: &ExportSettings,
+) -> Result<Vec<f32>, ExportError> {
+    let size = [settings.width, settings.height];
+    let tile = TileInfo {
+        offset: [0, 0],
+        size,
+        full_size: size,
+        supersample: 1,
+    };
+    shader.render_kit().set_capture_uniforms(core, time, &tile);
+    let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Float Capture Encoder"),
+    });
+    shader.render_float_capture(core, &mut encoder, time, frame);
+    let capture = shader.render_kit().take_offscreen_capture(core);
+    let result = match shader.float_output() {
+        Some(texture) => capture.resample_float(&core.device, &core.queue, encoder, texture, size),
+        None => Err(ExportError::Unsupported("no float output".to_string())),
+    };
+    shader.render_kit().finish_capture(core, capture);
+    result
+}
+
+// Offscreen capture with the size, supersampling and tiling of `settings`
+fn capture_with_settings<S: FrameCapture + ?Sized>(
+    shader: &mut S,
+    core: &Core,
+    time: f32,
+    frame: u32,
+    settings: &ExportSettings,
+) -> Result<Vec<u8>, ExportError> {
+    let tiled = OffscreenCapture::needs_tiling(
+        &core.device,
+        settings.width,
+        settings.height,
+        settings.supersample,
+        settings.max_tile_size,
+    );
+    if tiled && !shader.supports_tiling() {
+        return Err(ExportError::Unsupported(format!(
+            "{}x{} at {}x supersampling needs tiling, which this shader doesn't support; lower the size or supersampling",
+            settings.width, settings.height, settings.supersample
+        )));
+    }
+    let mut capture = shader.render_kit().take_offscreen_capture(core);
+    let result = capture.capture(
+        &core.device,
+        &core.queue,
+        settings.width,
+        settings.height,
+        settings.supersample,
+        settings.downsample_filter,
+        settings.max_tile_size,
+        |encoder, view, tile| {
+            shader.render_kit().set_capture_uniforms(core, time, tile);
+            shader.render_capture(core, encoder, view, time, frame);
+        },
+    );
+    shader.render_kit().finish_capture(core, capture);
+    result
+}
+
+/// Implements `preset_params`, `apply_preset_params` and `source_hash` of `FrameCapture` for
+/// a shader whose params live in one `UniformBinding`, inside the `impl FrameCapture` block:
+/// `cuneus::impl_frame_capture_presets!(params_uniform, hot_reload);`. Leave out the hot reload
+/// field to write `source_hash` yourself.
+#[macro_export]
+macro_rules! impl_frame_capture_presets {
+    ($params:ident, $hot_reload:ident) => {
+        $crate::impl_frame_capture_presets!($params);
+
+        fn source_hash(&self) -> Option<u64> {
+            Some(self.$hot_reload.source_hash())
+        }
+    };
+    ($params:ident) => {
+        fn preset_params(&self) -> Option<Vec<u8>> {
+            Some($crate::UniformProvider::as_bytes(&self.$params.data).to_vec())
+        }
+
+        fn apply_preset_params(&mut self, core: &$crate::Core, params: &[u8]) -> bool {
+            self.$params.set_from_bytes(&core.queue, params)
+        }
+    };
+}
@@ -0,0 +1,369 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use crate::ExportFormat;
+
+static OPTIONS: OnceLock<CliOptions> = OnceLock::new();
+static STARTUP_REQUESTS_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// Frames to render without a visible window before exiting, from `--export START:END`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportRange {
+    pub start: f32,
+    pub end: f32,
+    pub fps: Option<u32>,
+    pub size: Option<(u32, u32)>,
+    pub path: Option<PathBuf>,
+    pub format: Option<ExportFormat>,
+}
+
+/// Options every shader binary accepts, parsed once by `ShaderApp::new`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliOptions {
+    pub size: Option<(u32, u32)>,
+    pub fullscreen: bool,
+    pub present_mode: Option<wgpu::PresentMode>,
+    pub backends: Option<wgpu::Backends>,
+    pub adapter: Option<String>,
+    pub power_preference: Option<wgpu::PowerPreference>,
+    pub hdr: bool,
+    pub media: Option<PathBuf>,
+    pub preset: Option<PathBuf>,
+    pub paused: bool,
+    pub export: Option<ExportRange>,
+    pub no_ui: bool,
+    pub control_window: bool,
+    pub borderless: bool,
+    pub transparent: bool,
+    pub always_on_top: Option<bool>,
+    pub max_fps: Option<f32>,
+    pub unfocused_fps: Option<f32>,
+    pub on_demand: bool,
+    pub idle_when_paused: Option<bool>,
+    pub position: Option<(i32, i32)>,
+    pub monitor: Option<usize>,
+    // everything after `--`, left for the binary itself
+    pub extra: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliError {
+    MissingValue(String),
+    InvalidValue { flag: String, value: String },
+    UnknownFlag(String),
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::MissingValue(flag) => write!(f, "{} needs a value", flag),
+            CliError::InvalidValue { flag, value } => write!(f, "invalid value '{}' for {}", value, flag),
+            CliError::UnknownFlag(flag) => write!(f, "unknown option '{}'", flag),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+pub const USAGE: &str = "\
+Options:
+  -s, --size WxH             initial window size
+  -f, --fullscreen           start in borderless fullscreen
+      --monitor N            open (and go fullscreen) on monitor N, counting from 0
+      --position X,Y         window position in pixels, relative to --monitor when given
+      --borderless           window without decorations
+      --transparent          transparent window, shaders write premultiplied alpha
+      --always-on-top on|off keep the window above others (on by default)
+      --vsync on|off         shortcut for --present-mode auto-vsync / auto-no-vsync
+      --present-mode MODE    fifo, fifo-relaxed, mailbox, immediate, auto-vsync, auto-no-vsync
+      --backend LIST         vulkan, dx12, metal, gl, primary or all, comma separated
+      --adapter NAME         use the first adapter whose name contains NAME
+      --power low|high       prefer the integrated or the discrete GPU
+      --hdr                  Rgba16Float surface when the display supports it
+      --max-fps N            cap the frame rate
+      --unfocused-fps N      cap the frame rate while no window has focus
+      --on-demand            draw only after input or when the shader asks for a frame
+      --idle-when-paused on|off stop drawing while time is paused, except after input (on by default)
+      --media PATH           image, video or HDRI to load at startup (overrides CUNEUS_MEDIA)
+      --preset PATH          PNG exported by cuneus to restore parameters and time from
+      --paused               start with time paused
+      --export START:END     render frames for that time range without a window, then exit
+      --fps N                frame rate of --export
+      --export-size WxH      resolution of --export
+      --output DIR           directory of --export
+      --format FORMAT        png8, png16, exr-half, exr-float or hdr for --export
+      --no-ui                hide the egui panels (toggle back with H)
+      --control-window       put the egui panels in their own window
+  -h, --help                 print this help
+  --                         pass the remaining arguments to the binary";
+
+fn parse_size(flag: &str, value: &str) -> Result<(u32, u32), CliError> {
+    let invalid = || CliError::InvalidValue { flag: flag.to_string(), value: value.to_string() };
+    let (width, height) = value.split_once(['x', 'X']).ok_or_else(invalid)?;
+    match (width.trim().parse::<u32>(), height.trim().parse::<u32>()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_present_mode(flag: &str, value: &str) -> Result<wgpu::PresentMode, CliError> {
+    match value.to_ascii_lowercase().as_str() {
+        "fifo" => Ok(wgpu::PresentMode::Fifo),
+        "fifo-relaxed" => Ok(wgpu::PresentMode::FifoRelaxed),
+        "mailbox" => Ok(wgpu::PresentMode::Mailbox),
+        "immediate" => Ok(wgpu::PresentMode::Immediate),
+        "auto-vsync" | "auto" => Ok(wgpu::PresentMode::AutoVsync),
+        "auto-no-vsync" => Ok(wgpu::PresentMode::AutoNoVsync),
+        _ => Err(CliError::InvalidValue { flag: flag.to_string(), value: value.to_string() }),
+    }
+}
+
+fn parse_backends(flag: &str, value: &str) -> Result<wgpu::Backends, CliError> {
+    let mut backends = wgpu::Backends::empty();
+    for name in value.split(',') {
+        backends |= match name.trim().to_ascii_lowercase().as_str() {
+            "vulkan" | "vk" => wgpu::Backends::VULKAN,
+            "dx12" | "d3d12" => wgpu::Backends::DX12,
+            "metal" | "mtl" => wgpu::Backends::METAL,
+            "gl" | "opengl" | "gles" => wgpu::Backends::GL,
+            "primary" => wgpu::Backends::PRIMARY,
+            "all" => wgpu::Backends::all(),
+            _ => return Err(CliError::InvalidValue { flag: flag.to_string(), value: value.to_string() }),
+        };
+    }
+    Ok(backends)
+}
+
+fn parse_format(flag: &str, value: &str) -> Result<ExportFormat, CliError> {
+    match value.to_ascii_lowercase().as_str() {
+        "png" | "png8" => Ok(ExportFormat::Png8),
+        "png16" => Ok(ExportFormat::Png16),
+        "exr" | "exr-half" => Ok(ExportFormat::ExrHalf),
+        "exr-float" => Ok(ExportFormat::ExrFloat),
+        "hdr" => Ok(ExportFormat::Hdr),
+        _ => Err(CliError::InvalidValue { flag: flag.to_string(), value: value.to_string() }),
+    }
+}
+
+impl CliOptions {
+    /// Parses arguments without the program name. Values go after a space or `=`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, CliError> {
+        let mut options = Self::default();
+        let mut range: Option<(f32, f32)> = None;
+        let (mut fps, mut export_size, mut path, mut format) = (None, None, None, None);
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                options.extra = args.by_ref().collect();
+                break;
+            }
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| CliError::MissingValue(flag.clone()))
+            };
+            match flag.as_str() {
+                "-s" | "--size" => options.size = Some(parse_size(&flag, &value()?)?),
+                "-f" | "--fullscreen" => options.fullscreen = true,
+                "--monitor" => {
+                    let value = value()?;
+                    options.monitor = Some(value.parse::<usize>().map_err(|_| CliError::InvalidValue { flag, value })?);
+                }
+                "--position" => {
+                    let value = value()?;
+                    let parsed = value
+                        .split_once(',')
+                        .and_then(|(x, y)| Some((x.trim().parse::<i32>().ok()?, y.trim().parse::<i32>().ok()?)));
+                    options.position = Some(parsed.ok_or(CliError::InvalidValue { flag, value })?);
+                }
+                "--max-fps" | "--unfocused-fps" => {
+                    let value = value()?;
+                    let fps = value.parse::<f32>().ok().filter(|fps| *fps > 0.0);
+                    let fps = Some(fps.ok_or(CliError::InvalidValue { flag: flag.clone(), value })?);
+                    if flag == "--max-fps" {
+                        options.max_fps = fps;
+                    } else {
+                        options.unfocused_fps = fps;
+                    }
+                }
+                "--on-demand" => options.on_demand = true,
+                "--idle-when-paused" => {
+                    let value = value()?;
+                    options.idle_when_paused = Some(match value.to_ascii_lowercase().as_str() {
+                        "on" | "true" | "1" => true,
+                        "off" | "false" | "0" => false,
+                        _ => return Err(CliError::InvalidValue { flag, value }),
+                    });
+                }
+                "--borderless" => options.borderless = true,
+                "--transparent" => options.transparent = true,
+                "--always-on-top" => {
+                    let value = value()?;
+                    options.always_on_top = Some(match value.to_ascii_lowercase().as_str() {
+                        "on" | "true" | "1" => true,
+                        "off" | "false" | "0" => false,
+                        _ => return Err(CliError::InvalidValue { flag, value }),
+                    });
+                }
+                "--vsync" => {
+                    let value = value()?;
+                    options.present_mode = Some(match value.to_ascii_lowercase().as_str() {
+                        "on" | "true" | "1" => wgpu::PresentMode::AutoVsync,
+                        "off" | "false" | "0" => wgpu::PresentMode::AutoNoVsync,
+                        _ => return Err(CliError::InvalidValue { flag, value }),
+                    });
+                }
+                "--present-mode" => options.present_mode = Some(parse_present_mode(&flag, &value()?)?),
+                "--backend" => options.backends = Some(parse_backends(&flag, &value()?)?),
+                "--adapter" => options.adapter = Some(value()?),
+                "--power" => {
+                    let value = value()?;
+                    options.power_preference = Some(match value.to_ascii_lowercase().as_str() {
+                        "low" | "low-power" | "integrated" => wgpu::PowerPreference::LowPower,
+                        "high" | "high-performance" | "discrete" => wgpu::PowerPreference::HighPerformance,
+                        _ => return Err(CliError::InvalidValue { flag, value }),
+                    });
+                }
+                "--hdr" => options.hdr = true,
+                "--media" => options.media = Some(PathBuf::from(value()?)),
+                "--preset" => options.preset = Some(PathBuf::from(value()?)),
+                "--paused" => options.paused = true,
+                "--export" => {
+                    let value = value()?;
+                    let parsed = value
+                        .split_once(':')
+                        .and_then(|(start, end)| Some((start.trim().parse::<f32>().ok()?, end.trim().parse::<f32>().ok()?)))
+                        .filter(|(start, end)| end > start && *start >= 0.0);
+                    range = Some(parsed.ok_or(CliError::InvalidValue { flag, value })?);
+                }
+                "--fps" => {
+                    let value = value()?;
+                    fps = Some(value.parse::<u32>().ok().filter(|fps| *fps > 0).ok_or(CliError::InvalidValue { flag, value })?);
+                }
+                "--export-size" => export_size = Some(parse_size(&flag, &value()?)?),
+                "--output" => path = Some(PathBuf::from(value()?)),
+                "--format" => format = Some(parse_format(&flag, &value()?)?),
+                "--no-ui" => options.no_ui = true,
+                "--control-window" => options.control_window = true,
+                _ => return Err(CliError::UnknownFlag(arg)),
+            }
+        }
+
+        options.export = range.map(|(start, end)| ExportRange { start, end, fps, size: export_size, path, format });
+        Ok(options)
+    }
+
+    /// Parses the process arguments, printing usage and exiting on `--help` or errors.
+    pub fn from_env() -> Self {
+        let mut args = std::env::args();
+        let program = args.next().unwrap_or_else(|| "cuneus".to_string());
+        let args: Vec<String> = args.collect();
+        if args.iter().take_while(|arg| *arg != "--").any(|arg| arg == "-h" || arg == "--help") {
+            println!("Usage: {} [options]\n\n{}", program, USAGE);
+            std::process::exit(0);
+        }
+        match Self::parse(args) {
+            Ok(options) => options,
+            Err(e) => {
+                eprintln!("{}: {}\n\nUsage: {} [options]\n\n{}", program, e, program, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// True when the binary should run without showing a window.
+    pub fn headless(&self) -> bool {
+        self.export.is_some()
+    }
+
+    /// Options of this process, defaults when `ShaderApp::new` did not parse any.
+    pub fn current() -> &'static CliOptions {
+        OPTIONS.get_or_init(CliOptions::default)
+    }
+
+    // true for the first caller only, so a shader recreated after a device loss does not
+    // queue --preset and --export again
+    pub(crate) fn take_startup_requests() -> bool {
+        !STARTUP_REQUESTS_TAKEN.swap(true, Ordering::Relaxed)
+    }
+
+    // Called by ShaderApp::new, later calls keep the first options
+    pub(crate) fn install(self) -> &'static CliOptions {
+        let _ = OPTIONS.set(self);
+        Self::current()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliOptions, CliError> {
+        CliOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_give_the_defaults() {
+        assert_eq!(parse(&[]), Ok(CliOptions::default()));
+    }
+
+    #[test]
+    fn values_follow_a_space_or_an_equals_sign() {
+        let options = parse(&["-s", "1280x720", "--present-mode=mailbox", "--position", "10,-20", "--fullscreen"]).unwrap();
+        assert_eq!(options.size, Some((1280, 720)));
+        assert_eq!(options.present_mode, Some(wgpu::PresentMode::Mailbox));
+        assert_eq!(options.position, Some((10, -20)));
+        assert!(options.fullscreen);
+    }
+
+    #[test]
+    fn export_collects_its_settings() {
+        let options = parse(&["--export", "1.5:4", "--fps=30", "--export-size", "640X480", "--format", "exr-float", "--output", "out"]).unwrap();
+        assert_eq!(
+            options.export,
+            Some(ExportRange {
+                start: 1.5,
+                end: 4.0,
+                fps: Some(30),
+                size: Some((640, 480)),
+                path: Some(PathBuf::from("out")),
+                format: Some(ExportFormat::ExrFloat),
+            })
+        );
+        assert!(options.headless());
+        // export settings alone do not start an export
+        assert_eq!(parse(&["--fps", "30"]).unwrap().export, None);
+    }
+
+    #[test]
+    fn backends_and_vsync_shortcuts() {
+        let options = parse(&["--backend", "vulkan, gl", "--vsync", "off"]).unwrap();
+        assert_eq!(options.backends, Some(wgpu::Backends::VULKAN | wgpu::Backends::GL));
+        assert_eq!(options.present_mode, Some(wgpu::PresentMode::AutoNoVsync));
+    }
+
+    #[test]
+    fn arguments_after_double_dash_are_left_alone() {
+        let options = parse(&["--paused", "--", "--size", "nonsense"]).unwrap();
+        assert!(options.paused);
+        assert_eq!(options.size, None);
+        assert_eq!(options.extra, vec!["--size".to_string(), "nonsense".to_string()]);
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(parse(&["--size"]), Err(CliError::MissingValue("--size".to_string())));
+        assert_eq!(parse(&["--bogus"]), Err(CliError::UnknownFlag("--bogus".to_string())));
+        let invalid = |flag: &str, value: &str| Err(CliError::InvalidValue { flag: flag.to_string(), value: value.to_string() });
+        assert_eq!(parse(&["--size", "0x10"]), invalid("--size", "0x10"));
+        assert_eq!(parse(&["--export=5:2"]), invalid("--export", "5:2"));
+        assert_eq!(parse(&["--max-fps", "-1"]), invalid("--max-fps", "-1"));
+        assert_eq!(parse(&["--backend", "vulkan,glide"]), invalid("--backend", "vulkan,glide"));
+    }
+}
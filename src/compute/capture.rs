@@ -0,0 +1,103 @@
+/// Output a `ComputeShader` is not drawing into right now: the export-size output between
+/// captures, the live one while a capture renders. Swapping instead of resizing keeps the
+/// live output, atomic buffer and stage textures, so accumulation survives a screenshot.
+pub(crate) struct CaptureSwap<T> {
+    parked: Option<T>,
+    capturing: bool,
+}
+
+impl<T> Default for CaptureSwap<T> {
+    fn default() -> Self {
+        Self {
+            parked: None,
+            capturing: false,
+        }
+    }
+}
+
+impl<T> CaptureSwap<T> {
+    pub fn is_capturing(&self) -> bool {
+        self.capturing
+    }
+
+    // Puts an export output in place of `live`, reusing the parked one when `fits` it
+    pub fn begin(&mut self, live: &mut T, fits: impl Fn(&T) -> bool, create: impl FnOnce() -> T) {
+        if self.capturing {
+            if fits(live) {
+                return;
+            }
+            self.end(live);
+        }
+        let export = match self.parked.take() {
+            Some(parked) if fits(&parked) => parked,
+            _ => create(),
+        };
+        self.parked = Some(std::mem::replace(live, export));
+        self.capturing = true;
+    }
+
+    // Puts the live output back and keeps the export one for the next capture
+    pub fn end(&mut self, live: &mut T) {
+        if !self.capturing {
+            return;
+        }
+        if let Some(parked) = self.parked.take() {
+            self.parked = Some(std::mem::replace(live, parked));
+        }
+        self.capturing = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (id, size), the id tells a kept output from a recreated one
+    type Output = (u32, [u32; 2]);
+
+    fn begin(swap: &mut CaptureSwap<Output>, live: &mut Output, size: [u32; 2], next_id: &mut u32) {
+        swap.begin(live, |output| output.1 == size, || {
+            *next_id += 1;
+            (*next_id, size)
+        });
+    }
+
+    #[test]
+    fn live_output_comes_back_untouched() {
+        let mut swap = CaptureSwap::default();
+        let mut live = (1, [640, 360]);
+        let mut next_id = 1;
+        begin(&mut swap, &mut live, [1920, 1080], &mut next_id);
+        assert!(swap.is_capturing());
+        assert_eq!(live, (2, [1920, 1080]));
+        swap.end(&mut live);
+        assert!(!swap.is_capturing());
+        assert_eq!(live, (1, [640, 360]));
+    }
+
+    #[test]
+    fn export_output_is_reused_while_the_size_holds() {
+        let mut swap = CaptureSwap::default();
+        let mut live = (1, [640, 360]);
+        let mut next_id = 1;
+        for _ in 0..3 {
+            begin(&mut swap, &mut live, [1920, 1080], &mut next_id);
+            // a second tile of the same capture keeps the output
+            begin(&mut swap, &mut live, [1920, 1080], &mut next_id);
+            assert_eq!(live.0, 2);
+            swap.end(&mut live);
+        }
+        begin(&mut swap, &mut live, [3840, 2160], &mut next_id);
+        assert_eq!(live, (3, [3840, 2160]));
+        swap.end(&mut live);
+        assert_eq!(live, (1, [640, 360]));
+    }
+
+    #[test]
+    fn end_without_begin_keeps_the_live_output() {
+        let mut swap = CaptureSwap::<Output>::default();
+        let mut live = (1, [640, 360]);
+        swap.end(&mut live);
+        assert_eq!(live, (1, [640, 360]));
+    }
+}
@@ -0,0 +1,70 @@
+use wgpu::util::DeviceExt;
+
+/// Size of the indirect args buffer: workgroup counts (x, y, z) + one spare slot
+/// that compaction passes use to store the element count for the next pass.
+pub const INDIRECT_DISPATCH_BUFFER_SIZE: u64 = 16;
+
+pub struct IndirectDispatchBuffer {
+    // bound read_write so passes can write the counts
+    pub buffer: wgpu::Buffer,
+    // INDIRECT-only copy the dispatch reads, a buffer can't be bound as storage and
+    // used for indirect args in the same dispatch
+    pub args: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+    pub initial_count: [u32; 3],
+}
+
+impl IndirectDispatchBuffer {
+    pub fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        initial_count: [u32; 3],
+        label: &str,
+    ) -> Self {
+        let contents = [initial_count[0], initial_count[1], initial_count[2], 0u32];
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Indirect Dispatch Buffer", label)),
+            contents: bytemuck::cast_slice(&contents),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::INDIRECT
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+        });
+        let args = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Indirect Args Buffer", label)),
+            contents: bytemuck::cast_slice(&contents),
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some(&format!("{} Indirect Dispatch Bind Group", label)),
+        });
+
+        Self {
+            buffer,
+            args,
+            bind_group,
+            initial_count,
+        }
+    }
+
+    // Overwrite the workgroup counts from the CPU, e.g. when restarting an emitter
+    pub fn set_count(&self, queue: &wgpu::Queue, count: [u32; 3]) {
+        let contents = [count[0], count[1], count[2], 0u32];
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&contents));
+    }
+
+    // Snapshot of the counts written so far, call between passes before dispatching from `args`
+    pub fn copy_args(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.copy_buffer_to_buffer(&self.buffer, 0, &self.args, 0, 12);
+    }
+
+    pub fn reset(&self, queue: &wgpu::Queue) {
+        self.set_count(queue, self.initial_count);
+    }
+}
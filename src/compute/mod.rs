@@ -1,10 +1,76 @@
-use crate::{Core, UniformProvider, UniformBinding, TextureManager, ShaderHotReload, AtomicBuffer, FontSystem};
+use crate::{Core, UniformProvider, UniformBinding, TextureManager, ShaderHotReload, AtomicBuffer, FontSystem, GlyphMode, TextBlock, TextLayout, GpuProfiler, GpuMemoryStats};
 use std::sync::Arc;
 use std::path::PathBuf;
 use log::{info, warn};
 
+mod capture;
+mod indirect;
+mod scan;
+mod stages;
+
+use capture::CaptureSwap;
+pub use indirect::*;
+pub use scan::*;
+pub use stages::*;
+
 pub const COMPUTE_TEXTURE_FORMAT_RGBA16: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
 pub const COMPUTE_TEXTURE_FORMAT_RGBA8: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+pub const COMPUTE_TEXTURE_FORMAT_R32F: wgpu::TextureFormat = wgpu::TextureFormat::R32Float;
+pub const COMPUTE_TEXTURE_FORMAT_RG32F: wgpu::TextureFormat = wgpu::TextureFormat::Rg32Float;
+pub const COMPUTE_TEXTURE_FORMAT_RGBA32F: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+
+// Shape of a storage texture: plain 2D, 2D array with N layers or 3D with N depth slices
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StorageTextureDimension {
+    D2,
+    D2Array(u32),
+    D3(u32),
+}
+
+impl StorageTextureDimension {
+    pub fn depth_or_array_layers(&self) -> u32 {
+        match self {
+            StorageTextureDimension::D2 => 1,
+            StorageTextureDimension::D2Array(layers) => (*layers).max(1),
+            StorageTextureDimension::D3(depth) => (*depth).max(1),
+        }
+    }
+
+    pub fn texture_dimension(&self) -> wgpu::TextureDimension {
+        match self {
+            StorageTextureDimension::D3(_) => wgpu::TextureDimension::D3,
+            _ => wgpu::TextureDimension::D2,
+        }
+    }
+
+    pub fn view_dimension(&self) -> wgpu::TextureViewDimension {
+        match self {
+            StorageTextureDimension::D2 => wgpu::TextureViewDimension::D2,
+            StorageTextureDimension::D2Array(_) => wgpu::TextureViewDimension::D2Array,
+            StorageTextureDimension::D3(_) => wgpu::TextureViewDimension::D3,
+        }
+    }
+}
+
+// Everything needed to create a storage texture and its layout entry.
+// Note: ReadWrite access works for the R32 formats everywhere, other formats need
+// TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES on the device.
+#[derive(Copy, Clone, Debug)]
+pub struct StorageTextureSpec {
+    pub format: wgpu::TextureFormat,
+    pub access: wgpu::StorageTextureAccess,
+    pub dimension: StorageTextureDimension,
+}
+
+impl Default for StorageTextureSpec {
+    fn default() -> Self {
+        Self {
+            format: COMPUTE_TEXTURE_FORMAT_RGBA16,
+            access: wgpu::StorageTextureAccess::WriteOnly,
+            dimension: StorageTextureDimension::D2,
+        }
+    }
+}
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -26,6 +92,8 @@ pub struct ComputeShaderConfig {
     pub workgroup_count: Option<[u32; 3]>,
     pub dispatch_once: bool,
     pub storage_texture_format: wgpu::TextureFormat,
+    // the main output stays 2D because it is blitted to the screen, but it can be read-write
+    pub storage_texture_access: wgpu::StorageTextureAccess,
     pub enable_atomic_buffer: bool,
     pub atomic_buffer_multiples: usize,
     pub entry_points: Vec<String>,
@@ -33,7 +101,20 @@ pub struct ComputeShaderConfig {
     pub sampler_filter_mode: wgpu::FilterMode,
     pub label: String,
     pub mouse_bind_group_layout: Option<wgpu::BindGroupLayout>,
-    pub enable_fonts: bool,}
+    pub enable_fonts: bool,
+    // coverage by default, Sdf/Msdf atlases stay sharp at any scale
+    pub font_glyph_mode: GlyphMode,
+    // GPU-driven dispatch: entry points listed in `indirect_entry_points` read their
+    // workgroup counts from the indirect buffer, which earlier passes can write to.
+    pub enable_indirect_dispatch: bool,
+    pub indirect_entry_points: Vec<String>,
+    pub indirect_initial_count: [u32; 3],
+    // Multi-stage mode: when `stages` is not empty it replaces `entry_points`, every stage
+    // gets its own dispatch size, resources and iteration count, and `dispatch` runs them in order.
+    pub stages: Vec<ComputeStage>,
+    pub textures: Vec<ComputeTextureDesc>,
+    pub buffers: Vec<ComputeBufferDesc>,
+}
 
 impl Default for ComputeShaderConfig {
     fn default() -> Self {
@@ -42,6 +123,7 @@ impl Default for ComputeShaderConfig {
             workgroup_count: None,
             dispatch_once: false,
             storage_texture_format: COMPUTE_TEXTURE_FORMAT_RGBA16,
+            storage_texture_access: wgpu::StorageTextureAccess::WriteOnly,
             enable_atomic_buffer: false,
             atomic_buffer_multiples: 4,
             entry_points: vec!["main".to_string()],
@@ -50,19 +132,86 @@ impl Default for ComputeShaderConfig {
             label: "Compute Shader".to_string(),
             mouse_bind_group_layout: None,
             enable_fonts: false,
+            font_glyph_mode: GlyphMode::Coverage,
+            enable_indirect_dispatch: false,
+            indirect_entry_points: Vec::new(),
+            indirect_initial_count: [1, 1, 1],
+            stages: Vec::new(),
+            textures: Vec::new(),
+            buffers: Vec::new(),
+        }
+    }
+}
+
+impl ComputeShaderConfig {
+    // time + output, then one group per enabled option in pipeline layout order:
+    // mouse, fonts, external textures (more than one entry point), atomic buffer, stage resources, indirect args
+    pub fn bind_group_count(&self) -> u32 {
+        let optional = [
+            self.mouse_bind_group_layout.is_some(),
+            self.enable_fonts,
+            self.entry_points.len() > 1,
+            self.enable_atomic_buffer,
+            !self.stages.is_empty(),
+            self.enable_indirect_dispatch,
+        ];
+        2 + optional.iter().filter(|enabled| **enabled).count() as u32
+    }
+
+    pub fn validate(&self, limits: &wgpu::Limits) -> Result<(), ComputeConfigError> {
+        let needed = self.bind_group_count();
+        if needed > limits.max_bind_groups {
+            return Err(ComputeConfigError::TooManyBindGroups { needed, max: limits.max_bind_groups });
+        }
+        for stage in &self.stages {
+            stage.validate(&self.textures, self.enable_indirect_dispatch)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum ComputeConfigError {
+    // the enabled bindings need more bind groups than the device has (WebGPU guarantees 4)
+    TooManyBindGroups { needed: u32, max: u32 },
+    // a stage samples a texture side it also writes
+    StageTextureConflict { stage: String, texture: String },
+    IndirectStageWithoutBuffer { stage: String },
+}
+
+impl std::fmt::Display for ComputeConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComputeConfigError::TooManyBindGroups { needed, max } => write!(
+                f,
+                "Compute shader needs {} bind groups but the device only supports {}, disable mouse, fonts, atomics or indirect dispatch",
+                needed, max
+            ),
+            ComputeConfigError::StageTextureConflict { stage, texture } => write!(
+                f,
+                "Stage '{}' reads and writes the same side of '{}', make it double buffered or use ReadWriteTexture only",
+                stage, texture
+            ),
+            ComputeConfigError::IndirectStageWithoutBuffer { stage } => {
+                write!(f, "Stage '{}' uses indirect dispatch but enable_indirect_dispatch is off", stage)
+            }
         }
     }
 }
 
+impl std::error::Error for ComputeConfigError {}
+
 //bind group layout types for different shader needs
 pub enum BindGroupLayoutType {
     StorageTexture,
+    StorageTextureWithSpec(StorageTextureSpec),
     TimeUniform,
     CustomUniform,
     AtomicBuffer,
     ExternalTexture,
     MouseUniform,
     FontTexture,
+    IndirectDispatch,
 }
 
 pub fn create_storage_texture(
@@ -71,23 +220,48 @@ pub fn create_storage_texture(
     height: u32, 
     format: wgpu::TextureFormat,
     label: &str
+) -> wgpu::Texture {
+    let spec = StorageTextureSpec {
+        format,
+        ..Default::default()
+    };
+    create_storage_texture_with_spec(device, width, height, &spec, label)
+}
+
+pub fn create_storage_texture_with_spec(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    spec: &StorageTextureSpec,
+    label: &str,
 ) -> wgpu::Texture {
     device.create_texture(&wgpu::TextureDescriptor {
         label: Some(label),
         size: wgpu::Extent3d {
             width,
             height,
-            depth_or_array_layers: 1,
+            depth_or_array_layers: spec.dimension.depth_or_array_layers(),
         },
         mip_level_count: 1,
         sample_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format,
-        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+        dimension: spec.dimension.texture_dimension(),
+        format: spec.format,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::STORAGE_BINDING
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::COPY_DST,
         view_formats: &[],
     })
 }
 
+// View with the right dimension, the default view would pick D2 for a one-layer array
+pub fn create_storage_texture_view(texture: &wgpu::Texture, spec: &StorageTextureSpec) -> wgpu::TextureView {
+    texture.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(spec.dimension.view_dimension()),
+        ..Default::default()
+    })
+}
+
 pub fn create_output_texture(
     device: &wgpu::Device,
     width: u32,
@@ -156,6 +330,23 @@ pub fn create_bind_group_layout(
                 ],
             })
         },
+        BindGroupLayoutType::StorageTextureWithSpec(spec) => {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some(&format!("{} Storage Texture Layout", label)),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::StorageTexture {
+                            access: spec.access,
+                            format: spec.format,
+                            view_dimension: spec.dimension.view_dimension(),
+                        },
+                        count: None,
+                    },
+                ],
+            })
+        },
         BindGroupLayoutType::TimeUniform => {
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 entries: &[wgpu::BindGroupLayoutEntry {
@@ -281,9 +472,35 @@ pub fn create_bind_group_layout(
                         ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                         count: None,
                     },
+                    // Laid out text, see TextLayout
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
                 label: Some(&format!("{} Font Layout", label)),
             })
+        },
+        BindGroupLayoutType::IndirectDispatch => {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(INDIRECT_DISPATCH_BUFFER_SIZE),
+                    },
+                    count: None,
+                }],
+                label: Some(&format!("{} Indirect Dispatch Layout", label)),
+            })
         }
     }
 }
@@ -340,6 +557,17 @@ pub struct ComputeShader {
     pub font_system: Option<FontSystem>,
     pub font_bind_group: Option<wgpu::BindGroup>,
     pub font_bind_group_layout: Option<wgpu::BindGroupLayout>,
+    pub indirect_buffer: Option<IndirectDispatchBuffer>,
+    pub indirect_bind_group_layout: Option<wgpu::BindGroupLayout>,
+    pub indirect_bind_group_index: Option<u32>,
+    pub stage_pipelines: Vec<wgpu::ComputePipeline>,
+    pub stage_bind_group_layouts: Vec<wgpu::BindGroupLayout>,
+    pub stage_resources: Option<StageResources>,
+    pub stage_bind_group_index: Option<u32>,
+    // `once` stages still have to run
+    pub once_stages_pending: bool,
+    // export-size output and storage bind group for captures larger or smaller than the view
+    capture_output: CaptureSwap<(TextureManager, wgpu::BindGroup)>,
 }
 
 impl ComputeShader {
@@ -363,11 +591,24 @@ impl ComputeShader {
         Self::new_with_config(core, shader_source, config)
     }
     
+    /// `try_new_with_config`, panics when the config doesn't fit the device
     pub fn new_with_config(
         core: &Core,
         shader_source: &str,
         config: ComputeShaderConfig,
     ) -> Self {
+        match Self::try_new_with_config(core, shader_source, config) {
+            Ok(shader) => shader,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    
+    pub fn try_new_with_config(
+        core: &Core,
+        shader_source: &str,
+        config: ComputeShaderConfig,
+    ) -> Result<Self, ComputeConfigError> {
+        config.validate(&core.device.limits())?;
         let time_bind_group_layout = create_bind_group_layout(
             &core.device, 
             BindGroupLayoutType::TimeUniform,
@@ -389,7 +630,11 @@ impl ComputeShader {
         
         let storage_texture_layout = create_bind_group_layout(
             &core.device, 
-            BindGroupLayoutType::StorageTexture,
+            BindGroupLayoutType::StorageTextureWithSpec(StorageTextureSpec {
+                format: config.storage_texture_format,
+                access: config.storage_texture_access,
+                dimension: StorageTextureDimension::D2,
+            }),
             &config.label
         );
         let texture_bind_group_layout = core.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -450,7 +695,7 @@ impl ComputeShader {
 
         let (font_system, font_bind_group_layout) = if config.enable_fonts {
             let font_data = include_bytes!("../../assets/fonts/Courier Prime Bold.ttf");
-            let font_system = FontSystem::new(core, font_data);
+            let font_system = FontSystem::new_with_mode(core, font_data, config.font_glyph_mode);
             let layout = create_bind_group_layout(
                 &core.device,
                 BindGroupLayoutType::FontTexture,
@@ -461,6 +706,16 @@ impl ComputeShader {
             (None, None)
         };
         
+        let indirect_bind_group_layout = if config.enable_indirect_dispatch {
+            Some(create_bind_group_layout(
+                &core.device,
+                BindGroupLayoutType::IndirectDispatch,
+                &config.label
+            ))
+        } else {
+            None
+        };
+        
         // Create atomic buffer if needed
         let atomic_buffer = if config.enable_atomic_buffer {
             let buffer_size = core.size.width * core.size.height;
@@ -526,15 +781,43 @@ impl ComputeShader {
             bind_group_layouts.push(layout);
         }
         
+        // Multi-stage resources get their own group right after the shared ones
+        let stage_bind_group_index = if config.stages.is_empty() {
+            None
+        } else {
+            Some(bind_group_layouts.len() as u32)
+        };
+        
+        // Indirect args always go last so the index is known regardless of the optional groups above
+        let indirect_bind_group_index = if let Some(layout) = &indirect_bind_group_layout {
+            let index = bind_group_layouts.len() as u32 + u32::from(stage_bind_group_index.is_some());
+            if stage_bind_group_index.is_none() {
+                bind_group_layouts.push(layout);
+            }
+            Some(index)
+        } else {
+            None
+        };
+        
+        let indirect_buffer = indirect_bind_group_layout.as_ref().map(|layout| {
+            IndirectDispatchBuffer::new(
+                &core.device,
+                layout,
+                config.indirect_initial_count,
+                &config.label,
+            )
+        });
+        
         let pipeline_layout = core.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some(&format!("{} Pipeline Layout", config.label)),
             bind_group_layouts: &bind_group_layouts,
             push_constant_ranges: &[],
         });
         
-        // Create pipelines for each entry point
+        // Create pipelines for each entry point (stages build their own pipelines below)
         let mut pipelines = Vec::new();
-        for entry_point in &config.entry_points {
+        let entry_points: &[String] = if config.stages.is_empty() { &config.entry_points } else { &[] };
+        for entry_point in entry_points {
             let pipeline = core.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
                 label: Some(&format!("{} Pipeline - {}", config.label, entry_point)),
                 layout: Some(&pipeline_layout),
@@ -547,29 +830,25 @@ impl ComputeShader {
         }
         
         let font_bind_group = if let (Some(fs), Some(layout)) = (&font_system, &font_bind_group_layout) {
-            Some(core.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: fs.font_uniforms.buffer.as_entire_binding(),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&fs.atlas_texture.view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: wgpu::BindingResource::Sampler(&fs.atlas_texture.sampler),
-                    },
-                ],
-                label: Some(&format!("{} Font Bind Group", config.label)),
-            }))
+            Some(Self::create_font_bind_group(&core.device, fs, layout, &config.label))
         } else {
             None
         };
         
-        Self {
+        let stage_resources = if config.stages.is_empty() {
+            None
+        } else {
+            Some(StageResources::new(
+                &core.device,
+                &config.textures,
+                &config.buffers,
+                [core.size.width, core.size.height],
+                config.sampler_address_mode,
+                config.sampler_filter_mode,
+            ))
+        };
+        
+        let mut shader = Self {
             pipelines,
             output_texture,
             workgroup_size: config.workgroup_size,
@@ -593,7 +872,130 @@ impl ComputeShader {
             font_system,
             font_bind_group,
             font_bind_group_layout,
+            indirect_buffer,
+            indirect_bind_group_layout,
+            indirect_bind_group_index,
+            stage_pipelines: Vec::new(),
+            stage_bind_group_layouts: Vec::new(),
+            stage_resources,
+            stage_bind_group_index,
+            once_stages_pending: true,
+            capture_output: CaptureSwap::default(),
+        };
+        
+        shader.build_stage_pipelines(&core.device, &shader_module);
+        Ok(shader)
+    }
+    
+    fn create_font_bind_group(device: &wgpu::Device, font_system: &FontSystem, layout: &wgpu::BindGroupLayout, label: &str) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: font_system.font_uniforms.buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&font_system.atlas_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&font_system.atlas_texture.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: font_system.text_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some(&format!("{} Font Bind Group", label)),
+        })
+    }
+
+    // rebuilds the font bind group after the atlas or text buffer was replaced
+    fn refresh_font_bind_group(&mut self, core: &Core) {
+        let (Some(font_system), Some(layout)) = (&mut self.font_system, &self.font_bind_group_layout) else {
+            return;
+        };
+        if font_system.take_rebind() {
+            let label = self.config.as_ref().map_or("Compute", |config| config.label.as_str());
+            self.font_bind_group = Some(Self::create_font_bind_group(&core.device, font_system, layout, label));
+        }
+    }
+
+    /// Packs the glyphs of `text` into the font atlas, rebuilding the font bind group when the atlas grew.
+    pub fn ensure_font_text(&mut self, core: &Core, font: usize, text: &str, size: f32) {
+        if let Some(font_system) = &mut self.font_system {
+            font_system.ensure_text(core, font, text, size);
+        }
+        self.refresh_font_bind_group(core);
+    }
+
+    /// Lays out `text` into the font text buffer (group 3, binding 3) for the shader to draw.
+    pub fn set_font_text(&mut self, core: &Core, text: &str, layout: &TextLayout) -> Option<TextBlock> {
+        let font_system = self.font_system.as_mut()?;
+        let block = layout.build(font_system, core, text);
+        font_system.write_text(core, &block);
+        self.refresh_font_bind_group(core);
+        Some(block)
+    }
+
+    /// Writes blocks laid out with `TextLayout::build`, e.g. several merged with `TextBlock::append`.
+    pub fn set_font_text_block(&mut self, core: &Core, block: &TextBlock) {
+        if let Some(font_system) = &mut self.font_system {
+            font_system.write_text(core, block);
         }
+        self.refresh_font_bind_group(core);
+    }
+    
+    // One pipeline per stage: shared groups + the stage resource group (+ indirect args)
+    fn build_stage_pipelines(&mut self, device: &wgpu::Device, shader_module: &wgpu::ShaderModule) {
+        let (Some(config), Some(resources)) = (&self.config, &mut self.stage_resources) else {
+            return;
+        };
+        
+        let mut common_layouts: Vec<&wgpu::BindGroupLayout> = vec![&self.time_bind_group_layout, &self.storage_texture_layout];
+        if let Some(layout) = &config.mouse_bind_group_layout {
+            common_layouts.push(layout);
+        }
+        if let Some(layout) = &self.font_bind_group_layout {
+            common_layouts.push(layout);
+        }
+        if let Some(layout) = &self.external_texture_bind_group_layout {
+            common_layouts.push(layout);
+        }
+        if let Some(layout) = &self.atomic_bind_group_layout {
+            common_layouts.push(layout);
+        }
+        
+        let mut stage_pipelines = Vec::with_capacity(config.stages.len());
+        let mut stage_layouts = Vec::with_capacity(config.stages.len());
+        for stage in &config.stages {
+            let stage_layout = resources.create_bind_group_layout(device, stage, config.sampler_filter_mode);
+            let mut layouts = common_layouts.clone();
+            layouts.push(&stage_layout);
+            if let Some(layout) = &self.indirect_bind_group_layout {
+                layouts.push(layout);
+            }
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(&format!("{} Stage Pipeline Layout - {}", config.label, stage.entry_point)),
+                bind_group_layouts: &layouts,
+                push_constant_ranges: &[],
+            });
+            stage_pipelines.push(device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(&format!("{} Stage Pipeline - {}", config.label, stage.entry_point)),
+                layout: Some(&pipeline_layout),
+                module: shader_module,
+                entry_point: Some(&stage.entry_point),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: None,
+            }));
+            stage_layouts.push(stage_layout);
+        }
+        resources.invalidate_bind_groups();
+        
+        self.stage_pipelines = stage_pipelines;
+        self.stage_bind_group_layouts = stage_layouts;
     }
     pub fn add_mouse_uniform_binding(
         &mut self,
@@ -674,6 +1076,10 @@ impl ComputeShader {
             ],
         });
         
+        if let Some(resources) = &mut self.stage_resources {
+            resources.resize(&core.device, [core.size.width, core.size.height]);
+        }
+        
         // Recreate external texture bind group if needed
         if let (Some(layout), Some(in_view), Some(in_sampler)) = (
             &self.external_texture_bind_group_layout,
@@ -715,6 +1121,13 @@ impl ComputeShader {
             
             // Call reload_compute_shader directly for compute shaders
             if let Some(new_module) = hot_reload.reload_compute_shader() {
+                if self.stage_resources.is_some() {
+                    let new_module = new_module.clone();
+                    self.build_stage_pipelines(device, &new_module);
+                    info!("Compute stages hot-reloaded at frame: {}", self.current_frame);
+                    return true;
+                }
+                
                 let entry_points = if let Some(ep) = entry_point {
                     vec![ep]
                 } else {
@@ -751,22 +1164,41 @@ impl ComputeShader {
     }
     
     pub fn dispatch(&mut self, encoder: &mut wgpu::CommandEncoder, core: &Core) {
+        self.dispatch_with_profiler(encoder, core, None);
+    }
+    
+    // Same as dispatch, every compute pass gets timestamp queries when a profiler is given
+    pub fn dispatch_with_profiler(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        core: &Core,
+        mut profiler: Option<&mut GpuProfiler>,
+    ) {
         self.check_hot_reload(&core.device);
         if self.dispatch_once && self.current_frame > 0 {
             return;
         }
         
+        if self.stage_resources.is_some() {
+            self.dispatch_stages(encoder, core, profiler);
+            self.current_frame += 1;
+            return;
+        }
+        
+        let [width, height] = self.output_size();
         let workgroup_count = self.workgroup_count.unwrap_or([
-            core.size.width.div_ceil(self.workgroup_size[0]),
-            core.size.height.div_ceil(self.workgroup_size[1]),
+            width.div_ceil(self.workgroup_size[0]),
+            height.div_ceil(self.workgroup_size[1]),
             1,
         ]);
         
         // For multi-pass compute shaders (e.g., clear -> process -> generate)
         for (i, pipeline) in self.pipelines.iter().enumerate() {
+            self.prepare_indirect_args(encoder, i);
+            let label = format!("Compute Pass {}", i);
             let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some(&format!("Compute Pass {}", i)),
-                timestamp_writes: None,
+                label: Some(&label),
+                timestamp_writes: profiler.as_deref_mut().and_then(|p| p.compute_pass_writes(&label)),
             });
             
             compute_pass.set_pipeline(pipeline);
@@ -799,17 +1231,14 @@ impl ComputeShader {
                 compute_pass.set_bind_group(3, font_bind_group, &[]);
             }
             
-            compute_pass.dispatch_workgroups(
-                workgroup_count[0],
-                workgroup_count[1],
-                workgroup_count[2],
-            );
+            self.dispatch_workgroups(&mut compute_pass, i, workgroup_count);
         }
         
         self.current_frame += 1;
     }
     
-    pub fn resize(&mut self, core: &Core, width: u32, height: u32) {
+    // Output texture and the storage bind group the shader writes it through
+    fn create_output(&self, core: &Core, width: u32, height: u32) -> (TextureManager, wgpu::BindGroup) {
         let default_config = ComputeShaderConfig::default();
         let config = self.config.as_ref().unwrap_or(&default_config);
         
@@ -857,6 +1286,11 @@ impl ComputeShader {
                 },
             ],
         });
+        (output_texture, storage_bind_group)
+    }
+
+    pub fn resize(&mut self, core: &Core, width: u32, height: u32) {
+        let (output_texture, storage_bind_group) = self.create_output(core, width, height);
         
         // Update atomic buffer if needed
         if let Some(atomic_bind_group_layout) = &self.atomic_bind_group_layout {
@@ -870,16 +1304,255 @@ impl ComputeShader {
         
         self.output_texture = output_texture;
         self.storage_bind_group = storage_bind_group;
+        
+        if let Some(resources) = &mut self.stage_resources {
+            resources.resize(&core.device, [width, height]);
+        }
+    }
+
+    /// Points the output at an export-size texture until `end_capture_output`. The live
+    /// output, atomic buffer and stage textures are left alone, so accumulated state
+    /// survives a screenshot or export at a different size.
+    pub fn begin_capture_output(&mut self, core: &Core, width: u32, height: u32) {
+        let mut capture_output = std::mem::take(&mut self.capture_output);
+        let mut output = (self.output_texture.clone(), self.storage_bind_group.clone());
+        capture_output.begin(
+            &mut output,
+            |(texture, _)| {
+                let size = texture.texture.size();
+                [size.width, size.height] == [width, height]
+            },
+            || self.create_output(core, width, height),
+        );
+        (self.output_texture, self.storage_bind_group) = output;
+        self.capture_output = capture_output;
+    }
+
+    // Back to the live output, the export one is kept for the next capture
+    pub fn end_capture_output(&mut self) {
+        if !self.capture_output.is_capturing() {
+            return;
+        }
+        let mut output = (self.output_texture.clone(), self.storage_bind_group.clone());
+        self.capture_output.end(&mut output);
+        (self.output_texture, self.storage_bind_group) = output;
     }
     
     pub fn get_output_texture(&self) -> &TextureManager {
         &self.output_texture
     }
     
+    // Size the shader renders at, the window size unless RenderKit applies a render scale
+    pub fn output_size(&self) -> [u32; 2] {
+        let size = self.output_texture.texture.size();
+        [size.width, size.height]
+    }
+    
+    pub fn gpu_memory_stats(&self) -> GpuMemoryStats {
+        let mut stats = GpuMemoryStats::default();
+        stats.add_texture(&self.output_texture.texture);
+        stats.add_buffer(&self.time_uniform.buffer);
+        if let Some(atomic_buffer) = &self.atomic_buffer {
+            stats.add_buffer(&atomic_buffer.buffer);
+        }
+        if let Some(indirect_buffer) = &self.indirect_buffer {
+            stats.add_buffer(&indirect_buffer.buffer);
+        }
+        if let Some(resources) = &self.stage_resources {
+            for texture in resources.textures.values() {
+                for t in &texture.textures {
+                    stats.add_texture(t);
+                }
+            }
+            for buffer in resources.buffers.values() {
+                stats.add_buffer(buffer);
+            }
+        }
+        stats
+    }
+    
+    pub fn stage_texture(&self, name: &str) -> Option<&StageTexture> {
+        self.stage_resources.as_ref()?.texture(name)
+    }
+    
+    pub fn stage_buffer(&self, name: &str) -> Option<&wgpu::Buffer> {
+        self.stage_resources.as_ref()?.buffer(name)
+    }
+    
+    pub fn resize_stage_buffer(&mut self, core: &Core, name: &str, size: u64) {
+        if let Some(resources) = &mut self.stage_resources {
+            resources.resize_buffer(&core.device, name, size);
+        }
+    }
+    
+    // Buffer bound by `StageResource::Uniform(name)` (or Buffer/ReadBuffer) that the bin owns
+    pub fn set_stage_buffer(&mut self, name: &str, buffer: &wgpu::Buffer) {
+        if let Some(resources) = &mut self.stage_resources {
+            resources.set_buffer(name, buffer);
+        }
+    }
+    
+    // Texture bound by `StageResource::ExternalTexture(name)`, set it again when the source changes
+    pub fn set_stage_view(&mut self, name: &str, view: &wgpu::TextureView) {
+        if let Some(resources) = &mut self.stage_resources {
+            resources.set_view(name, view);
+        }
+    }
+    
+    // Dispatch size and iterations can change between frames, resources need a new shader
+    pub fn stage_mut(&mut self, entry_point: &str) -> Option<&mut ComputeStage> {
+        self.config.as_mut()?.stages.iter_mut().find(|stage| stage.entry_point == entry_point)
+    }
+    
+    pub fn rerun_once_stages(&mut self) {
+        self.once_stages_pending = true;
+    }
+    
+    // Shared groups in pipeline layout order: time, storage, mouse, font, external, atomic
+    fn set_common_bind_groups(&self, compute_pass: &mut wgpu::ComputePass) {
+        compute_pass.set_bind_group(0, &self.time_uniform.bind_group, &[]);
+        compute_pass.set_bind_group(1, &self.storage_bind_group, &[]);
+        
+        let mut index = 2;
+        if self.config.as_ref().is_some_and(|config| config.mouse_bind_group_layout.is_some()) {
+            if let Some(mouse_bind_group) = &self.mouse_bind_group {
+                compute_pass.set_bind_group(index, mouse_bind_group, &[]);
+            }
+            index += 1;
+        }
+        if let Some(font_bind_group) = &self.font_bind_group {
+            compute_pass.set_bind_group(index, font_bind_group, &[]);
+            index += 1;
+        }
+        if self.external_texture_bind_group_layout.is_some() {
+            if let Some(external_bind_group) = &self.external_texture_bind_group {
+                compute_pass.set_bind_group(index, external_bind_group, &[]);
+            }
+            index += 1;
+        }
+        if let Some(atomic_buffer) = &self.atomic_buffer {
+            compute_pass.set_bind_group(index, &atomic_buffer.bind_group, &[]);
+        }
+        if let (Some(indirect_buffer), Some(bind_idx)) = (&self.indirect_buffer, self.indirect_bind_group_index) {
+            compute_pass.set_bind_group(bind_idx, &indirect_buffer.bind_group, &[]);
+        }
+    }
+    
+    // Runs every stage in order, each repeated `iterations` times
+    fn dispatch_stages(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        core: &Core,
+        mut profiler: Option<&mut GpuProfiler>,
+    ) {
+        let (Some(config), Some(stage_idx)) = (&self.config, self.stage_bind_group_index) else {
+            return;
+        };
+        let screen_size = self.output_size();
+        let run_once = std::mem::take(&mut self.once_stages_pending);
+        
+        for (i, stage) in config.stages.iter().enumerate() {
+            let Some(pipeline) = self.stage_pipelines.get(i) else {
+                continue;
+            };
+            if stage.once && !run_once {
+                continue;
+            }
+            if let (StageDispatch::Indirect, Some(indirect_buffer)) = (&stage.dispatch, &self.indirect_buffer) {
+                // counts written before this stage, the stage can't resize itself
+                indirect_buffer.copy_args(encoder);
+            }
+            let label = format!("Compute Stage {} - {}", i, stage.entry_point);
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some(&label),
+                timestamp_writes: profiler.as_deref_mut().and_then(|p| p.compute_pass_writes(&label)),
+            });
+            compute_pass.set_pipeline(pipeline);
+            self.set_common_bind_groups(&mut compute_pass);
+            
+            for _ in 0..stage.iterations {
+                let Some(resources) = self.stage_resources.as_mut() else {
+                    return;
+                };
+                let texture_size = match &stage.dispatch {
+                    StageDispatch::TextureSize(name) => resources.texture(name).map(|t| t.size),
+                    _ => None,
+                };
+                match resources.bind_group(&core.device, i, stage, &self.stage_bind_group_layouts[i]) {
+                    Some(bind_group) => compute_pass.set_bind_group(stage_idx, bind_group, &[]),
+                    None => break,
+                }
+                
+                match (&stage.dispatch, &self.indirect_buffer) {
+                    (StageDispatch::Indirect, Some(indirect_buffer)) => {
+                        compute_pass.dispatch_workgroups_indirect(&indirect_buffer.args, 0);
+                    }
+                    (StageDispatch::Indirect, None) => {
+                        warn!("Stage '{}' uses indirect dispatch but enable_indirect_dispatch is off", stage.entry_point);
+                        break;
+                    }
+                    _ => {
+                        let count = stage.workgroup_count(screen_size, texture_size);
+                        compute_pass.dispatch_workgroups(count[0], count[1], count[2]);
+                    }
+                }
+                resources.swap_written(stage);
+            }
+        }
+    }
+    
+    pub fn is_indirect_pipeline(&self, pipeline_index: usize) -> bool {
+        self.indirect_buffer.is_some()
+            && self.entry_points.get(pipeline_index).is_some_and(|entry_point| {
+                self.config.as_ref().is_some_and(|config| config.indirect_entry_points.contains(entry_point))
+            })
+    }
+    
+    // Copies the counts written by earlier passes into the args buffer, before the pass begins
+    fn prepare_indirect_args(&self, encoder: &mut wgpu::CommandEncoder, pipeline_index: usize) {
+        if let Some(indirect_buffer) = &self.indirect_buffer {
+            if self.is_indirect_pipeline(pipeline_index) {
+                indirect_buffer.copy_args(encoder);
+            }
+        }
+    }
+    
+    // Binds the indirect args buffer (so any pass can write it) and dispatches either
+    // with the CPU-side workgroup count or indirectly from the args copy
+    fn dispatch_workgroups(
+        &self,
+        compute_pass: &mut wgpu::ComputePass,
+        pipeline_index: usize,
+        workgroup_count: [u32; 3],
+    ) {
+        if let (Some(indirect_buffer), Some(bind_idx)) = (&self.indirect_buffer, self.indirect_bind_group_index) {
+            compute_pass.set_bind_group(bind_idx, &indirect_buffer.bind_group, &[]);
+            if self.is_indirect_pipeline(pipeline_index) {
+                compute_pass.dispatch_workgroups_indirect(&indirect_buffer.args, 0);
+                return;
+            }
+        }
+        compute_pass.dispatch_workgroups(
+            workgroup_count[0],
+            workgroup_count[1],
+            workgroup_count[2],
+        );
+    }
+    
     // Dispatch a specific pipeline by index
     pub fn dispatch_pipeline(&mut self, encoder: &mut wgpu::CommandEncoder, core: &Core, pipeline_index: usize) {
+        self.dispatch_pipeline_with_profiler(encoder, core, pipeline_index, None);
+    }
+    
+    pub fn dispatch_pipeline_with_profiler(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        core: &Core,
+        pipeline_index: usize,
+        profiler: Option<&mut GpuProfiler>,
+    ) {
         if pipeline_index >= self.pipelines.len() {
-            warn!("Pipeline index {} out of bounds (max: {})", pipeline_index, self.pipelines.len() - 1);
+            warn!("Pipeline index {} out of bounds (pipelines: {})", pipeline_index, self.pipelines.len());
             return;
         }
         
@@ -889,16 +1562,19 @@ impl ComputeShader {
             return;
         }
         
+        let [width, height] = self.output_size();
         let workgroup_count = self.workgroup_count.unwrap_or([
-            core.size.width.div_ceil(self.workgroup_size[0]),
-            core.size.height.div_ceil(self.workgroup_size[1]),
+            width.div_ceil(self.workgroup_size[0]),
+            height.div_ceil(self.workgroup_size[1]),
             1,
         ]);
         
+        self.prepare_indirect_args(encoder, pipeline_index);
         let pipeline = &self.pipelines[pipeline_index];
+        let label = format!("Compute Pass {}", pipeline_index);
         let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-            label: Some(&format!("Compute Pass {}", pipeline_index)),
-            timestamp_writes: None,
+            label: Some(&label),
+            timestamp_writes: profiler.and_then(|p| p.compute_pass_writes(&label)),
         });
         
         compute_pass.set_pipeline(pipeline);
@@ -932,11 +1608,7 @@ impl ComputeShader {
             compute_pass.set_bind_group(3, font_bind_group, &[]);
         }
         
-        compute_pass.dispatch_workgroups(
-            workgroup_count[0],
-            workgroup_count[1],
-            workgroup_count[2],
-        );
+        self.dispatch_workgroups(&mut compute_pass, pipeline_index, workgroup_count);
         
         // Only increment the frame counter if this is the last pipeline in sequence
         if pipeline_index == self.pipelines.len() - 1 {
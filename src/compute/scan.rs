@@ -0,0 +1,313 @@
+use crate::{UniformProvider, UniformBinding};
+use super::IndirectDispatchBuffer;
+use wgpu::util::DeviceExt;
+
+const SCAN_BLOCK_SIZE: u32 = 256;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ScanParams {
+    pub len: u32,
+    pub block_count: u32,
+    pub out_workgroup_size: u32,
+    pub _padding: u32,
+}
+
+impl UniformProvider for ScanParams {
+    fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+fn storage_entry(binding: u32, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn create_scan_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    module: &wgpu::ShaderModule,
+    entry_point: &str,
+) -> wgpu::ComputePipeline {
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(&format!("Scan Pipeline - {}", entry_point)),
+        layout: Some(layout),
+        module,
+        entry_point: Some(entry_point),
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        cache: None,
+    })
+}
+
+/// In-place exclusive prefix sum over a `u32` storage buffer.
+/// `data` needs `STORAGE` usage and at least `len` elements.
+pub struct PrefixSum {
+    pub len: u32,
+    pub block_sums: wgpu::Buffer,
+    pub params_uniform: UniformBinding<ScanParams>,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+    scan_blocks_pipeline: wgpu::ComputePipeline,
+    scan_block_sums_pipeline: wgpu::ComputePipeline,
+    add_block_offsets_pipeline: wgpu::ComputePipeline,
+}
+
+impl PrefixSum {
+    pub fn new(device: &wgpu::Device, data: &wgpu::Buffer, len: u32) -> Self {
+        Self::new_with_output_workgroup_size(device, data, len, 1)
+    }
+
+    fn new_with_output_workgroup_size(
+        device: &wgpu::Device,
+        data: &wgpu::Buffer,
+        len: u32,
+        out_workgroup_size: u32,
+    ) -> Self {
+        let block_count = len.div_ceil(SCAN_BLOCK_SIZE).max(1);
+
+        let uniform_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Scan Params Layout"),
+        });
+        let params_uniform = UniformBinding::new(
+            device,
+            "Scan Params",
+            ScanParams {
+                len,
+                block_count,
+                out_workgroup_size: out_workgroup_size.max(1),
+                _padding: 0,
+            },
+            &uniform_layout,
+            0,
+        );
+
+        let block_sums = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Scan Block Sums"),
+            size: (block_count * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, false),
+                storage_entry(2, false),
+            ],
+            label: Some("Scan Layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_uniform.buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: data.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: block_sums.as_entire_binding(),
+                },
+            ],
+            label: Some("Scan Bind Group"),
+        });
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Scan Module"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/scan.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Scan Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        Self {
+            len,
+            block_sums,
+            params_uniform,
+            scan_blocks_pipeline: create_scan_pipeline(device, &pipeline_layout, &module, "scan_blocks"),
+            scan_block_sums_pipeline: create_scan_pipeline(device, &pipeline_layout, &module, "scan_block_sums"),
+            add_block_offsets_pipeline: create_scan_pipeline(device, &pipeline_layout, &module, "add_block_offsets"),
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    fn block_count(&self) -> u32 {
+        self.params_uniform.data.block_count
+    }
+
+    pub fn dispatch(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Prefix Sum Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+
+        compute_pass.set_pipeline(&self.scan_blocks_pipeline);
+        compute_pass.dispatch_workgroups(self.block_count(), 1, 1);
+
+        compute_pass.set_pipeline(&self.scan_block_sums_pipeline);
+        compute_pass.dispatch_workgroups(1, 1, 1);
+
+        compute_pass.set_pipeline(&self.add_block_offsets_pipeline);
+        compute_pass.dispatch_workgroups(self.block_count(), 1, 1);
+    }
+}
+
+/// Stream compaction: collects the indices of all elements whose flag is 1 into
+/// `indices` and writes the survivor count plus `[ceil(count / workgroup_size), 1, 1]`
+/// into an `IndirectDispatchBuffer`, so the next pass only processes the survivors.
+/// `flags` must hold 0 or 1 per element and needs `STORAGE | COPY_SRC` usage.
+pub struct StreamCompaction {
+    pub len: u32,
+    pub prefix_sum: PrefixSum,
+    pub scan_buffer: wgpu::Buffer,
+    pub indices: wgpu::Buffer,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+    flags: wgpu::Buffer,
+    scatter_pipeline: wgpu::ComputePipeline,
+    // len 0 runs no scatter pass, so `[0, 1, 1]` + count 0 is copied into the args instead
+    empty_args: Option<wgpu::Buffer>,
+    indirect: wgpu::Buffer,
+}
+
+impl StreamCompaction {
+    pub fn new(
+        device: &wgpu::Device,
+        flags: &wgpu::Buffer,
+        len: u32,
+        indirect: &IndirectDispatchBuffer,
+        out_workgroup_size: u32,
+    ) -> Self {
+        let buffer_size = (len.max(1) * 4) as u64;
+        let scan_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Compaction Scan Buffer"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let indices = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Compaction Indices"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let prefix_sum = PrefixSum::new_with_output_workgroup_size(
+            device,
+            &scan_buffer,
+            len,
+            out_workgroup_size,
+        );
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                storage_entry(0, true),
+                storage_entry(1, false),
+                storage_entry(2, false),
+            ],
+            label: Some("Compaction Layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: flags.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: indices.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: indirect.buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("Compaction Bind Group"),
+        });
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Compaction Module"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/scan.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Compaction Pipeline Layout"),
+            bind_group_layouts: &[&prefix_sum.bind_group_layout, &bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let scatter_pipeline = create_scan_pipeline(device, &pipeline_layout, &module, "compact_scatter");
+        let empty_args = (len == 0).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Compaction Empty Args"),
+                contents: bytemuck::cast_slice(&[0u32, 1, 1, 0]),
+                usage: wgpu::BufferUsages::COPY_SRC,
+            })
+        });
+
+        Self {
+            len,
+            prefix_sum,
+            scan_buffer,
+            indices,
+            bind_group_layout,
+            bind_group,
+            flags: flags.clone(),
+            scatter_pipeline,
+            empty_args,
+            indirect: indirect.buffer.clone(),
+        }
+    }
+
+    pub fn dispatch(&self, encoder: &mut wgpu::CommandEncoder) {
+        if let Some(empty_args) = &self.empty_args {
+            encoder.copy_buffer_to_buffer(empty_args, 0, &self.indirect, 0, 16);
+            return;
+        }
+        encoder.copy_buffer_to_buffer(&self.flags, 0, &self.scan_buffer, 0, (self.len * 4) as u64);
+        self.prefix_sum.dispatch(encoder);
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Compaction Scatter Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.scatter_pipeline);
+        compute_pass.set_bind_group(0, &self.prefix_sum.bind_group, &[]);
+        compute_pass.set_bind_group(1, &self.bind_group, &[]);
+        compute_pass.dispatch_workgroups(self.len.div_ceil(SCAN_BLOCK_SIZE), 1, 1);
+    }
+}
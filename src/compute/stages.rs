@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+use log::warn;
+use super::{ComputeConfigError, StorageTextureDimension};
+
+// How many workgroups a stage dispatches
+#[derive(Clone, Debug)]
+pub enum StageDispatch {
+    // ceil(window size / workgroup size), same as the single-pass default
+    ScreenSize,
+    // ceil(size of a named stage texture / workgroup size), z covers depth or array layers
+    TextureSize(String),
+    // total invocations, divided by the stage workgroup size
+    Invocations([u32; 3]),
+    // raw workgroup counts
+    Workgroups([u32; 3]),
+    // read from the indirect buffer (needs enable_indirect_dispatch)
+    Indirect,
+}
+
+// Resources a stage binds, in binding order, in its own bind group
+#[derive(Clone, Debug)]
+pub enum StageResource {
+    // texture_2d<f32> (or _2d_array / _3d), the front side if the texture is double buffered
+    ReadTexture(String),
+    // texture_storage_2d<format, write> (or _2d_array / _3d), the back side if the texture is double buffered
+    WriteTexture(String),
+    // texture_storage_*<format, read_write> on the front side, never swapped
+    ReadWriteTexture(String),
+    // sampler using the config's address/filter mode
+    Sampler,
+    // var<storage, read_write>
+    Buffer(String),
+    // var<storage, read>
+    ReadBuffer(String),
+    // var<uniform> backed by a named buffer, e.g. params written with queue.write_buffer
+    Uniform(String),
+    // texture_2d<f32> owned elsewhere, e.g. the media texture, see `ComputeShader::set_stage_view`
+    ExternalTexture(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct ComputeStage {
+    pub entry_point: String,
+    pub workgroup_size: [u32; 3],
+    pub dispatch: StageDispatch,
+    pub resources: Vec<StageResource>,
+    // repeat count per frame, e.g. Jacobi iterations. Double buffered textures written
+    // by the stage are swapped after every iteration.
+    pub iterations: u32,
+    // runs on the first dispatch and after `ComputeShader::rerun_once_stages`, e.g. loading input data
+    pub once: bool,
+}
+
+impl ComputeStage {
+    pub fn new(entry_point: &str) -> Self {
+        Self {
+            entry_point: entry_point.to_string(),
+            workgroup_size: [16, 16, 1],
+            dispatch: StageDispatch::ScreenSize,
+            resources: Vec::new(),
+            iterations: 1,
+            once: false,
+        }
+    }
+
+    // A texture side can't be sampled and written by the same dispatch, and indirect
+    // stages need the indirect buffer
+    pub fn validate(&self, textures: &[ComputeTextureDesc], indirect: bool) -> Result<(), ComputeConfigError> {
+        if matches!(self.dispatch, StageDispatch::Indirect) && !indirect {
+            return Err(ComputeConfigError::IndirectStageWithoutBuffer { stage: self.entry_point.clone() });
+        }
+        for resource in &self.resources {
+            let StageResource::ReadTexture(name) = resource else {
+                continue;
+            };
+            let double_buffered = textures.iter().any(|t| &t.name == name && t.double_buffered);
+            let written = self.resources.iter().any(|other| match other {
+                StageResource::ReadWriteTexture(other) => other == name,
+                // the write goes to the back side of a double buffered texture
+                StageResource::WriteTexture(other) => other == name && !double_buffered,
+                _ => false,
+            });
+            if written {
+                return Err(ComputeConfigError::StageTextureConflict {
+                    stage: self.entry_point.clone(),
+                    texture: name.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn workgroup_count(&self, screen_size: [u32; 2], texture_size: Option<[u32; 3]>) -> [u32; 3] {
+        let [wx, wy, wz] = self.workgroup_size;
+        match &self.dispatch {
+            StageDispatch::ScreenSize => [screen_size[0].div_ceil(wx), screen_size[1].div_ceil(wy), 1],
+            StageDispatch::TextureSize(_) => {
+                let size = texture_size.unwrap_or([screen_size[0], screen_size[1], 1]);
+                [size[0].div_ceil(wx), size[1].div_ceil(wy), size[2].div_ceil(wz)]
+            }
+            StageDispatch::Invocations(n) => [n[0].div_ceil(wx), n[1].div_ceil(wy), n[2].div_ceil(wz)],
+            StageDispatch::Workgroups(count) => *count,
+            StageDispatch::Indirect => [0, 0, 0],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ComputeTextureDesc {
+    pub name: String,
+    pub format: wgpu::TextureFormat,
+    // None follows the window size
+    pub size: Option<[u32; 2]>,
+    // D2Array(layers) / D3(depth) for volumetric effects
+    pub dimension: StorageTextureDimension,
+    pub double_buffered: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct ComputeBufferDesc {
+    pub name: String,
+    pub size: u64,
+}
+
+pub struct StageTexture {
+    pub desc: ComputeTextureDesc,
+    pub textures: Vec<wgpu::Texture>,
+    pub views: Vec<wgpu::TextureView>,
+    pub front: usize,
+    // width, height, depth or array layers
+    pub size: [u32; 3],
+}
+
+impl StageTexture {
+    fn new(device: &wgpu::Device, desc: &ComputeTextureDesc, screen_size: [u32; 2]) -> Self {
+        let [width, height] = desc.size.unwrap_or(screen_size);
+        let size = [width.max(1), height.max(1), desc.dimension.depth_or_array_layers()];
+        let count = if desc.double_buffered { 2 } else { 1 };
+        let textures: Vec<wgpu::Texture> = (0..count)
+            .map(|i| {
+                device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some(&format!("{} Stage Texture {}", desc.name, i)),
+                    size: wgpu::Extent3d {
+                        width: size[0],
+                        height: size[1],
+                        depth_or_array_layers: size[2],
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: desc.dimension.texture_dimension(),
+                    format: desc.format,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING
+                        | wgpu::TextureUsages::STORAGE_BINDING
+                        | wgpu::TextureUsages::COPY_SRC
+                        | wgpu::TextureUsages::COPY_DST,
+                    view_formats: &[],
+                })
+            })
+            .collect();
+        let views = textures
+            .iter()
+            .map(|t| {
+                t.create_view(&wgpu::TextureViewDescriptor {
+                    dimension: Some(desc.dimension.view_dimension()),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        Self {
+            desc: desc.clone(),
+            textures,
+            views,
+            front: 0,
+            size,
+        }
+    }
+
+    pub fn view_dimension(&self) -> wgpu::TextureViewDimension {
+        self.desc.dimension.view_dimension()
+    }
+
+    pub fn read_view(&self) -> &wgpu::TextureView {
+        &self.views[self.front]
+    }
+
+    pub fn write_view(&self) -> &wgpu::TextureView {
+        &self.views[(self.front + 1) % self.views.len()]
+    }
+
+    pub fn swap(&mut self) {
+        self.front = (self.front + 1) % self.views.len();
+    }
+}
+
+/// Named textures and buffers shared by the stages of a `ComputeShader`
+pub struct StageResources {
+    pub textures: HashMap<String, StageTexture>,
+    pub buffers: HashMap<String, wgpu::Buffer>,
+    pub views: HashMap<String, wgpu::TextureView>,
+    pub sampler: wgpu::Sampler,
+    bind_group_cache: HashMap<(usize, u64), wgpu::BindGroup>,
+}
+
+impl StageResources {
+    pub fn new(
+        device: &wgpu::Device,
+        textures: &[ComputeTextureDesc],
+        buffers: &[ComputeBufferDesc],
+        screen_size: [u32; 2],
+        address_mode: wgpu::AddressMode,
+        filter_mode: wgpu::FilterMode,
+    ) -> Self {
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Stage Sampler"),
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: filter_mode,
+            min_filter: filter_mode,
+            mipmap_filter: filter_mode,
+            ..Default::default()
+        });
+        let textures = textures
+            .iter()
+            .map(|desc| (desc.name.clone(), StageTexture::new(device, desc, screen_size)))
+            .collect();
+        let buffers = buffers
+            .iter()
+            .map(|desc| (desc.name.clone(), create_stage_buffer(device, &desc.name, desc.size)))
+            .collect();
+        Self {
+            textures,
+            buffers,
+            views: HashMap::new(),
+            sampler,
+            bind_group_cache: HashMap::new(),
+        }
+    }
+
+    // Recreate the textures that follow the window size
+    pub fn resize(&mut self, device: &wgpu::Device, screen_size: [u32; 2]) {
+        for texture in self.textures.values_mut() {
+            if texture.desc.size.is_none() {
+                *texture = StageTexture::new(device, &texture.desc, screen_size);
+            }
+        }
+        self.invalidate_bind_groups();
+    }
+
+    // Replaces a buffer with a new, zeroed one, e.g. when its element count depends on a param
+    pub fn resize_buffer(&mut self, device: &wgpu::Device, name: &str, size: u64) {
+        if let Some(buffer) = self.buffers.get_mut(name) {
+            *buffer = create_stage_buffer(device, name, size);
+            self.invalidate_bind_groups();
+        }
+    }
+
+    // Shares a buffer owned elsewhere, e.g. the buffer of a `UniformBinding`
+    pub fn set_buffer(&mut self, name: &str, buffer: &wgpu::Buffer) {
+        self.buffers.insert(name.to_string(), buffer.clone());
+        self.invalidate_bind_groups();
+    }
+
+    pub fn set_view(&mut self, name: &str, view: &wgpu::TextureView) {
+        self.views.insert(name.to_string(), view.clone());
+        self.invalidate_bind_groups();
+    }
+
+    pub fn invalidate_bind_groups(&mut self) {
+        self.bind_group_cache.clear();
+    }
+
+    pub fn texture(&self, name: &str) -> Option<&StageTexture> {
+        self.textures.get(name)
+    }
+
+    pub fn buffer(&self, name: &str) -> Option<&wgpu::Buffer> {
+        self.buffers.get(name)
+    }
+
+    fn view_dimension(&self, name: &str) -> wgpu::TextureViewDimension {
+        self.textures
+            .get(name)
+            .map(|t| t.view_dimension())
+            .unwrap_or(wgpu::TextureViewDimension::D2)
+    }
+
+    pub fn create_bind_group_layout(
+        &self,
+        device: &wgpu::Device,
+        stage: &ComputeStage,
+        filter_mode: wgpu::FilterMode,
+    ) -> wgpu::BindGroupLayout {
+        let entries: Vec<wgpu::BindGroupLayoutEntry> = stage
+            .resources
+            .iter()
+            .enumerate()
+            .map(|(binding, resource)| {
+                let ty = match resource {
+                    StageResource::ReadTexture(name) => {
+                        let sample_type = self
+                            .textures
+                            .get(name)
+                            .and_then(|t| t.desc.format.sample_type(None, None))
+                            .unwrap_or(wgpu::TextureSampleType::Float { filterable: false });
+                        wgpu::BindingType::Texture {
+                            multisampled: false,
+                            sample_type,
+                            view_dimension: self.view_dimension(name),
+                        }
+                    }
+                    StageResource::WriteTexture(name) | StageResource::ReadWriteTexture(name) => {
+                        wgpu::BindingType::StorageTexture {
+                            access: if matches!(resource, StageResource::ReadWriteTexture(_)) {
+                                wgpu::StorageTextureAccess::ReadWrite
+                            } else {
+                                wgpu::StorageTextureAccess::WriteOnly
+                            },
+                            format: self
+                                .textures
+                                .get(name)
+                                .map(|t| t.desc.format)
+                                .unwrap_or(super::COMPUTE_TEXTURE_FORMAT_RGBA16),
+                            view_dimension: self.view_dimension(name),
+                        }
+                    }
+                    StageResource::Sampler => wgpu::BindingType::Sampler(if filter_mode == wgpu::FilterMode::Linear {
+                        wgpu::SamplerBindingType::Filtering
+                    } else {
+                        wgpu::SamplerBindingType::NonFiltering
+                    }),
+                    StageResource::Buffer(_) | StageResource::ReadBuffer(_) => wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage {
+                            read_only: matches!(resource, StageResource::ReadBuffer(_)),
+                        },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    StageResource::Uniform(_) => wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    StageResource::ExternalTexture(_) => wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                };
+                wgpu::BindGroupLayoutEntry {
+                    binding: binding as u32,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty,
+                    count: None,
+                }
+            })
+            .collect();
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&format!("{} Stage Layout", stage.entry_point)),
+            entries: &entries,
+        })
+    }
+
+    // Bind groups depend on which side of each double buffered texture is in front,
+    // so they are cached per stage and per swap state
+    pub fn bind_group(
+        &mut self,
+        device: &wgpu::Device,
+        stage_index: usize,
+        stage: &ComputeStage,
+        layout: &wgpu::BindGroupLayout,
+    ) -> Option<&wgpu::BindGroup> {
+        let mut key = 0u64;
+        for (i, resource) in stage.resources.iter().enumerate() {
+            if let StageResource::ReadTexture(name)
+            | StageResource::WriteTexture(name)
+            | StageResource::ReadWriteTexture(name) = resource
+            {
+                if let Some(texture) = self.textures.get(name) {
+                    key |= (texture.front as u64 & 1) << (i % 64);
+                }
+            }
+        }
+
+        if !self.bind_group_cache.contains_key(&(stage_index, key)) {
+            let mut entries = Vec::with_capacity(stage.resources.len());
+            for (binding, resource) in stage.resources.iter().enumerate() {
+                let resource = match resource {
+                    StageResource::ReadTexture(name) | StageResource::ReadWriteTexture(name) => self
+                        .textures
+                        .get(name)
+                        .map(|t| wgpu::BindingResource::TextureView(t.read_view())),
+                    StageResource::WriteTexture(name) => self
+                        .textures
+                        .get(name)
+                        .map(|t| wgpu::BindingResource::TextureView(t.write_view())),
+                    StageResource::Sampler => Some(wgpu::BindingResource::Sampler(&self.sampler)),
+                    StageResource::Buffer(name) | StageResource::ReadBuffer(name) | StageResource::Uniform(name) => {
+                        self.buffers.get(name).map(|b| b.as_entire_binding())
+                    }
+                    StageResource::ExternalTexture(name) => {
+                        self.views.get(name).map(wgpu::BindingResource::TextureView)
+                    }
+                };
+                let Some(resource) = resource else {
+                    warn!("Stage '{}' references an unknown resource at binding {}", stage.entry_point, binding);
+                    return None;
+                };
+                entries.push(wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource,
+                });
+            }
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&format!("{} Stage Bind Group", stage.entry_point)),
+                layout,
+                entries: &entries,
+            });
+            self.bind_group_cache.insert((stage_index, key), bind_group);
+        }
+        self.bind_group_cache.get(&(stage_index, key))
+    }
+
+    // Swap every double buffered texture the stage wrote to
+    pub fn swap_written(&mut self, stage: &ComputeStage) {
+        for resource in &stage.resources {
+            if let StageResource::WriteTexture(name) = resource {
+                if let Some(texture) = self.textures.get_mut(name) {
+                    texture.swap();
+                }
+            }
+        }
+    }
+}
+
+fn create_stage_buffer(device: &wgpu::Device, name: &str, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(&format!("{} Stage Buffer", name)),
+        size,
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::UNIFORM
+            | wgpu::BufferUsages::COPY_DST
+            | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    })
+}
@@ -2,6 +2,7 @@ use std::path::PathBuf;
 #[cfg(feature = "media")]
 use crate::gst::video::VideoTextureManager;
 use crate::hdri::HdriMetadata;
+use crate::{FrameStats, RenderScale, RenderScaleUiRequest, StatsUiRequest};
 #[derive(Clone)]
 pub struct ControlsRequest {
     pub is_paused: bool,
@@ -11,6 +12,11 @@ pub struct ControlsRequest {
     pub window_size: Option<(u32, u32)>,
     
     pub current_fps: Option<f32>,
+    // adapter name and backend, see `adapter_label`
+    pub adapter: Option<String>,
+    // filled by RenderKit::add_frame_stats, shown under the controls
+    pub stats: Option<StatsUiRequest>,
+    pub render_scale: Option<RenderScaleUiRequest>,
     
     // Video reqs
     pub load_media_path: Option<PathBuf>,
@@ -29,20 +35,24 @@ pub struct ControlsRequest {
     pub hdri_exposure: Option<f32>,
     pub hdri_gamma: Option<f32>,
 }
+// --media from the command line, else the CUNEUS_MEDIA env var
+fn startup_media_path() -> Option<PathBuf> {
+    if let Some(path) = &crate::CliOptions::current().media {
+        return Some(path.clone());
+    }
+    let media_dir = std::env::var("CUNEUS_MEDIA").ok()?;
+    log::info!("CUNEUS_MEDIA: {}", media_dir);
+    let unquoted = media_dir
+        .strip_prefix('"')
+        .and_then(|dir| dir.strip_suffix('"'))
+        .unwrap_or(&media_dir);
+    Some(PathBuf::from(unquoted))
+}
+
 impl Default for ControlsRequest {
     fn default() -> Self {
-        let mut default_media = None;
-        let mut should_play_video=false;
-        if let Ok(media_dir) = std::env::var("CUNEUS_MEDIA") {
-            println!("CUNEUS_MEDIA: {}", media_dir);
-            if media_dir.starts_with('"') && media_dir.ends_with('"') {
-                let unquoted = &media_dir[1..media_dir.len() - 1];
-                default_media = Some(PathBuf::from(unquoted));
-            } else {
-                default_media = Some(PathBuf::from(media_dir));
-            }
-            should_play_video = true;
-        }
+        let default_media = startup_media_path();
+        let should_play_video = default_media.is_some();
         Self {
             is_paused: false,
             should_reset: false,
@@ -51,6 +61,9 @@ impl Default for ControlsRequest {
             window_size: None,
             
             current_fps: None,
+            adapter: None,
+            stats: None,
+            render_scale: None,
             
             // Video-related stuff
             load_media_path: default_media,
@@ -82,6 +95,7 @@ pub struct ShaderControls {
     total_pause_duration: f32,
     current_frame: u32,
     media_loaded_once: bool,
+    adapter: Option<String>,
 }
 
 impl Default for ShaderControls {
@@ -92,6 +106,7 @@ impl Default for ShaderControls {
             total_pause_duration: 0.0,
             current_frame: 0,
             media_loaded_once: false,
+            adapter: None,
         }
     }
 }
@@ -121,18 +136,23 @@ impl ShaderControls {
         }
     }
     
+    // Moves the clock so get_time returns `time` (keeping the pause state), e.g. when loading a preset
+    pub fn seek(&mut self, start_time: &mut std::time::Instant, time: f32, frame: u32) {
+        let now = std::time::Instant::now();
+        *start_time = now
+            .checked_sub(std::time::Duration::from_secs_f32(time.max(0.0)))
+            .unwrap_or(now);
+        self.total_pause_duration = 0.0;
+        self.pause_start = self.is_paused.then_some(now);
+        self.current_frame = frame;
+    }
+    
     pub fn get_ui_request(&mut self, start_time: &std::time::Instant, size: &winit::dpi::PhysicalSize<u32>) -> ControlsRequest {
         let mut load_media_path = None;
         let mut play_video = false;
         if !self.media_loaded_once {
-            if let Ok(media_dir) = std::env::var("CUNEUS_MEDIA") {
-                println!("CUNEUS_MEDIA: {}", media_dir);
-                if media_dir.starts_with('"') && media_dir.ends_with('"') {
-                    let unquoted = &media_dir[1..media_dir.len() - 1];
-                    load_media_path = Some(PathBuf::from(unquoted));
-                } else {
-                    load_media_path = Some(PathBuf::from(media_dir));
-                }
+            if let Some(path) = startup_media_path() {
+                load_media_path = Some(path);
                 play_video = true;
                 self.media_loaded_once = true;
             }
@@ -144,6 +164,9 @@ impl ShaderControls {
             current_time: Some(self.get_time(start_time)),
             window_size: Some((size.width, size.height)),
             current_fps: None,
+            adapter: self.adapter.clone(),
+            stats: None,
+            render_scale: None,
 
             load_media_path,
             play_video,
@@ -167,15 +190,29 @@ impl ShaderControls {
             self.total_pause_duration = 0.0;
             self.current_frame = 0;
             self.media_loaded_once = false;
-        } else if request.is_paused && !self.is_paused {
+        }
+        self.set_paused(request.is_paused);
+    }
+
+    // Shown next to the resolution, RenderKit::new sets it from the Core
+    pub fn set_adapter(&mut self, adapter: String) {
+        self.adapter = Some(adapter);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused && !self.is_paused {
             self.pause_start = Some(std::time::Instant::now());
-        } else if !request.is_paused && self.is_paused {
+        } else if !paused && self.is_paused {
             if let Some(pause_start) = self.pause_start {
                 self.total_pause_duration += pause_start.elapsed().as_secs_f32();
             }
             self.pause_start = None;
         }
-        self.is_paused = request.is_paused;
+        self.is_paused = paused;
     }
 
     /// Extract video info from a video texture manager
@@ -358,6 +395,15 @@ impl ShaderControls {
                     ui.label(format!("Resolution: {}x{}", width, height));
                 });
             }
+            if let Some(adapter) = &request.adapter {
+                ui.label(format!("GPU: {}", adapter));
+            }
+            if let Some(stats) = &mut request.stats {
+                FrameStats::render_stats_ui_widget(ui, stats);
+            }
+            if let Some(render_scale) = &mut request.render_scale {
+                RenderScale::render_scale_ui_widget(ui, render_scale);
+            }
         });
     }
 }
\ No newline at end of file
@@ -0,0 +1,252 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use crate::CliOptions;
+
+/// How `Core` picks its adapter, device and surface. `ShaderApp` starts from
+/// `CoreConfig::default()` (or `ShaderApp::set_core_config`) and applies the command line on top.
+#[derive(Debug, Clone)]
+pub struct CoreConfig {
+    pub backends: wgpu::Backends,
+    pub power_preference: wgpu::PowerPreference,
+    // case insensitive part of the adapter name, e.g. "nvidia" or "llvmpipe"
+    pub adapter_name: Option<String>,
+    pub force_fallback_adapter: bool,
+    // Fifo is always there, other modes fall back to it when the surface lacks them
+    pub present_mode: wgpu::PresentMode,
+    pub desired_maximum_frame_latency: u32,
+    // device creation fails without these
+    pub required_features: wgpu::Features,
+    // enabled when the adapter has them, GpuProfiler falls back to CPU timing without timestamps
+    pub optional_features: wgpu::Features,
+    pub required_limits: wgpu::Limits,
+    // Rgba16Float surface when the display supports it, values above 1.0 reach the screen
+    pub hdr: bool,
+    // explicit surface format, wins over `hdr`
+    pub surface_format: Option<wgpu::TextureFormat>,
+    // None is opaque, transparent windows set it through `WindowOptions`
+    pub alpha_mode: Option<wgpu::CompositeAlphaMode>,
+}
+
+impl Default for CoreConfig {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::Backends::all(),
+            power_preference: wgpu::PowerPreference::default(),
+            adapter_name: None,
+            force_fallback_adapter: false,
+            present_mode: wgpu::PresentMode::Fifo,
+            desired_maximum_frame_latency: 2,
+            required_features: wgpu::Features::empty(),
+            optional_features: wgpu::Features::TIMESTAMP_QUERY,
+            required_limits: wgpu::Limits::default(),
+            hdr: false,
+            surface_format: None,
+            alpha_mode: None,
+        }
+    }
+}
+
+impl CoreConfig {
+    /// Applies `--backend`, `--adapter`, `--power`, `--present-mode` and `--hdr`.
+    pub fn with_cli(mut self, options: &CliOptions) -> Self {
+        if let Some(backends) = options.backends {
+            self.backends = backends;
+        }
+        if let Some(name) = &options.adapter {
+            self.adapter_name = Some(name.clone());
+        }
+        if let Some(power) = options.power_preference {
+            self.power_preference = power;
+        }
+        if let Some(mode) = options.present_mode {
+            self.present_mode = mode;
+        }
+        // exports render with the surface pipelines into 8-bit capture textures
+        self.hdr = (self.hdr || options.hdr) && !options.headless();
+        self
+    }
+
+    pub(crate) async fn select_adapter(
+        &self,
+        instance: &wgpu::Instance,
+        surface: &wgpu::Surface<'_>,
+    ) -> Result<wgpu::Adapter, CoreError> {
+        let Some(name) = &self.adapter_name else {
+            return instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: self.power_preference,
+                    compatible_surface: Some(surface),
+                    force_fallback_adapter: self.force_fallback_adapter,
+                })
+                .await
+                .ok_or(CoreError::NoAdapter);
+        };
+        let wanted = name.to_lowercase();
+        let mut available = Vec::new();
+        for adapter in instance.enumerate_adapters(self.backends) {
+            let info = adapter.get_info();
+            if !adapter.is_surface_supported(surface) {
+                continue;
+            }
+            if info.name.to_lowercase().contains(&wanted) {
+                return Ok(adapter);
+            }
+            available.push(adapter_label(&info));
+        }
+        Err(CoreError::AdapterNotFound { name: name.clone(), available })
+    }
+
+    // adapter, device and queue, also used to replace a lost device
+    pub(crate) async fn request_device(
+        &self,
+        instance: &wgpu::Instance,
+        surface: &wgpu::Surface<'_>,
+    ) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), CoreError> {
+        let adapter = self.select_adapter(instance, surface).await?;
+        self.check_limits(&adapter)?;
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: self.device_features(&adapter)?,
+                    required_limits: self.required_limits.clone(),
+                    memory_hints: Default::default(),
+                },
+                None,
+            )
+            .await
+            .map_err(CoreError::RequestDevice)?;
+        Ok((adapter, device, queue))
+    }
+
+    // required features plus the optional ones the adapter has, or the missing required ones
+    pub(crate) fn device_features(&self, adapter: &wgpu::Adapter) -> Result<wgpu::Features, CoreError> {
+        let supported = adapter.features();
+        let missing = self.required_features - supported;
+        if !missing.is_empty() {
+            return Err(CoreError::MissingFeatures(missing));
+        }
+        Ok(self.required_features | (self.optional_features & supported))
+    }
+
+    pub(crate) fn check_limits(&self, adapter: &wgpu::Adapter) -> Result<(), CoreError> {
+        let mut exceeded = Vec::new();
+        self.required_limits
+            .check_limits_with_fail_fn(&adapter.limits(), false, |name, requested, allowed| {
+                exceeded.push(format!("{} {} (adapter allows {})", name, requested, allowed));
+            });
+        if exceeded.is_empty() {
+            Ok(())
+        } else {
+            Err(CoreError::LimitsExceeded(exceeded))
+        }
+    }
+
+    pub(crate) fn surface_format(&self, caps: &wgpu::SurfaceCapabilities) -> wgpu::TextureFormat {
+        let srgb = caps.formats.iter().copied().find(|f| f.is_srgb()).unwrap_or(caps.formats[0]);
+        let wanted = self
+            .surface_format
+            .or(self.hdr.then_some(wgpu::TextureFormat::Rgba16Float));
+        match wanted {
+            Some(format) if caps.formats.contains(&format) => format,
+            Some(format) => {
+                log::warn!("Surface format {:?} is not supported here, using {:?}", format, srgb);
+                srgb
+            }
+            None => srgb,
+        }
+    }
+
+    pub(crate) fn alpha_mode(&self, caps: &wgpu::SurfaceCapabilities) -> wgpu::CompositeAlphaMode {
+        match self.alpha_mode {
+            Some(mode) if caps.alpha_modes.contains(&mode) => mode,
+            Some(mode) => {
+                log::warn!("Alpha mode {:?} is not supported here, the window stays opaque", mode);
+                caps.alpha_modes[0]
+            }
+            None => caps.alpha_modes[0],
+        }
+    }
+
+    pub(crate) fn present_mode(&self, caps: &wgpu::SurfaceCapabilities) -> wgpu::PresentMode {
+        // auto modes fall back on their own
+        match self.present_mode {
+            mode @ (wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync) => mode,
+            mode if caps.present_modes.contains(&mode) => mode,
+            mode => {
+                log::warn!("Present mode {:?} is not supported here, using Fifo", mode);
+                wgpu::PresentMode::Fifo
+            }
+        }
+    }
+}
+
+// Flag set when the driver loses the device. Until Core replaces it, errors from calls on the
+// dead device are ignored instead of panicking like the default handler does.
+pub(crate) fn watch_device(device: &wgpu::Device) -> Arc<AtomicBool> {
+    let lost = Arc::new(AtomicBool::new(false));
+    let flag = lost.clone();
+    device.set_device_lost_callback(move |reason, message| {
+        // Destroyed is our own drop of the old device
+        if reason != wgpu::DeviceLostReason::Destroyed {
+            log::error!("GPU device lost: {}", message);
+            flag.store(true, Ordering::Relaxed);
+        }
+    });
+    let flag = lost.clone();
+    device.on_uncaptured_error(Box::new(move |error| {
+        // hot reload catches this panic to reject broken shaders
+        if !flag.load(Ordering::Relaxed) {
+            panic!("wgpu error: {}", error);
+        }
+    }));
+    lost
+}
+
+/// Name and backend of an adapter as shown in the UI, e.g. "NVIDIA GeForce RTX 3070 (Vulkan)"
+pub fn adapter_label(info: &wgpu::AdapterInfo) -> String {
+    format!("{} ({:?})", info.name, info.backend)
+}
+
+#[derive(Debug)]
+pub enum CoreError {
+    CreateSurface(wgpu::CreateSurfaceError),
+    NoAdapter,
+    AdapterNotFound { name: String, available: Vec<String> },
+    MissingFeatures(wgpu::Features),
+    LimitsExceeded(Vec<String>),
+    RequestDevice(wgpu::RequestDeviceError),
+    // the adapter can't present to this window at all
+    UnsupportedSurface,
+}
+
+impl std::fmt::Display for CoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoreError::CreateSurface(e) => write!(f, "Failed to create surface: {}", e),
+            CoreError::NoAdapter => write!(f, "No GPU adapter can present to this window"),
+            CoreError::AdapterNotFound { name, available } => {
+                write!(f, "No adapter matches '{}'", name)?;
+                if available.is_empty() {
+                    write!(f, ", none can present to this window")
+                } else {
+                    write!(f, ", available: {}", available.join(", "))
+                }
+            }
+            CoreError::MissingFeatures(features) => write!(f, "Adapter lacks required features: {:?}", features),
+            CoreError::LimitsExceeded(limits) => write!(f, "Adapter limits too low: {}", limits.join(", ")),
+            CoreError::RequestDevice(e) => write!(f, "Failed to create device: {}", e),
+            CoreError::UnsupportedSurface => write!(f, "Adapter reports no formats for this surface"),
+        }
+    }
+}
+
+impl std::error::Error for CoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CoreError::CreateSurface(e) => Some(e),
+            CoreError::RequestDevice(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -1,11 +1,63 @@
 use std::path::PathBuf;
 use std::sync::mpsc;
 use image::ImageError;
+use crate::screenshot::write_png_with_text;
+use crate::{read_float_texture, DownsampleFilter, FrameMetadata};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    // 8-bit sRGB PNG through CAPTURE_FORMAT
+    #[default]
+    Png8,
+    // the formats below read a float texture, see FrameCapture::float_output
+    Png16,
+    ExrHalf,
+    ExrFloat,
+    // Radiance RGBE, alpha is dropped
+    Hdr,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 5] = [
+        ExportFormat::Png8,
+        ExportFormat::Png16,
+        ExportFormat::ExrHalf,
+        ExportFormat::ExrFloat,
+        ExportFormat::Hdr,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Png8 => "PNG 8-bit",
+            ExportFormat::Png16 => "PNG 16-bit",
+            ExportFormat::ExrHalf => "EXR half",
+            ExportFormat::ExrFloat => "EXR float",
+            ExportFormat::Hdr => "Radiance HDR",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Png8 | ExportFormat::Png16 => "png",
+            ExportFormat::ExrHalf | ExportFormat::ExrFloat => "exr",
+            ExportFormat::Hdr => "hdr",
+        }
+    }
+
+    // true when frames should come from a float texture instead of the 8-bit capture
+    pub fn is_float(&self) -> bool {
+        *self != ExportFormat::Png8
+    }
+}
 
 #[derive(Debug)]
 pub enum ExportError {
     IoError(std::io::Error),
     ImageError(ImageError),
+    GpuError(String),
+    InvalidMetadata(String),
+    // the settings need something the shader can't do, e.g. tiling
+    Unsupported(String),
 }
 
 impl From<std::io::Error> for ExportError {
@@ -14,6 +66,12 @@ impl From<std::io::Error> for ExportError {
     }
 }
 
+impl From<png::EncodingError> for ExportError {
+    fn from(err: png::EncodingError) -> Self {
+        ExportError::IoError(std::io::Error::other(err))
+    }
+}
+
 impl From<ImageError> for ExportError {
     fn from(err: ImageError) -> Self {
         ExportError::ImageError(err)
@@ -28,6 +86,14 @@ pub struct ExportSettings {
     pub end_time: f32,
     pub fps: u32,
     pub is_exporting: bool,
+    // render at N times the size and filter down, 1 disables supersampling
+    pub supersample: u32,
+    pub downsample_filter: DownsampleFilter,
+    // caps the tile size below the device limit, None tiles only when needed
+    pub max_tile_size: Option<u32>,
+    pub format: ExportFormat,
+    // written as PNG text chunks by save_frame, filled per frame by FrameCapture::handle_export
+    pub metadata: Option<FrameMetadata>,
 }
 
 impl Default for ExportSettings {
@@ -40,6 +106,11 @@ impl Default for ExportSettings {
             end_time: 5.0,
             fps: 60,
             is_exporting: false,
+            supersample: 1,
+            downsample_filter: DownsampleFilter::Box,
+            max_tile_size: None,
+            format: ExportFormat::Png8,
+            metadata: None,
         }
     }
 }
@@ -52,6 +123,11 @@ pub struct ExportUiRequest {
     pub fps: u32,
     pub path: PathBuf,
     pub is_exporting: bool,
+    pub supersample: u32,
+    pub downsample_filter: DownsampleFilter,
+    pub format: ExportFormat,
+    // set when "Load Preset from Image" picked a file
+    pub load_preset: Option<PathBuf>,
 }
 #[derive(Default)]
 pub struct ExportUiState {
@@ -69,6 +145,10 @@ pub struct ExportManager {
     export_channel: Option<mpsc::Receiver<(u32, f32)>>,
     ui_state: ExportUiState,
     temp_state: TempExportState,
+    preset_request: Option<PathBuf>,
+    // set by --export, started once any startup preset is applied
+    queued_export: bool,
+    exit_when_done: bool,
 }
 
 #[derive(Clone)]
@@ -79,6 +159,9 @@ struct TempExportState {
     end_time: f32,
     fps: u32,
     path: PathBuf,
+    supersample: u32,
+    downsample_filter: DownsampleFilter,
+    format: ExportFormat,
 }
 
 impl ExportManager {
@@ -92,6 +175,9 @@ impl ExportManager {
             end_time: settings.end_time,
             fps: settings.fps,
             path: settings.export_path.clone(),
+            supersample: settings.supersample,
+            downsample_filter: settings.downsample_filter,
+            format: settings.format,
         };
         
         Self {
@@ -99,6 +185,9 @@ impl ExportManager {
             export_channel: None,
             ui_state,
             temp_state,
+            preset_request: None,
+            queued_export: false,
+            exit_when_done: false,
         }
     }
     pub fn get_ui_request(&self) -> ExportUiRequest {
@@ -110,6 +199,10 @@ impl ExportManager {
             fps: self.temp_state.fps,
             path: self.temp_state.path.clone(),
             is_exporting: self.settings.is_exporting,
+            supersample: self.temp_state.supersample,
+            downsample_filter: self.temp_state.downsample_filter,
+            format: self.temp_state.format,
+            load_preset: None,
         }
     }
     pub fn apply_ui_request(&mut self, request: ExportUiRequest) {
@@ -119,6 +212,46 @@ impl ExportManager {
         self.temp_state.end_time = request.end_time;
         self.temp_state.fps = request.fps;
         self.temp_state.path = request.path;
+        self.temp_state.supersample = request.supersample;
+        self.temp_state.downsample_filter = request.downsample_filter;
+        self.temp_state.format = request.format;
+        if request.load_preset.is_some() {
+            self.preset_request = request.load_preset;
+        }
+    }
+    /// Image picked with "Load Preset from Image", see `FrameCapture::handle_preset_request`
+    pub fn take_preset_request(&mut self) -> Option<PathBuf> {
+        self.preset_request.take()
+    }
+    pub fn request_preset(&mut self, path: PathBuf) {
+        self.preset_request = Some(path);
+    }
+    /// Sets up the export of a command line `ExportRange`, started by `FrameCapture::handle_preset_request`
+    pub fn queue_export(&mut self, range: &crate::ExportRange) {
+        self.temp_state.start_time = range.start;
+        self.temp_state.end_time = range.end;
+        if let Some(fps) = range.fps {
+            self.temp_state.fps = fps;
+        }
+        if let Some((width, height)) = range.size {
+            self.temp_state.width = width;
+            self.temp_state.height = height;
+        }
+        if let Some(path) = &range.path {
+            self.temp_state.path = path.clone();
+        }
+        if let Some(format) = range.format {
+            self.temp_state.format = format;
+        }
+        self.queued_export = true;
+        self.exit_when_done = true;
+    }
+    pub fn take_queued_export(&mut self) -> bool {
+        std::mem::take(&mut self.queued_export)
+    }
+    // true when the finished export came from the command line and the app should close
+    pub fn exit_when_done(&self) -> bool {
+        self.exit_when_done
     }
     /// Returns a reference to the current export settings
     pub fn settings(&self) -> &ExportSettings {
@@ -149,22 +282,24 @@ impl ExportManager {
         self.settings.end_time = self.temp_state.end_time;
         self.settings.fps = self.temp_state.fps;
         self.settings.export_path = self.temp_state.path.clone();
+        self.settings.supersample = self.temp_state.supersample;
+        self.settings.downsample_filter = self.temp_state.downsample_filter;
+        self.settings.format = self.temp_state.format;
         
         // Then start the export process
         self.settings.is_exporting = true;
-        let settings = self.settings.clone();
+        let settings = &self.settings;
         let (tx, rx) = mpsc::channel();
 
-        std::thread::spawn(move || {
-            let total_frames = ((settings.end_time - settings.start_time) * settings.fps as f32) as u32;
-            
-            for frame in 0..total_frames {
-                let time = settings.start_time + (frame as f32 / settings.fps as f32);
-                if tx.send((frame, time)).is_err() {
-                    break;
-                }
+        // queued up front so the first try_get_next_frame never sees an empty channel
+        // and ends the export early
+        let total_frames = ((settings.end_time - settings.start_time) * settings.fps as f32) as u32;
+        for frame in 0..total_frames {
+            let time = settings.start_time + (frame as f32 / settings.fps as f32);
+            if tx.send((frame, time)).is_err() {
+                break;
             }
-        });
+        }
         
         self.export_channel = Some(rx);
     }
@@ -188,13 +323,24 @@ impl ExportManager {
             if !request.is_exporting {
                 // Resolution section
                 ui.collapsing("Resolution", |ui| {
+                    // sizes above the device texture limit are rendered in tiles
                     ui.add(egui::DragValue::new(&mut request.width)
-                        .range(1..=7680)
+                        .range(1..=32768)
                         .prefix("Width: "));
                         
                     ui.add(egui::DragValue::new(&mut request.height)
-                        .range(1..=4320)
+                        .range(1..=32768)
                         .prefix("Height: "));
+
+                    ui.add(egui::Slider::new(&mut request.supersample, 1..=4)
+                        .text("Supersample"));
+                    if request.supersample > 1 {
+                        ui.horizontal(|ui| {
+                            ui.label("Filter:");
+                            ui.radio_value(&mut request.downsample_filter, DownsampleFilter::Box, "Box");
+                            ui.radio_value(&mut request.downsample_filter, DownsampleFilter::Tent, "Tent");
+                        });
+                    }
                 });
                 ui.collapsing("Time Settings", |ui| {
                     ui.add(egui::DragValue::new(&mut request.start_time)
@@ -210,6 +356,18 @@ impl ExportManager {
                         .prefix("FPS: "));
                 });
                 ui.collapsing("Output", |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("Format:");
+                        for format in ExportFormat::ALL {
+                            ui.radio_value(&mut request.format, format, format.label());
+                        }
+                    });
+                    if request.format.is_float() {
+                        ui.label(
+                            egui::RichText::new("Float formats save the output texture at its own size")
+                                .weak()
+                        );
+                    }
                     ui.horizontal(|ui| {
                         ui.label("Export Path:");
                         if ui.button("Browse").clicked() {
@@ -241,6 +399,15 @@ impl ExportManager {
                             );
                         });
                     }
+                    // PNGs carry the shader params, time and source hash as text chunks
+                    if ui.button("Load Preset from Image").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("PNG", &["png"])
+                            .set_directory(&request.path)
+                            .pick_file() {
+                            request.load_preset = Some(path);
+                        }
+                    }
                 });
                 ui.separator();
                 if ui.button("Start Export").clicked() {
@@ -275,7 +442,17 @@ impl ExportManager {
         }
     }
 }
-#[allow(unused_mut)]
+// Captures are BGRA on macOS (CAPTURE_FORMAT), swap them to RGBA in place
+#[allow(unused_variables)]
+pub(crate) fn capture_to_rgba(data: &mut [u8]) {
+    #[cfg(target_os = "macos")]
+    {
+        for chunk in data.chunks_mut(4) {
+            chunk.swap(0, 2);
+        }
+    }
+}
+
 pub fn save_frame(mut data: Vec<u8>, frame: u32, settings: &ExportSettings) -> Result<(), ExportError> {
     let frame_path = settings.export_path
         .join(format!("frame_{:05}.png", frame));
@@ -283,11 +460,9 @@ pub fn save_frame(mut data: Vec<u8>, frame: u32, settings: &ExportSettings) -> R
     if let Some(parent) = frame_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    #[cfg(target_os = "macos")]
-    {
-        for chunk in data.chunks_mut(4) {
-            chunk.swap(0, 2);
-        }
+    capture_to_rgba(&mut data);
+    if let Some(metadata) = &settings.metadata {
+        return write_png_with_text(&frame_path, &data, settings.width, settings.height, png::BitDepth::Eight, &metadata.to_text_chunks());
     }
     let image = image::ImageBuffer::<image::Rgba<u8>, Vec<u8>>::from_raw(
         settings.width,
@@ -303,4 +478,103 @@ pub fn save_frame(mut data: Vec<u8>, frame: u32, settings: &ExportSettings) -> R
     
     image.save(&frame_path)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+fn srgb_encode(linear: f32) -> f32 {
+    let linear = linear.clamp(0.0, 1.0);
+    if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Saves linear RGBA f32 pixels in `settings.format`. PNGs are sRGB encoded and clamped,
+/// EXR and HDR keep the linear values unclamped. `settings.metadata` goes into PNG text
+/// chunks and EXR header attributes; Radiance HDR has no place for it.
+pub fn save_frame_float(pixels: &[f32], width: u32, height: u32, frame: u32, settings: &ExportSettings) -> Result<(), ExportError> {
+    let frame_path = settings.export_path
+        .join(format!("frame_{:05}.{}", frame, settings.format.extension()));
+
+    if let Some(parent) = frame_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let buffer_error = || ImageError::Parameter(
+        image::error::ParameterError::from_kind(
+            image::error::ParameterErrorKind::Generic(
+                "Failed to create image buffer".to_string()
+            )
+        )
+    );
+    // alpha is linear in every format
+    let encode = |i: usize, value: f32| if i % 4 == 3 { value.clamp(0.0, 1.0) } else { srgb_encode(value) };
+    let metadata = settings.metadata.as_ref().map(FrameMetadata::to_text_chunks).unwrap_or_default();
+    match settings.format {
+        ExportFormat::Png8 => {
+            let data: Vec<u8> = pixels.iter().enumerate()
+                .map(|(i, &v)| (encode(i, v) * 255.0).round() as u8)
+                .collect();
+            write_png_with_text(&frame_path, &data, width, height, png::BitDepth::Eight, &metadata)?;
+        }
+        ExportFormat::Png16 => {
+            // PNG stores 16-bit samples big endian
+            let data: Vec<u8> = pixels.iter().enumerate()
+                .flat_map(|(i, &v)| ((encode(i, v) * 65535.0).round() as u16).to_be_bytes())
+                .collect();
+            write_png_with_text(&frame_path, &data, width, height, png::BitDepth::Sixteen, &metadata)?;
+        }
+        ExportFormat::ExrFloat => {
+            write_exr(&frame_path, width, height, pixels, |v| v, &metadata)?;
+        }
+        ExportFormat::ExrHalf => {
+            write_exr(&frame_path, width, height, pixels, exr::prelude::f16::from_f32, &metadata)?;
+        }
+        ExportFormat::Hdr => {
+            let data = pixels.chunks_exact(4)
+                .flat_map(|pixel| [pixel[0].max(0.0), pixel[1].max(0.0), pixel[2].max(0.0)])
+                .collect();
+            image::Rgb32FImage::from_raw(width, height, data)
+                .ok_or_else(buffer_error)?
+                .save(&frame_path)?;
+        }
+    }
+    Ok(())
+}
+
+// RGBA EXR with the metadata as text attributes in the header. EXR text is Latin-1, so
+// entries that don't fit are left out.
+fn write_exr<T: exr::prelude::IntoSample>(
+    path: &std::path::Path,
+    width: u32,
+    height: u32,
+    pixels: &[f32],
+    sample: impl Sync + Fn(f32) -> T,
+    metadata: &[(String, String)],
+) -> Result<(), ExportError> {
+    use exr::prelude::*;
+    let channels = SpecificChannels::rgba(|Vec2(x, y)| {
+        let i = (y * width as usize + x) * 4;
+        (sample(pixels[i]), sample(pixels[i + 1]), sample(pixels[i + 2]), sample(pixels[i + 3]))
+    });
+    let mut image = Image::from_channels((width as usize, height as usize), channels);
+    for (key, value) in metadata {
+        if let (Some(key), Some(value)) = (Text::new_or_none(key), Text::new_or_none(value)) {
+            image.attributes.other.insert(key, AttributeValue::Text(value));
+        }
+    }
+    image.write().to_file(path)
+        .map_err(|e| ExportError::IoError(std::io::Error::other(e.to_string())))
+}
+
+/// Reads a `Rgba16Float`/`Rgba32Float` texture and saves it with `save_frame_float`.
+/// The frame keeps the texture size, the export width/height only apply to captures.
+pub fn save_texture_frame(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    frame: u32,
+    settings: &ExportSettings,
+) -> Result<(), ExportError> {
+    let pixels = read_float_texture(device, queue, texture)?;
+    save_frame_float(&pixels, texture.width(), texture.height(), frame, settings)
+}
@@ -1,4 +1,6 @@
 use crate::{Core, UniformProvider, UniformBinding, TextureManager};
+use crate::sdf::{distance_glyph, GlyphBitmap};
+use crate::text::{GlyphInstance, TextBlock};
 use fontdue::{Font, FontSettings};
 use std::collections::HashMap;
 use bytemuck::{Pod, Zeroable};
@@ -8,13 +10,101 @@ use bytemuck::{Pod, Zeroable};
 // _CELL_SIZE: u32 = 64;
 // _GRID_SIZE: u32 = 16;
 
+// the ASCII grid keeps rows 0-7 of the first 1024x1024, dynamic glyphs are packed below it
+const GRID_ATLAS_SIZE: u32 = 1024;
+const GRID_CELL_SIZE: u32 = 64;
+const GRID_RESERVED_HEIGHT: u32 = 8 * GRID_CELL_SIZE;
+// empty texels around each packed glyph so linear filtering does not bleed
+const GLYPH_PADDING: u32 = 1;
+// distance field falloff in atlas texels on each side of the outline
+const DISTANCE_RANGE: f32 = 6.0;
+const INITIAL_TEXT_CAPACITY: usize = 256;
+
+/// Header of the text buffer at binding 3, followed by the glyphs:
+/// ```wgsl
+/// struct TextBuffer {
+///     count: u32,
+///     lines: u32,
+///     size: vec2<f32>,
+///     glyphs: array<GlyphInstance>,
+/// };
+/// @group(3) @binding(3) var<storage, read> u_text: TextBuffer;
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+pub struct TextHeader {
+    pub count: u32,
+    pub lines: u32,
+    pub size: [f32; 2],
+}
+
+/// What the atlas stores per texel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GlyphMode {
+    // white RGB with coverage alpha
+    #[default]
+    Coverage,
+    // signed distance in all four channels, 0.5 on the outline
+    Sdf,
+    // multi-channel distance in RGB (take the median) and the true distance in alpha
+    Msdf,
+}
+
+/// Atlas description and text effects for shaders. The first three fields keep the layout
+/// older shaders declare, distance field shaders can use the rest:
+/// ```wgsl
+/// struct FontUniforms {
+///     atlas_size: vec2<f32>,
+///     char_size: vec2<f32>,
+///     screen_size: vec2<f32>,
+///     distance_range: f32,
+///     glyph_mode: u32,
+///     fill_color: vec4<f32>,
+///     outline_color: vec4<f32>,
+///     glow_color: vec4<f32>,
+///     shadow_color: vec4<f32>,
+///     outline_width: f32,
+///     glow_radius: f32,
+///     shadow_offset: vec2<f32>,
+/// };
+/// ```
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct FontUniforms {
     pub atlas_size: [f32; 2],
     pub char_size: [f32; 2],
     pub screen_size: [f32; 2],
-    pub _padding: [f32; 2],
+    // distance field falloff in atlas texels, 0 for coverage atlases
+    pub distance_range: f32,
+    // GlyphMode as 0 coverage, 1 sdf, 2 msdf
+    pub glyph_mode: u32,
+    pub fill_color: [f32; 4],
+    pub outline_color: [f32; 4],
+    pub glow_color: [f32; 4],
+    pub shadow_color: [f32; 4],
+    // effect sizes in atlas texels, keep them below distance_range
+    pub outline_width: f32,
+    pub glow_radius: f32,
+    pub shadow_offset: [f32; 2],
+}
+
+impl Default for FontUniforms {
+    fn default() -> Self {
+        Self {
+            atlas_size: [512.0, 512.0],
+            char_size: [32.0, 32.0],
+            screen_size: [0.0, 0.0],
+            distance_range: 0.0,
+            glyph_mode: 0,
+            fill_color: [1.0, 1.0, 1.0, 1.0],
+            outline_color: [0.0, 0.0, 0.0, 1.0],
+            glow_color: [1.0, 0.8, 0.3, 0.8],
+            shadow_color: [0.0, 0.0, 0.0, 0.6],
+            outline_width: 0.0,
+            glow_radius: 0.0,
+            shadow_offset: [0.0, 0.0],
+        }
+    }
 }
 
 impl UniformProvider for FontUniforms {
@@ -23,6 +113,8 @@ impl UniformProvider for FontUniforms {
     }
 }
 
+/// A cell of the ASCII grid. The UVs are normalized to the current atlas size and rescaled
+/// when the atlas grows; the cell itself stays at `grid * char_size` texels.
 #[derive(Clone, Copy)]
 pub struct CharInfo {
     pub uv_min: [f32; 2],
@@ -32,19 +124,144 @@ pub struct CharInfo {
     pub advance: f32,
 }
 
+/// A glyph packed into the dynamic part of the atlas. The pixel rect never moves while the
+/// glyph stays cached, also when the atlas grows; only the normalized UVs change with it.
+/// For distance field atlases the rect and bearing include the falloff around the outline.
+#[derive(Clone, Copy, Debug)]
+pub struct GlyphInfo {
+    // top left texel of the glyph bitmap, padding excluded
+    pub position: [u32; 2],
+    pub size: [u32; 2],
+    // pen position to the bitmap's left edge and to its top edge above the baseline
+    pub bearing: [f32; 2],
+    pub advance: f32,
+    // font the glyph came from, differs from the requested one when a fallback font had it
+    pub font: usize,
+}
+
+impl GlyphInfo {
+    pub fn uv(&self, atlas_size: u32) -> ([f32; 2], [f32; 2]) {
+        let scale = 1.0 / atlas_size as f32;
+        (
+            [self.position[0] as f32 * scale, self.position[1] as f32 * scale],
+            [(self.position[0] + self.size[0]) as f32 * scale, (self.position[1] + self.size[1]) as f32 * scale],
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    font: usize,
+    ch: char,
+    size: u32,
+}
+
+struct GlyphEntry {
+    info: GlyphInfo,
+    shelf: Option<usize>,
+}
+
+// a row of glyphs with the same height class, filled left to right
+struct Shelf {
+    y: u32,
+    height: u32,
+    cursor: u32,
+    last_used: u64,
+}
+
+// packs glyph rects into shelves between `top` and the bottom of a `size` square atlas
+struct ShelfPacker {
+    shelves: Vec<Shelf>,
+    next_shelf_y: u32,
+    size: u32,
+}
+
+impl ShelfPacker {
+    fn new(top: u32, size: u32) -> Self {
+        Self { shelves: Vec::new(), next_shelf_y: top, size }
+    }
+
+    // (shelf, x, y) of a free rect, None when no shelf has room and no new one fits
+    fn allocate(&mut self, width: u32, height: u32, clock: u64) -> Option<(usize, u32, u32)> {
+        let shelf_height = height.next_multiple_of(4);
+        let size = self.size;
+        // tightest shelf with room, emptied shelves take anything that fits
+        let best = self
+            .shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.height >= height && s.cursor + width <= size)
+            .filter(|(_, s)| s.cursor == 0 || s.height <= shelf_height * 2)
+            .min_by_key(|(_, s)| s.height)
+            .map(|(index, _)| index);
+        if let Some(index) = best {
+            let shelf = &mut self.shelves[index];
+            let x = shelf.cursor;
+            shelf.cursor += width;
+            shelf.last_used = clock;
+            return Some((index, x, shelf.y));
+        }
+        if width > size || self.next_shelf_y + shelf_height > size {
+            return None;
+        }
+        let y = self.next_shelf_y;
+        self.next_shelf_y += shelf_height;
+        self.shelves.push(Shelf { y, height: shelf_height, cursor: width, last_used: clock });
+        Some((self.shelves.len() - 1, 0, y))
+    }
+
+    fn touch(&mut self, shelf: usize, clock: u64) {
+        self.shelves[shelf].last_used = clock;
+    }
+
+    // empties the least recently used shelf not touched at `clock` and returns its index
+    fn evict(&mut self, clock: u64) -> Option<usize> {
+        let index = self
+            .shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.cursor > 0 && s.last_used < clock)
+            .min_by_key(|(_, s)| s.last_used)
+            .map(|(index, _)| index)?;
+        self.shelves[index].cursor = 0;
+        // empty shelves at the bottom give their rows back so taller glyphs fit again
+        while let Some(shelf) = self.shelves.last().filter(|s| s.cursor == 0) {
+            self.next_shelf_y = shelf.y;
+            self.shelves.pop();
+        }
+        Some(index)
+    }
+}
+
 pub struct FontSystem {
     pub font: Font,
     pub atlas_texture: TextureManager,
     pub char_map: HashMap<char, CharInfo>,
     pub font_uniforms: UniformBinding<FontUniforms>,
     pub font_bind_group_layout: wgpu::BindGroupLayout,
+    // the atlas doubles up to this size before old glyphs get evicted
+    pub max_atlas_size: u32,
+    // laid out glyphs for shaders, see `write_text`
+    pub text_buffer: wgpu::Buffer,
+    text_capacity: usize,
+    glyph_mode: GlyphMode,
+    fallback_fonts: Vec<Font>,
+    // raw font files by id, distance fields are built from the outlines
+    font_data: Vec<Vec<u8>>,
+    glyphs: HashMap<GlyphKey, GlyphEntry>,
+    packer: ShelfPacker,
+    clock: u64,
+    generation: u64,
+    // replacements of the text buffer, which only need a new bind group
+    text_buffer_generation: u64,
+    bound_generation: (u64, u64),
 }
 
 
 impl FontSystem {
-    fn generate_simple_atlas(font: &Font) -> (Vec<u8>, u32, u32) {
-        let atlas_size = 1024u32;  
-        let cell_size = 64u32;
+    fn generate_simple_atlas(rasterize: impl Fn(char) -> Option<GlyphBitmap>) -> (Vec<u8>, u32, u32) {
+        let atlas_size = GRID_ATLAS_SIZE;
+        let cell_size = GRID_CELL_SIZE;
         let grid_size = 16u32;
         let mut atlas_data = vec![0u8; (atlas_size * atlas_size * 4) as usize];
         
@@ -55,11 +272,11 @@ impl FontSystem {
             let grid_y = ascii_code / grid_size;
             let cell_x = grid_x * cell_size;
             let cell_y = grid_y * cell_size;
-            //note: larger, more quality:
-            let font_size = 48.0;
-            let (metrics, bitmap) = font.rasterize(ch, font_size);
+            let Some(glyph) = rasterize(ch) else {
+                continue;
+            };
             
-            if bitmap.is_empty() {
+            if glyph.pixels.is_empty() {
                 continue;
             }
             
@@ -67,37 +284,31 @@ impl FontSystem {
             let available_width = cell_size - padding * 2;
             let available_height = cell_size - padding * 2;
             
-            let scale_x = available_width as f32 / metrics.width as f32;
-            let scale_y = available_height as f32 / metrics.height as f32;
+            let scale_x = available_width as f32 / glyph.width as f32;
+            let scale_y = available_height as f32 / glyph.height as f32;
             let scale = scale_x.min(scale_y).min(1.0);
             
-            let scaled_width = (metrics.width as f32 * scale) as u32;
-            let scaled_height = (metrics.height as f32 * scale) as u32;
+            let scaled_width = (glyph.width as f32 * scale) as u32;
+            let scaled_height = (glyph.height as f32 * scale) as u32;
             
             let offset_x = cell_x + padding + (available_width - scaled_width) / 2;
             let offset_y = cell_y + padding + (available_height - scaled_height) / 2;
             
             for y in 0..scaled_height {
                 for x in 0..scaled_width {
-                    let src_x = (x as f32 / scale) as usize;
-                    let src_y = (y as f32 / scale) as usize;
+                    let src_x = (x as f32 / scale) as u32;
+                    let src_y = (y as f32 / scale) as u32;
                     
-                    if src_x < metrics.width && src_y < metrics.height {
+                    if src_x < glyph.width && src_y < glyph.height {
                         let atlas_x = offset_x + x;
                         let atlas_y = offset_y + y;
                         
                         if atlas_x < atlas_size && atlas_y < atlas_size {
                             let atlas_idx = ((atlas_y * atlas_size + atlas_x) * 4) as usize;
-                            let src_idx = src_y * metrics.width + src_x;
+                            let src_idx = (src_y * glyph.width + src_x) as usize;
                             
-                            if atlas_idx + 3 < atlas_data.len() && src_idx < bitmap.len() {
-                                let alpha = bitmap[src_idx];
-
-                                let corrected_alpha = ((alpha as f32 / 255.0).powf(0.8) * 255.0) as u8;
-                                atlas_data[atlas_idx] = 255;     // R
-                                atlas_data[atlas_idx + 1] = 255; // G
-                                atlas_data[atlas_idx + 2] = 255; // B
-                                atlas_data[atlas_idx + 3] = corrected_alpha; // A
+                            if atlas_idx + 3 < atlas_data.len() && src_idx < glyph.pixels.len() {
+                                atlas_data[atlas_idx..atlas_idx + 4].copy_from_slice(&glyph.pixels[src_idx]);
                             }
                         }
                     }
@@ -108,8 +319,40 @@ impl FontSystem {
         (atlas_data, atlas_size, atlas_size)
     }
 
+    fn rasterize_with(font: &Font, font_data: &[u8], mode: GlyphMode, ch: char, size: f32) -> Option<GlyphBitmap> {
+        if mode != GlyphMode::Coverage {
+            if let Some(glyph) = distance_glyph(font_data, ch, size, DISTANCE_RANGE, mode == GlyphMode::Msdf) {
+                return Some(glyph);
+            }
+        }
+        let (metrics, bitmap) = font.rasterize(ch, size);
+        let pixels = bitmap
+            .iter()
+            .map(|&alpha| {
+                let corrected_alpha = ((alpha as f32 / 255.0).powf(0.8) * 255.0) as u8;
+                [255, 255, 255, corrected_alpha]
+            })
+            .collect();
+        Some(GlyphBitmap {
+            width: metrics.width as u32,
+            height: metrics.height as u32,
+            bearing: [metrics.xmin as f32, (metrics.ymin + metrics.height as i32) as f32],
+            advance: metrics.advance_width,
+            pixels,
+        })
+    }
+
+    fn rasterize(&self, font: usize, ch: char, size: f32) -> Option<GlyphBitmap> {
+        Self::rasterize_with(self.font_by_id(font)?, self.font_data.get(font)?, self.glyph_mode, ch, size)
+    }
+
 
     pub fn new(core: &Core, font_data: &[u8]) -> Self {
+        Self::new_with_mode(core, font_data, GlyphMode::Coverage)
+    }
+
+    /// Like `new`, with the ASCII grid and every packed glyph stored as `mode`.
+    pub fn new_with_mode(core: &Core, font_data: &[u8], mode: GlyphMode) -> Self {
         let font = Font::from_bytes(font_data, FontSettings::default()).unwrap();
         
         let font_bind_group_layout = core.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -143,15 +386,26 @@ impl FontSystem {
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: None,
                 },
+                // Laid out text
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
             label: Some("Font Bind Group Layout"),
         });
 
         let font_uniforms_data = FontUniforms {
-            atlas_size: [512.0, 512.0],
-            char_size: [32.0, 32.0],
             screen_size: [core.size.width as f32, core.size.height as f32],
-            _padding: [0.0, 0.0],
+            distance_range: if mode == GlyphMode::Coverage { 0.0 } else { DISTANCE_RANGE },
+            glyph_mode: mode as u32,
+            ..Default::default()
         };
 
         //separate uniform layout for just the font uniforms buffer
@@ -179,7 +433,7 @@ impl FontSystem {
             0,
         );
 
-        let (atlas_texture, char_map, atlas_width, atlas_height) = Self::create_font_atlas(core, &font);
+        let (atlas_texture, char_map, atlas_width, atlas_height) = Self::create_font_atlas(core, &font, font_data, mode);
         
         //font uniforms update with actual atlas dimensions
         let mut font_system = Self {
@@ -188,6 +442,18 @@ impl FontSystem {
             char_map,
             font_uniforms,
             font_bind_group_layout,
+            max_atlas_size: core.device.limits().max_texture_dimension_2d.min(4096),
+            glyph_mode: mode,
+            fallback_fonts: Vec::new(),
+            font_data: vec![font_data.to_vec()],
+            glyphs: HashMap::new(),
+            packer: ShelfPacker::new(GRID_RESERVED_HEIGHT, atlas_width),
+            text_buffer: Self::create_text_buffer(&core.device, INITIAL_TEXT_CAPACITY),
+            text_capacity: INITIAL_TEXT_CAPACITY,
+            clock: 0,
+            generation: 0,
+            text_buffer_generation: 0,
+            bound_generation: (0, 0),
         };
         
         font_system.font_uniforms.data.atlas_size = [atlas_width as f32, atlas_height as f32];
@@ -197,8 +463,13 @@ impl FontSystem {
         font_system
     }
 
-    fn create_font_atlas(core: &Core, font: &Font) -> (TextureManager, HashMap<char, CharInfo>, u32, u32) {
-        let (atlas_data, width, height) = Self::generate_simple_atlas(font);
+    fn create_font_atlas(core: &Core, font: &Font, font_data: &[u8], mode: GlyphMode) -> (TextureManager, HashMap<char, CharInfo>, u32, u32) {
+        //note: larger, more quality; distance fields add their falloff around the 48px box
+        let font_size = match mode {
+            GlyphMode::Coverage => 48.0,
+            GlyphMode::Sdf | GlyphMode::Msdf => 48.0 - 2.0 * DISTANCE_RANGE,
+        };
+        let (atlas_data, width, height) = Self::generate_simple_atlas(|ch| Self::rasterize_with(font, font_data, mode, ch, font_size));
         let mut char_map = HashMap::new();
         
         let grid_size = 16;
@@ -218,24 +489,10 @@ impl FontSystem {
             char_map.insert(ch, char_info);
         }
         
-        let texture = core.device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Shadertoy Font Atlas"),
-            size: wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
-        
+        let atlas_texture = Self::create_atlas_texture(core, width);
         core.queue.write_texture(
             wgpu::TexelCopyTextureInfo {
-                texture: &texture,
+                texture: &atlas_texture.texture,
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
@@ -253,6 +510,26 @@ impl FontSystem {
             },
         );
         
+        (atlas_texture, char_map, width, height)
+    }
+
+    fn create_atlas_texture(core: &Core, size: u32) -> TextureManager {
+        let texture = core.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadertoy Font Atlas"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            // COPY_SRC so the atlas can grow by copying into a larger texture
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let sampler = core.device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -301,14 +578,12 @@ impl FontSystem {
             label: Some("Font Atlas Bind Group"),
         });
         
-        let atlas_texture = TextureManager {
+        TextureManager {
             texture,
             view,
             sampler,
             bind_group,
-        };
-        
-        (atlas_texture, char_map, width, height)
+        }
     }
 
     pub fn update_screen_size(&mut self, width: u32, height: u32, queue: &wgpu::Queue) {
@@ -336,9 +611,280 @@ impl FontSystem {
                     binding: 2,
                     resource: wgpu::BindingResource::Sampler(&self.atlas_texture.sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: self.text_buffer.as_entire_binding(),
+                },
             ],
             label: Some("Font Bind Group"),
         })
     }
+
+    /// Adds another font, usable by id and as a fallback for characters the others lack.
+    pub fn add_font(&mut self, font_data: &[u8]) -> Result<usize, &'static str> {
+        let font = Font::from_bytes(font_data, FontSettings::default())?;
+        self.fallback_fonts.push(font);
+        self.font_data.push(font_data.to_vec());
+        Ok(self.fallback_fonts.len())
+    }
+
+    pub fn font_count(&self) -> usize {
+        1 + self.fallback_fonts.len()
+    }
+
+    // id 0 is the primary font
+    pub fn font_by_id(&self, id: usize) -> Option<&Font> {
+        match id {
+            0 => Some(&self.font),
+            _ => self.fallback_fonts.get(id - 1),
+        }
+    }
+
+    pub fn glyph_mode(&self) -> GlyphMode {
+        self.glyph_mode
+    }
+
+    pub fn atlas_size(&self) -> u32 {
+        self.packer.size
+    }
+
+    /// Changes whenever the atlas texture is replaced or glyphs are evicted, rebuild bind
+    /// groups and re-query cached glyphs when it does.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    // true once after the atlas or the text buffer was replaced, for the owner of the font bind group
+    pub(crate) fn take_rebind(&mut self) -> bool {
+        let current = (self.generation, self.text_buffer_generation);
+        let changed = self.bound_generation != current;
+        self.bound_generation = current;
+        changed
+    }
+
+    fn create_text_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Font Text Buffer"),
+            size: (std::mem::size_of::<TextHeader>() + capacity * std::mem::size_of::<GlyphInstance>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Uploads laid out glyphs to the text buffer, growing it when needed.
+    pub fn write_text(&mut self, core: &Core, block: &TextBlock) {
+        if block.glyphs.len() > self.text_capacity {
+            self.text_capacity = block.glyphs.len().next_power_of_two();
+            self.text_buffer = Self::create_text_buffer(&core.device, self.text_capacity);
+            self.text_buffer_generation += 1;
+        }
+        let header = TextHeader {
+            count: block.glyphs.len() as u32,
+            lines: block.lines,
+            size: block.size,
+        };
+        core.queue.write_buffer(&self.text_buffer, 0, bytemuck::bytes_of(&header));
+        if !block.glyphs.is_empty() {
+            core.queue.write_buffer(
+                &self.text_buffer,
+                std::mem::size_of::<TextHeader>() as u64,
+                bytemuck::cast_slice(&block.glyphs),
+            );
+        }
+    }
+
+    fn size_key(size: f32) -> u32 {
+        size.round().max(1.0) as u32
+    }
+
+    pub fn cached_glyph(&self, font: usize, ch: char, size: f32) -> Option<GlyphInfo> {
+        let key = GlyphKey { font, ch, size: Self::size_key(size) };
+        self.glyphs.get(&key).map(|entry| entry.info)
+    }
+
+    /// Glyph of `ch` at `size` pixels, rasterized and packed into the atlas on first use.
+    /// None when it fits nowhere even after growing the atlas and evicting old glyphs.
+    pub fn glyph(&mut self, core: &Core, font: usize, ch: char, size: f32) -> Option<GlyphInfo> {
+        self.clock += 1;
+        self.load_glyph(core, GlyphKey { font, ch, size: Self::size_key(size) })
+    }
+
+    /// Loads every character of `text` at once so none of them evicts another.
+    /// Returns true when the atlas texture was replaced and bind groups using it need rebuilding.
+    pub fn ensure_text(&mut self, core: &Core, font: usize, text: &str, size: f32) -> bool {
+        let atlas_size = self.packer.size;
+        self.clock += 1;
+        let size = Self::size_key(size);
+        let skipped = text
+            .chars()
+            .filter(|ch| !ch.is_control())
+            .filter(|&ch| self.load_glyph(core, GlyphKey { font, ch, size }).is_none())
+            .count();
+        if skipped > 0 {
+            log::warn!("Font atlas is full, skipped {} characters at {}px", skipped, size);
+        }
+        self.packer.size != atlas_size
+    }
+
+    // requested font when it has the character, otherwise the first one that does
+    fn resolve_font(&self, font: usize, ch: char) -> usize {
+        let has_glyph = |id: usize| self.font_by_id(id).is_some_and(|f| f.lookup_glyph_index(ch) != 0);
+        if has_glyph(font) {
+            return font;
+        }
+        (0..self.font_count()).find(|&id| has_glyph(id)).unwrap_or(0)
+    }
+
+    fn load_glyph(&mut self, core: &Core, key: GlyphKey) -> Option<GlyphInfo> {
+        if let Some(entry) = self.glyphs.get(&key) {
+            if let Some(shelf) = entry.shelf {
+                self.packer.touch(shelf, self.clock);
+            }
+            return Some(entry.info);
+        }
+
+        let source = self.resolve_font(key.font, key.ch);
+        let bitmap = self.rasterize(source, key.ch, key.size as f32)?;
+        let mut info = GlyphInfo {
+            position: [0, 0],
+            size: [bitmap.width, bitmap.height],
+            bearing: bitmap.bearing,
+            advance: bitmap.advance,
+            font: source,
+        };
+        // whitespace has metrics but nothing to pack
+        let shelf = if bitmap.pixels.is_empty() {
+            None
+        } else {
+            let padded = [info.size[0] + GLYPH_PADDING * 2, info.size[1] + GLYPH_PADDING * 2];
+            let (shelf, x, y) = self.allocate(core, padded[0], padded[1])?;
+            self.upload_glyph(core, [x, y], padded, &bitmap);
+            info.position = [x + GLYPH_PADDING, y + GLYPH_PADDING];
+            Some(shelf)
+        };
+        self.glyphs.insert(key, GlyphEntry { info, shelf });
+        Some(info)
+    }
+
+    fn allocate(&mut self, core: &Core, width: u32, height: u32) -> Option<(usize, u32, u32)> {
+        loop {
+            if let Some(slot) = self.packer.allocate(width, height, self.clock) {
+                return Some(slot);
+            }
+            if self.packer.size * 2 <= self.max_atlas_size {
+                self.grow(core);
+            } else {
+                // drops the least recently used shelf that the current call has not touched
+                let index = self.packer.evict(self.clock)?;
+                self.glyphs.retain(|_, entry| entry.shelf != Some(index));
+                self.generation += 1;
+            }
+        }
+    }
+
+    // doubles the atlas, existing texels keep their pixel position
+    fn grow(&mut self, core: &Core) {
+        let old_size = self.packer.size;
+        let size = old_size * 2;
+        let atlas_texture = Self::create_atlas_texture(core, size);
+        let mut encoder = core.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Font Atlas Grow Encoder"),
+        });
+        encoder.copy_texture_to_texture(
+            self.atlas_texture.texture.as_image_copy(),
+            atlas_texture.texture.as_image_copy(),
+            wgpu::Extent3d {
+                width: old_size,
+                height: old_size,
+                depth_or_array_layers: 1,
+            },
+        );
+        core.queue.submit(Some(encoder.finish()));
+
+        self.atlas_texture = atlas_texture;
+        self.packer.size = size;
+        self.generation += 1;
+        // the grid cells keep their texels, so their normalized UVs shrink
+        let rescale = old_size as f32 / size as f32;
+        for info in self.char_map.values_mut() {
+            info.uv_min = info.uv_min.map(|uv| uv * rescale);
+            info.uv_max = info.uv_max.map(|uv| uv * rescale);
+        }
+        self.font_uniforms.data.atlas_size = [size as f32, size as f32];
+        self.font_uniforms.update(&core.queue);
+    }
+
+    fn upload_glyph(&self, core: &Core, origin: [u32; 2], padded: [u32; 2], bitmap: &GlyphBitmap) {
+        let [width, height] = padded;
+        let mut data = vec![0u8; (width * height * 4) as usize];
+        for y in 0..bitmap.height {
+            for x in 0..bitmap.width {
+                let index = (((y + GLYPH_PADDING) * width + x + GLYPH_PADDING) * 4) as usize;
+                data[index..index + 4].copy_from_slice(&bitmap.pixels[(y * bitmap.width + x) as usize]);
+            }
+        }
+        core.queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.atlas_texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: origin[0], y: origin[1], z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_glyphs_into_shelves_below_the_top() {
+        let mut packer = ShelfPacker::new(512, 1024);
+        assert_eq!(packer.allocate(30, 30, 1), Some((0, 0, 512)));
+        assert_eq!(packer.allocate(20, 29, 1), Some((0, 30, 512)));
+        // too tall for the first shelf starts a new one, rows rounded up to 4 texels
+        assert_eq!(packer.allocate(10, 40, 1), Some((1, 0, 544)));
+        // much shorter glyphs get their own shelf instead of wasting a tall row
+        assert_eq!(packer.allocate(10, 8, 1), Some((2, 0, 584)));
+        assert_eq!(packer.allocate(1025, 8, 1), None);
+    }
+
+    #[test]
+    fn full_atlas_refuses_until_a_shelf_is_evicted() {
+        let mut packer = ShelfPacker::new(0, 64);
+        assert_eq!(packer.allocate(64, 32, 1), Some((0, 0, 0)));
+        assert_eq!(packer.allocate(64, 32, 2), Some((1, 0, 32)));
+        assert_eq!(packer.allocate(64, 32, 3), None);
+        // shelves used in the current call are never evicted
+        assert_eq!(packer.evict(1), None);
+        packer.touch(0, 4);
+        assert_eq!(packer.evict(5), Some(1));
+        assert_eq!(packer.allocate(64, 32, 5), Some((1, 0, 32)));
+    }
+
+    #[test]
+    fn evicting_the_last_shelves_returns_their_rows() {
+        let mut packer = ShelfPacker::new(0, 64);
+        packer.allocate(16, 16, 1);
+        packer.allocate(16, 32, 2);
+        assert_eq!(packer.evict(3), Some(0));
+        assert_eq!(packer.shelves.len(), 2);
+        assert_eq!(packer.evict(3), Some(1));
+        assert!(packer.shelves.is_empty());
+        // the whole height is free again for a taller glyph
+        assert_eq!(packer.allocate(16, 64, 3), Some((0, 0, 0)));
+    }
+}
@@ -1,10 +1,25 @@
 use notify::{Watcher, RecursiveMode, Event, EventKind};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::path::{PathBuf, Path};
 use std::fs;
 use std::sync::mpsc::{channel, Receiver};
 use std::time::{Duration, Instant};
 use std::collections::HashMap;
+use winit::event_loop::EventLoopProxy;
+
+// ShaderApp's event loop, woken from the watcher threads so on demand pacing draws a frame
+// that picks up the changed shader
+static RELOAD_WAKER: OnceLock<Mutex<EventLoopProxy<()>>> = OnceLock::new();
+
+pub(crate) fn set_reload_waker(proxy: EventLoopProxy<()>) {
+    let _ = RELOAD_WAKER.set(Mutex::new(proxy));
+}
+
+fn wake_event_loop() {
+    if let Some(proxy) = RELOAD_WAKER.get().and_then(|proxy| proxy.lock().ok()) {
+        let _ = proxy.send_event(());
+    }
+}
 
 pub enum ShaderType {
     RenderPair, // Vertex + Fragment
@@ -48,6 +63,7 @@ impl ShaderHotReload {
                     EventKind::Remove(_)
                     => {
                         tx.send(event).unwrap_or_default();
+                        wake_event_loop();
                     },
                     _ => {}
                 }
@@ -116,6 +132,7 @@ impl ShaderHotReload {
                     EventKind::Remove(_)
                     => {
                         tx.send(event).unwrap_or_default();
+                        wake_event_loop();
                     },
                     _ => {}
                 }
@@ -316,6 +333,15 @@ impl ShaderHotReload {
         }
     }
     
+    // hash of the last loaded sources, stored in exported frames to detect edited shaders
+    pub fn source_hash(&self) -> u64 {
+        crate::source_hash(
+            [self.last_vs_content.as_str(), self.last_fs_content.as_str()]
+                .into_iter()
+                .chain(self.last_compute_content.as_deref()),
+        )
+    }
+    
     pub fn entry_point(&self) -> Option<&str> {
         self.entry_point.as_deref()
     }
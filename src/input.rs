@@ -0,0 +1,492 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use serde::Deserialize;
+use winit::event::{ElementState, KeyEvent, MouseButton, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+// (key, JavaScript keyCode) for the Shadertoy style keyboard texture.
+// Config files use the winit names, e.g. "KeyW", "Space", "ArrowUp", "F12".
+const KEYS: &[(KeyCode, u8)] = &[
+    (KeyCode::Backspace, 8), (KeyCode::Tab, 9), (KeyCode::Enter, 13), (KeyCode::NumpadEnter, 13),
+    (KeyCode::ShiftLeft, 16), (KeyCode::ShiftRight, 16), (KeyCode::ControlLeft, 17), (KeyCode::ControlRight, 17),
+    (KeyCode::AltLeft, 18), (KeyCode::AltRight, 18), (KeyCode::Pause, 19), (KeyCode::CapsLock, 20),
+    (KeyCode::Escape, 27), (KeyCode::Space, 32), (KeyCode::PageUp, 33), (KeyCode::PageDown, 34),
+    (KeyCode::End, 35), (KeyCode::Home, 36), (KeyCode::ArrowLeft, 37), (KeyCode::ArrowUp, 38),
+    (KeyCode::ArrowRight, 39), (KeyCode::ArrowDown, 40), (KeyCode::Insert, 45), (KeyCode::Delete, 46),
+    (KeyCode::Digit0, 48), (KeyCode::Digit1, 49), (KeyCode::Digit2, 50), (KeyCode::Digit3, 51),
+    (KeyCode::Digit4, 52), (KeyCode::Digit5, 53), (KeyCode::Digit6, 54), (KeyCode::Digit7, 55),
+    (KeyCode::Digit8, 56), (KeyCode::Digit9, 57),
+    (KeyCode::KeyA, 65), (KeyCode::KeyB, 66), (KeyCode::KeyC, 67), (KeyCode::KeyD, 68),
+    (KeyCode::KeyE, 69), (KeyCode::KeyF, 70), (KeyCode::KeyG, 71), (KeyCode::KeyH, 72),
+    (KeyCode::KeyI, 73), (KeyCode::KeyJ, 74), (KeyCode::KeyK, 75), (KeyCode::KeyL, 76),
+    (KeyCode::KeyM, 77), (KeyCode::KeyN, 78), (KeyCode::KeyO, 79), (KeyCode::KeyP, 80),
+    (KeyCode::KeyQ, 81), (KeyCode::KeyR, 82), (KeyCode::KeyS, 83), (KeyCode::KeyT, 84),
+    (KeyCode::KeyU, 85), (KeyCode::KeyV, 86), (KeyCode::KeyW, 87), (KeyCode::KeyX, 88),
+    (KeyCode::KeyY, 89), (KeyCode::KeyZ, 90),
+    (KeyCode::SuperLeft, 91), (KeyCode::SuperRight, 92), (KeyCode::ContextMenu, 93),
+    (KeyCode::Numpad0, 96), (KeyCode::Numpad1, 97), (KeyCode::Numpad2, 98), (KeyCode::Numpad3, 99),
+    (KeyCode::Numpad4, 100), (KeyCode::Numpad5, 101), (KeyCode::Numpad6, 102), (KeyCode::Numpad7, 103),
+    (KeyCode::Numpad8, 104), (KeyCode::Numpad9, 105), (KeyCode::NumpadMultiply, 106), (KeyCode::NumpadAdd, 107),
+    (KeyCode::NumpadSubtract, 109), (KeyCode::NumpadDecimal, 110), (KeyCode::NumpadDivide, 111),
+    (KeyCode::F1, 112), (KeyCode::F2, 113), (KeyCode::F3, 114), (KeyCode::F4, 115),
+    (KeyCode::F5, 116), (KeyCode::F6, 117), (KeyCode::F7, 118), (KeyCode::F8, 119),
+    (KeyCode::F9, 120), (KeyCode::F10, 121), (KeyCode::F11, 122), (KeyCode::F12, 123),
+    (KeyCode::NumLock, 144), (KeyCode::ScrollLock, 145),
+    (KeyCode::Semicolon, 186), (KeyCode::Equal, 187), (KeyCode::Comma, 188), (KeyCode::Minus, 189),
+    (KeyCode::Period, 190), (KeyCode::Slash, 191), (KeyCode::Backquote, 192), (KeyCode::BracketLeft, 219),
+    (KeyCode::Backslash, 220), (KeyCode::BracketRight, 221), (KeyCode::Quote, 222),
+];
+
+// gamepad axes count as pressed past this value
+const AXIS_PRESS_THRESHOLD: f32 = 0.5;
+
+/// A physical input an action can be bound to. Gamepad state is fed by the app through
+/// `InputState::set_gamepad_button` / `set_gamepad_axis` (e.g. from gilrs).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    GamepadButton(u32),
+    // one direction of an axis, so a stick can drive two actions
+    GamepadAxis { axis: u32, positive: bool },
+}
+
+impl InputBinding {
+    /// Parses "KeyW", "W", "Space", "Mouse:Left", "Mouse:4", "Gamepad:0", "GamepadAxis:1+" or "GamepadAxis:1-"
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some(button) = text.strip_prefix("Mouse:") {
+            return Some(InputBinding::Mouse(match button {
+                "Left" => MouseButton::Left,
+                "Right" => MouseButton::Right,
+                "Middle" => MouseButton::Middle,
+                "Back" => MouseButton::Back,
+                "Forward" => MouseButton::Forward,
+                other => MouseButton::Other(other.parse().ok()?),
+            }));
+        }
+        if let Some(axis) = text.strip_prefix("GamepadAxis:") {
+            let (axis, positive) = match axis.strip_suffix('-') {
+                Some(axis) => (axis, false),
+                None => (axis.strip_suffix('+').unwrap_or(axis), true),
+            };
+            return Some(InputBinding::GamepadAxis { axis: axis.parse().ok()?, positive });
+        }
+        if let Some(button) = text.strip_prefix("Gamepad:") {
+            return Some(InputBinding::GamepadButton(button.parse().ok()?));
+        }
+        // single letters and digits as shorthand for KeyX / DigitX
+        let name = match text.chars().next() {
+            Some(c) if text.len() == 1 && c.is_ascii_alphabetic() => format!("Key{}", c.to_ascii_uppercase()),
+            Some(c) if text.len() == 1 && c.is_ascii_digit() => format!("Digit{}", c),
+            _ => text.to_string(),
+        };
+        KEYS.iter()
+            .find(|(key, _)| format!("{:?}", key) == name)
+            .map(|(key, _)| InputBinding::Key(*key))
+    }
+}
+
+#[derive(Debug)]
+pub enum BindingsError {
+    IoError(std::io::Error),
+    ParseError(String),
+    UnknownBinding(String),
+}
+
+impl From<std::io::Error> for BindingsError {
+    fn from(err: std::io::Error) -> Self {
+        BindingsError::IoError(err)
+    }
+}
+
+#[derive(Deserialize)]
+struct BindingsFile {
+    #[serde(default)]
+    actions: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    axes: HashMap<String, AxisConfig>,
+}
+
+#[derive(Deserialize)]
+struct AxisConfig {
+    negative: String,
+    positive: String,
+}
+
+/// Named actions bound to inputs, plus axes made of a negative and a positive action.
+/// `Default` has the built-in bindings (fullscreen, UI, screenshot and WASD/QE movement).
+#[derive(Debug, Clone)]
+pub struct ActionMap {
+    actions: HashMap<String, Vec<InputBinding>>,
+    axes: HashMap<String, (String, String)>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        let mut map = Self::empty();
+        let defaults: &[(&str, &[KeyCode])] = &[
+            ("toggle_fullscreen", &[KeyCode::KeyF]),
+            ("toggle_ui", &[KeyCode::KeyH]),
+            ("screenshot", &[KeyCode::F12]),
+            ("move_forward", &[KeyCode::KeyW, KeyCode::ArrowUp]),
+            ("move_backward", &[KeyCode::KeyS, KeyCode::ArrowDown]),
+            ("move_left", &[KeyCode::KeyA, KeyCode::ArrowLeft]),
+            ("move_right", &[KeyCode::KeyD, KeyCode::ArrowRight]),
+            ("move_up", &[KeyCode::KeyE]),
+            ("move_down", &[KeyCode::KeyQ]),
+        ];
+        for (action, keys) in defaults {
+            for key in *keys {
+                map.bind(action, InputBinding::Key(*key));
+            }
+        }
+        map.define_axis("move_x", "move_left", "move_right");
+        map.define_axis("move_y", "move_down", "move_up");
+        map.define_axis("move_z", "move_backward", "move_forward");
+        map
+    }
+}
+
+impl ActionMap {
+    pub fn empty() -> Self {
+        Self {
+            actions: HashMap::new(),
+            axes: HashMap::new(),
+        }
+    }
+
+    pub fn bind(&mut self, action: &str, binding: InputBinding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[InputBinding] {
+        self.actions.get(action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn define_axis(&mut self, axis: &str, negative: &str, positive: &str) {
+        self.axes.insert(axis.to_string(), (negative.to_string(), positive.to_string()));
+    }
+
+    pub fn is_bound(&self, action: &str, binding: &InputBinding) -> bool {
+        self.bindings(action).contains(binding)
+    }
+
+    /// Parses a TOML bindings file on top of the defaults; an action listed in the file
+    /// replaces its default bindings.
+    /// ```toml
+    /// [actions]
+    /// toggle_ui = ["Tab"]
+    /// move_forward = ["W", "ArrowUp", "GamepadAxis:1-"]
+    /// boost = ["ShiftLeft", "Mouse:Right", "Gamepad:4"]
+    ///
+    /// [axes]
+    /// zoom = { negative = "zoom_out", positive = "zoom_in" }
+    /// ```
+    pub fn from_toml(text: &str) -> Result<Self, BindingsError> {
+        let file: BindingsFile = toml::from_str(text).map_err(|e| BindingsError::ParseError(e.to_string()))?;
+        let mut map = Self::default();
+        for (action, bindings) in file.actions {
+            map.unbind(&action);
+            // keep an empty entry so an action can be disabled with `action = []`
+            map.actions.entry(action.clone()).or_default();
+            for binding in bindings {
+                let parsed = InputBinding::parse(&binding)
+                    .ok_or_else(|| BindingsError::UnknownBinding(format!("{} = {}", action, binding)))?;
+                map.bind(&action, parsed);
+            }
+        }
+        for (axis, config) in file.axes {
+            map.define_axis(&axis, &config.negative, &config.positive);
+        }
+        Ok(map)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BindingsError> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    // Bindings from $CUNEUS_BINDINGS or ./bindings.toml, the defaults when neither exists
+    pub fn load_default() -> Self {
+        let path = std::env::var("CUNEUS_BINDINGS").unwrap_or_else(|_| "bindings.toml".to_string());
+        if !Path::new(&path).exists() {
+            return Self::default();
+        }
+        match Self::load(&path) {
+            Ok(map) => {
+                log::info!("Loaded key bindings from {}", path);
+                map
+            }
+            Err(e) => {
+                log::error!("Failed to load key bindings from {}: {:?}", path, e);
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Current keyboard, mouse and gamepad state with per-frame edges. Queried by action name
+/// through `actions`, or by raw key. `end_frame` is called by `RenderKit::handle_render_output`.
+#[derive(Default)]
+pub struct InputState {
+    pub actions: ActionMap,
+    down: HashSet<InputBinding>,
+    pressed: HashSet<InputBinding>,
+    released: HashSet<InputBinding>,
+    // flipped on every press, the third row of the keyboard texture
+    toggled: HashSet<KeyCode>,
+    gamepad_axes: HashMap<u32, f32>,
+}
+
+impl InputState {
+    pub fn new(actions: ActionMap) -> Self {
+        Self {
+            actions,
+            ..Default::default()
+        }
+    }
+
+    // Returns the binding the event changed, if any
+    pub fn handle_event(&mut self, event: &WindowEvent) -> Option<InputBinding> {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => self.handle_key_event(event),
+            WindowEvent::MouseInput { state, button, .. } => {
+                let binding = InputBinding::Mouse(*button);
+                self.set_down(binding, *state == ElementState::Pressed);
+                Some(binding)
+            }
+            WindowEvent::Focused(false) => {
+                // releases would go to another window
+                self.release_all();
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub fn handle_key_event(&mut self, event: &KeyEvent) -> Option<InputBinding> {
+        let PhysicalKey::Code(code) = event.physical_key else {
+            return None;
+        };
+        let binding = InputBinding::Key(code);
+        let pressed = event.state == ElementState::Pressed;
+        if event.repeat && pressed {
+            return Some(binding);
+        }
+        if pressed && !self.down.contains(&binding) && !self.toggled.remove(&code) {
+            self.toggled.insert(code);
+        }
+        self.set_down(binding, pressed);
+        Some(binding)
+    }
+
+    pub fn set_gamepad_button(&mut self, button: u32, pressed: bool) {
+        self.set_down(InputBinding::GamepadButton(button), pressed);
+    }
+
+    // value in -1..1, each direction also acts as a button past AXIS_PRESS_THRESHOLD
+    pub fn set_gamepad_axis(&mut self, axis: u32, value: f32) {
+        let value = value.clamp(-1.0, 1.0);
+        self.gamepad_axes.insert(axis, value);
+        self.set_down(InputBinding::GamepadAxis { axis, positive: true }, value > AXIS_PRESS_THRESHOLD);
+        self.set_down(InputBinding::GamepadAxis { axis, positive: false }, value < -AXIS_PRESS_THRESHOLD);
+    }
+
+    fn set_down(&mut self, binding: InputBinding, down: bool) {
+        if down {
+            if self.down.insert(binding) {
+                self.pressed.insert(binding);
+            }
+        } else if self.down.remove(&binding) {
+            self.released.insert(binding);
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        let down: Vec<_> = self.down.drain().collect();
+        self.released.extend(down);
+        self.gamepad_axes.clear();
+    }
+
+    // Clears the just pressed/released edges, once per frame after rendering
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    pub fn key_down(&self, key: KeyCode) -> bool {
+        self.down.contains(&InputBinding::Key(key))
+    }
+
+    pub fn binding_down(&self, binding: &InputBinding) -> bool {
+        self.down.contains(binding)
+    }
+
+    pub fn pressed(&self, action: &str) -> bool {
+        self.actions.bindings(action).iter().any(|b| self.down.contains(b))
+    }
+
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.actions.bindings(action).iter().any(|b| self.pressed.contains(b))
+    }
+
+    pub fn just_released(&self, action: &str) -> bool {
+        let bindings = self.actions.bindings(action);
+        bindings.iter().any(|b| self.released.contains(b)) && !self.pressed(action)
+    }
+
+    // 0..1, analog for gamepad axes and 0 or 1 for buttons
+    pub fn value(&self, action: &str) -> f32 {
+        self.actions
+            .bindings(action)
+            .iter()
+            .map(|binding| match binding {
+                InputBinding::GamepadAxis { axis, positive } => {
+                    let value = self.gamepad_axes.get(axis).copied().unwrap_or(0.0);
+                    if *positive { value.max(0.0) } else { (-value).max(0.0) }
+                }
+                _ => if self.down.contains(binding) { 1.0 } else { 0.0 },
+            })
+            .fold(0.0, f32::max)
+    }
+
+    // -1..1 for an axis defined in the action map, 0 when undefined
+    pub fn axis(&self, axis: &str) -> f32 {
+        match self.actions.axes.get(axis) {
+            Some((negative, positive)) => (self.value(positive) - self.value(negative)).clamp(-1.0, 1.0),
+            None => 0.0,
+        }
+    }
+}
+
+/// Shadertoy style keyboard channel: a 256x3 R8Unorm texture indexed by JavaScript keyCode,
+/// row 0 is held down, row 1 pressed this frame and row 2 toggled. Read it in WGSL with
+/// `textureLoad(keyboard, vec2<i32>(87, 0), 0).x` (87 = W).
+pub struct KeyboardTexture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+    data: [u8; 256 * 3],
+}
+
+impl KeyboardTexture {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Keyboard Texture"),
+            size: wgpu::Extent3d {
+                width: 256,
+                height: 3,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Keyboard Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Keyboard Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        });
+        Self {
+            texture,
+            view,
+            bind_group_layout,
+            bind_group,
+            data: [0; 256 * 3],
+        }
+    }
+
+    pub fn update(&mut self, queue: &wgpu::Queue, input: &InputState) {
+        let mut data = [0u8; 256 * 3];
+        for (key, code) in KEYS {
+            let binding = InputBinding::Key(*key);
+            let code = *code as usize;
+            data[code] |= if input.down.contains(&binding) { 255 } else { 0 };
+            data[256 + code] |= if input.pressed.contains(&binding) { 255 } else { 0 };
+            data[512 + code] |= if input.toggled.contains(key) { 255 } else { 0 };
+        }
+        if data == self.data {
+            return;
+        }
+        self.data = data;
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &self.data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(256),
+                rows_per_image: Some(3),
+            },
+            wgpu::Extent3d {
+                width: 256,
+                height: 3,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_key_names_and_shorthands() {
+        assert_eq!(InputBinding::parse("KeyW"), Some(InputBinding::Key(KeyCode::KeyW)));
+        assert_eq!(InputBinding::parse(" w "), Some(InputBinding::Key(KeyCode::KeyW)));
+        assert_eq!(InputBinding::parse("7"), Some(InputBinding::Key(KeyCode::Digit7)));
+        assert_eq!(InputBinding::parse("Space"), Some(InputBinding::Key(KeyCode::Space)));
+        assert_eq!(InputBinding::parse("ArrowUp"), Some(InputBinding::Key(KeyCode::ArrowUp)));
+    }
+
+    #[test]
+    fn parses_mouse_and_gamepad_bindings() {
+        assert_eq!(InputBinding::parse("Mouse:Left"), Some(InputBinding::Mouse(MouseButton::Left)));
+        assert_eq!(InputBinding::parse("Mouse:4"), Some(InputBinding::Mouse(MouseButton::Other(4))));
+        assert_eq!(InputBinding::parse("Gamepad:3"), Some(InputBinding::GamepadButton(3)));
+        assert_eq!(InputBinding::parse("GamepadAxis:1-"), Some(InputBinding::GamepadAxis { axis: 1, positive: false }));
+        assert_eq!(InputBinding::parse("GamepadAxis:1+"), Some(InputBinding::GamepadAxis { axis: 1, positive: true }));
+        // the sign is optional for the positive direction
+        assert_eq!(InputBinding::parse("GamepadAxis:0"), Some(InputBinding::GamepadAxis { axis: 0, positive: true }));
+    }
+
+    #[test]
+    fn rejects_unknown_bindings() {
+        for text in ["", "Keyw", "Banana", "Mouse:Side", "Gamepad:A", "GamepadAxis:x+", "ww"] {
+            assert_eq!(InputBinding::parse(text), None, "{:?}", text);
+        }
+    }
+}
@@ -1,36 +1,61 @@
 use winit::window::Window;
-use winit::event::{ElementState, KeyEvent};
-use winit::keyboard::Key;
+use winit::event::{ElementState, KeyEvent, WindowEvent};
+use crate::{ActionMap, InputBinding, InputState};
 
 pub struct KeyInputHandler {
     is_fullscreen: bool,
     pub show_ui: bool,
+    screenshot_requested: bool,
+    // action bindings and key/mouse/gamepad state, see input.rs
+    pub input: InputState,
 }
 impl KeyInputHandler {
     pub fn new() -> Self {
         Self {
             is_fullscreen: false,
             show_ui: true,
+            screenshot_requested: false,
+            input: InputState::new(ActionMap::load_default()),
         }
     }
     pub fn handle_keyboard_input(&mut self, window: &Window, event: &KeyEvent) -> bool {
+        let binding = self.input.handle_key_event(event);
         if event.state == ElementState::Pressed && !event.repeat {
-            if let Key::Character(ch) = &event.logical_key {
-                match ch.as_str() {
-                    "f" | "F" => {
-                        self.toggle_fullscreen(window);
-                        return true;
-                    }
-                    "h" | "H" => {
-                        self.show_ui = !self.show_ui;
-                        return true;
-                    }
-                    _ => {}
-                }
+            if let Some(binding) = binding {
+                return self.handle_builtin_action(window, &binding);
             }
         }
         false
     }
+    // Keyboard and mouse events; returns true when a built-in action consumed the event
+    pub fn handle_event(&mut self, window: &Window, event: &WindowEvent) -> bool {
+        if let WindowEvent::KeyboardInput { event, .. } = event {
+            return self.handle_keyboard_input(window, event);
+        }
+        match self.input.handle_event(event) {
+            Some(binding) if self.input.binding_down(&binding) => self.handle_builtin_action(window, &binding),
+            _ => false,
+        }
+    }
+    fn handle_builtin_action(&mut self, window: &Window, binding: &InputBinding) -> bool {
+        let actions = &self.input.actions;
+        if actions.is_bound("screenshot", binding) {
+            self.screenshot_requested = true;
+            true
+        } else if actions.is_bound("toggle_fullscreen", binding) {
+            self.toggle_fullscreen(window);
+            true
+        } else if actions.is_bound("toggle_ui", binding) {
+            self.show_ui = !self.show_ui;
+            true
+        } else {
+            false
+        }
+    }
+    // true once after the screenshot action (F12 by default)
+    pub fn take_screenshot_request(&mut self) -> bool {
+        std::mem::take(&mut self.screenshot_requested)
+    }
     fn toggle_fullscreen(&mut self, window: &Window) {
         if !self.is_fullscreen {
             window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
@@ -39,4 +64,4 @@ impl KeyInputHandler {
         }
         self.is_fullscreen = !self.is_fullscreen;
     }
-}
\ No newline at end of file
+}
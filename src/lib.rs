@@ -17,9 +17,14 @@ mod shader;
 mod texture;
 mod uniforms;
 mod app;
+mod cli;
+mod coreconfig;
 mod renderkit;
 mod feedback; 
 mod keyinputs;
+mod input;
+mod camera;
+mod panzoom;
 mod export;
 mod hot;
 mod controls;
@@ -28,35 +33,59 @@ mod atomic;
 pub mod gst;
 pub mod compute;
 mod spectrum;
-mod fps;
+mod stats;
+mod scaling;
 mod mouse;
 pub mod hdri;
 mod font;
+mod sdf;
+mod text;
+mod profiler;
+mod offscreen;
+mod outputs;
+mod capture;
+mod screenshot;
+mod metadata;
 
 pub use renderer::*;
 pub use shader::*;
 pub use texture::*;
 pub use uniforms::*;
 pub use app::*;
+pub use cli::{CliError, CliOptions, ExportRange};
+pub use coreconfig::{CoreConfig, CoreError, adapter_label};
+use coreconfig::watch_device;
 pub use renderkit::*;
 pub use feedback::*;
 pub use keyinputs::KeyInputHandler;
-pub use export::{ExportSettings, ExportManager, ExportError, ExportUiState, save_frame};
+pub use camera::{CameraController, CameraPath, CameraPose, CameraUniform, FlyCamera, OrbitCamera};
+pub use panzoom::{PanZoomCamera, PanZoomUniform, join_f64, reference_orbit, split_f64};
+pub use input::{ActionMap, BindingsError, InputBinding, InputState, KeyboardTexture};
+pub use export::{ExportSettings, ExportManager, ExportError, ExportFormat, ExportUiState, save_frame, save_frame_float, save_texture_frame};
 pub use hot::ShaderHotReload;
 pub use controls::{ControlsRequest, ShaderControls};
 pub use atomic::AtomicBuffer;
 pub use mouse::*;
 pub use hdri::*;
-pub use font::{FontSystem, FontUniforms, CharInfo};
+pub use font::{FontSystem, FontUniforms, CharInfo, GlyphInfo, GlyphMode, TextHeader};
+pub use text::{GlyphInstance, TextAlign, TextBlock, TextLayout};
+pub use profiler::{GpuProfiler, ProfilerUiRequest};
+pub use capture::FrameCapture;
+pub use screenshot::{ScreenshotManager, ScreenshotSettings, ScreenshotUiRequest};
+pub use metadata::{FrameMetadata, source_hash};
+pub use outputs::{ControlWindow, OutputConfig, OutputWindow};
+pub use offscreen::{DownsampleFilter, OffscreenCapture, TileInfo, read_float_texture};
+pub use scaling::{RenderScale, RenderScaleMode, RenderScaleUiRequest, UpscaleFilter, Upscaler};
+pub use stats::{FrameStats, FpsTracker, GpuMemoryStats, StatsUiRequest, texture_memory_bytes};
 
 pub mod prelude {
     pub use crate::{
-        Core, ShaderApp, ShaderManager,
+        Core, CoreConfig, ShaderApp, ShaderManager,
         UniformProvider, UniformBinding, 
         RenderKit, ShaderControls, ExportManager, ShaderHotReload,
         TextureManager, Renderer, AtomicBuffer,
-        KeyInputHandler, ControlsRequest, FontSystem, FontUniforms,
-        save_frame, compute::create_bind_group_layout,compute::BindGroupLayoutType
+        KeyInputHandler, InputState, ControlsRequest, FontSystem, FontUniforms, GpuProfiler, FrameStats,
+        FrameCapture, save_frame, compute::create_bind_group_layout,compute::BindGroupLayoutType
     };
     
     #[cfg(feature = "media")]
@@ -95,74 +124,142 @@ pub struct Core {
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
     pub size: winit::dpi::PhysicalSize<u32>,
-    pub window: Window,
+    pub window: Arc<Window>,
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub adapter_info: wgpu::AdapterInfo,
+    // extra windows showing the frame, see `ShaderApp::add_output`
+    pub outputs: Vec<OutputWindow>,
+    output_canvas: Option<outputs::OutputCanvas>,
+    // egui panels in their own window, see `ShaderApp::set_control_window`
+    pub control_window: Option<ControlWindow>,
+    core_config: CoreConfig,
+    device_lost: Arc<std::sync::atomic::AtomicBool>,
+    exit_requested: std::cell::Cell<bool>,
+    animating: std::cell::Cell<bool>,
+    frame_request: std::cell::Cell<Option<std::time::Instant>>,
 }
 impl Core {
+    /// `with_config` with the defaults and the command line, panics when no device can be created
     pub async fn new(window: Window) -> Self {
+        let config = CoreConfig::default().with_cli(CliOptions::current());
+        match Self::with_config(window, &config).await {
+            Ok(core) => core,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    pub async fn with_config(window: Window, core_config: &CoreConfig) -> Result<Self, CoreError> {
         let size = window.inner_size();
+        let window = Arc::new(window);
         let instance_desc = wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
+            backends: core_config.backends,
             backend_options: wgpu::BackendOptions::default(),
             ..Default::default()
         };
         let instance = wgpu::Instance::new(&instance_desc);
-        let window_box = Box::new(window);
-        let window_ptr = Box::into_raw(window_box);
-        // SAFETY: window_ptr is valid as we just created it
-        let surface = unsafe { instance.create_surface(&*window_ptr) }.unwrap();
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                    memory_hints: Default::default(),
-                },
-                None,
-            )
-            .await
-            .unwrap();
+        let surface = instance
+            .create_surface(window.clone())
+            .map_err(CoreError::CreateSurface)?;
+        let (adapter, device, queue) = core_config.request_device(&instance, &surface).await?;
+        let adapter_info = adapter.get_info();
+        let device_lost = watch_device(&device);
         let device = Arc::new(device);
         let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
+        if surface_caps.formats.is_empty() {
+            return Err(CoreError::UnsupportedSurface);
+        }
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
+            format: core_config.surface_format(&surface_caps),
             width: size.width,
             height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: surface_caps.alpha_modes[0],
+            present_mode: core_config.present_mode(&surface_caps),
+            alpha_mode: core_config.alpha_mode(&surface_caps),
             view_formats: vec![],
-            desired_maximum_frame_latency: 2,
+            desired_maximum_frame_latency: core_config.desired_maximum_frame_latency,
         };
         surface.configure(&device, &config);
-        // SAFETY: window_ptr is still valid and we're taking back ownership
-        let window = unsafe { *Box::from_raw(window_ptr) };
-        Self {
+        log::info!("Using {} with {:?}", adapter_label(&adapter_info), config.format);
+        Ok(Self {
             surface,
             device,
             queue,
             config,
             size,
             window,
+            instance,
+            adapter,
+            adapter_info,
+            outputs: Vec::new(),
+            output_canvas: None,
+            control_window: None,
+            core_config: core_config.clone(),
+            device_lost,
+            exit_requested: std::cell::Cell::new(false),
+            animating: std::cell::Cell::new(true),
+            frame_request: std::cell::Cell::new(None),
+        })
+    }
+    /// True after a driver reset or GPU hang, until `recreate_device` succeeds
+    pub fn device_lost(&self) -> bool {
+        self.device_lost.load(std::sync::atomic::Ordering::Relaxed)
+    }
+    /// Replaces a lost device with a new one from the same `CoreConfig` and reconfigures the
+    /// surface. Everything created on the old device is gone, see `ShaderManager::recreate`.
+    pub async fn recreate_device(&mut self) -> Result<(), CoreError> {
+        let (adapter, device, queue) = self.core_config.request_device(&self.instance, &self.surface).await?;
+        let surface_caps = self.surface.get_capabilities(&adapter);
+        if surface_caps.formats.is_empty() {
+            return Err(CoreError::UnsupportedSurface);
+        }
+        self.device_lost = watch_device(&device);
+        self.device = Arc::new(device);
+        self.queue = queue;
+        self.adapter_info = adapter.get_info();
+        self.adapter = adapter;
+        self.config.format = self.core_config.surface_format(&surface_caps);
+        self.config.present_mode = self.core_config.present_mode(&surface_caps);
+        self.config.alpha_mode = self.core_config.alpha_mode(&surface_caps);
+        self.surface.configure(&self.device, &self.config);
+        self.rebuild_output_canvas(true);
+        if let Some(control) = &self.control_window {
+            control.surface.configure(&self.device, &control.config);
         }
+        log::info!("Recreated device on {}", adapter_label(&self.adapter_info));
+        Ok(())
     }
     pub fn window(&self) -> &Window {
         &self.window
     }
+    /// Asks `ShaderApp` to close after the current frame, e.g. when a command line export is done
+    pub fn request_exit(&self) {
+        self.exit_requested.set(true);
+    }
+    pub fn exit_requested(&self) -> bool {
+        self.exit_requested.get()
+    }
+    /// Asks for another frame when `FramePacing` would otherwise idle: on demand, while
+    /// paused or while the window is hidden
+    pub fn request_frame(&self) {
+        self.request_frame_after(std::time::Duration::ZERO);
+    }
+    pub fn request_frame_after(&self, delay: std::time::Duration) {
+        let Some(at) = std::time::Instant::now().checked_add(delay) else {
+            return;
+        };
+        let earliest = self.frame_request.get().map_or(at, |pending| pending.min(at));
+        self.frame_request.set(Some(earliest));
+    }
+    pub(crate) fn take_frame_request(&self) -> Option<std::time::Instant> {
+        self.frame_request.take()
+    }
+    /// False while `ShaderControls` is paused, RenderKit sets it every frame
+    pub fn set_animating(&self, animating: bool) {
+        self.animating.set(animating);
+    }
+    pub fn animating(&self) -> bool {
+        self.animating.get()
+    }
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         println!("Core resize called with size: {:?}", new_size);
         if new_size.width > 0 && new_size.height > 0 {
@@ -170,6 +267,7 @@ impl Core {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
+            self.rebuild_output_canvas(false);
             println!("Surface reconfigured");
         }
     }
@@ -0,0 +1,173 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use crate::ExportError;
+
+const KEY_SHADER: &str = "cuneus.shader";
+const KEY_PARAMS: &str = "cuneus.params";
+const KEY_TIME: &str = "cuneus.time";
+const KEY_FRAME: &str = "cuneus.frame";
+const KEY_RESOLUTION: &str = "cuneus.resolution";
+const KEY_SOURCE_HASH: &str = "cuneus.source_hash";
+const KEY_VERSION: &str = "cuneus.version";
+
+/// What made an exported frame: written as PNG text chunks (EXR header attributes for EXR
+/// exports) by `save_frame` and screenshots, read back from PNGs with `FrameMetadata::load`
+/// to restore a preset.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameMetadata {
+    pub shader: String,
+    // raw bytes of the params uniform, see FrameCapture::preset_params
+    pub params: Option<Vec<u8>>,
+    pub time: f32,
+    pub frame: u32,
+    pub resolution: [u32; 2],
+    pub source_hash: Option<u64>,
+    pub version: String,
+    // other text chunks, e.g. from FrameCapture::capture_metadata
+    pub extra: Vec<(String, String)>,
+}
+
+impl FrameMetadata {
+    pub fn to_text_chunks(&self) -> Vec<(String, String)> {
+        let mut chunks = vec![
+            ("Software".to_string(), format!("cuneus {}", self.version)),
+            ("Title".to_string(), self.shader.clone()),
+            (KEY_SHADER.to_string(), self.shader.clone()),
+            (KEY_TIME.to_string(), self.time.to_string()),
+            (KEY_FRAME.to_string(), self.frame.to_string()),
+            (KEY_RESOLUTION.to_string(), format!("{}x{}", self.resolution[0], self.resolution[1])),
+            (KEY_VERSION.to_string(), self.version.clone()),
+        ];
+        if let Some(params) = &self.params {
+            chunks.push((KEY_PARAMS.to_string(), to_hex(params)));
+        }
+        if let Some(hash) = self.source_hash {
+            chunks.push((KEY_SOURCE_HASH.to_string(), format!("{:016x}", hash)));
+        }
+        chunks.extend(self.extra.iter().cloned());
+        chunks
+    }
+
+    // None when the chunks were not written by cuneus
+    pub fn from_text_chunks<I>(chunks: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut metadata = FrameMetadata::default();
+        let mut found = false;
+        for (key, value) in chunks {
+            match key.as_str() {
+                KEY_SHADER => {
+                    metadata.shader = value;
+                    found = true;
+                }
+                KEY_PARAMS => metadata.params = from_hex(&value),
+                KEY_TIME => metadata.time = value.parse().unwrap_or(0.0),
+                KEY_FRAME => metadata.frame = value.parse().unwrap_or(0),
+                KEY_RESOLUTION => {
+                    if let Some((width, height)) = value.split_once('x') {
+                        metadata.resolution = [width.parse().unwrap_or(0), height.parse().unwrap_or(0)];
+                    }
+                }
+                KEY_SOURCE_HASH => metadata.source_hash = u64::from_str_radix(&value, 16).ok(),
+                KEY_VERSION => metadata.version = value,
+                "Software" | "Title" => {}
+                _ => metadata.extra.push((key, value)),
+            }
+        }
+        found.then_some(metadata)
+    }
+
+    /// Reads the metadata of a PNG saved by cuneus (export frame or screenshot)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ExportError> {
+        let decoder = png::Decoder::new(BufReader::new(File::open(path.as_ref())?));
+        let reader = decoder
+            .read_info()
+            .map_err(|e| ExportError::InvalidMetadata(e.to_string()))?;
+        let info = reader.info();
+        let mut chunks: Vec<(String, String)> = info
+            .uncompressed_latin1_text
+            .iter()
+            .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+            .collect();
+        for chunk in &info.compressed_latin1_text {
+            if let Ok(text) = chunk.get_text() {
+                chunks.push((chunk.keyword.clone(), text));
+            }
+        }
+        for chunk in &info.utf8_text {
+            if let Ok(text) = chunk.get_text() {
+                chunks.push((chunk.keyword.clone(), text));
+            }
+        }
+        Self::from_text_chunks(chunks).ok_or_else(|| {
+            ExportError::InvalidMetadata(format!("{} has no cuneus metadata", path.as_ref().display()))
+        })
+    }
+}
+
+/// FNV-1a over the shader sources. Stable across runs and Rust versions, unlike DefaultHasher.
+pub fn source_hash<'a, I>(sources: I) -> u64
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for source in sources {
+        for byte in source.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trip() {
+        let bytes = [0x00, 0x0f, 0xa5, 0xff];
+        assert_eq!(to_hex(&bytes), "000fa5ff");
+        assert_eq!(from_hex(&to_hex(&bytes)), Some(bytes.to_vec()));
+        assert_eq!(from_hex(""), Some(Vec::new()));
+    }
+
+    #[test]
+    fn hex_rejects_malformed_text() {
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+        assert_eq!(from_hex("\u{e9}"), None);
+    }
+
+    #[test]
+    fn text_chunks_round_trip() {
+        let metadata = FrameMetadata {
+            shader: "asahi".to_string(),
+            params: Some(vec![1, 2, 3, 4]),
+            time: 4.5,
+            frame: 270,
+            resolution: [1920, 1080],
+            source_hash: Some(0x0123456789abcdef),
+            version: "0.3.0".to_string(),
+            extra: vec![("camera".to_string(), "0 1 2".to_string())],
+        };
+        assert_eq!(FrameMetadata::from_text_chunks(metadata.to_text_chunks()), Some(metadata));
+        assert_eq!(FrameMetadata::from_text_chunks(vec![("Title".to_string(), "x".to_string())]), None);
+    }
+}
@@ -1,13 +1,43 @@
+use std::time::Instant;
 use crate::UniformProvider;
 use winit::event::WindowEvent;
 
+/// Mouse state for shaders. Times are seconds on the tracker clock, compare them with `time`:
+/// ```wgsl
+/// struct MouseUniform {
+///     position: vec2<f32>,
+///     click_position: vec2<f32>,
+///     wheel: vec2<f32>,
+///     buttons: vec2<u32>,        // x: held bitmask, y: double-clicked bitmask
+///     prev_position: vec2<f32>,
+///     velocity: vec2<f32>,
+///     drag_delta: vec2<f32>,
+///     time: f32,
+///     _padding: f32,
+///     press_time: vec4<f32>,     // left, right, middle, back
+///     release_time: vec4<f32>,
+/// };
+/// ```
+/// Shaders declaring only the first four fields keep working.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct MouseUniform {
     pub position: [f32; 2],
     pub click_position: [f32; 2],
     pub wheel: [f32; 2],
+    // [held, double-clicked] bitmasks, bit 0 left, 1 right, 2 middle, 3 back, 4 forward
     pub buttons: [u32; 2],
+    // position at the end of the previous frame
+    pub prev_position: [f32; 2],
+    // normalized units per second over the previous frame
+    pub velocity: [f32; 2],
+    // position - click_position while a button is held, zero otherwise
+    pub drag_delta: [f32; 2],
+    pub time: f32,
+    pub _padding: f32,
+    // last press/release per button (left, right, middle, back), -1 before the first one
+    pub press_time: [f32; 4],
+    pub release_time: [f32; 4],
 }
 
 impl Default for MouseUniform {
@@ -17,6 +47,13 @@ impl Default for MouseUniform {
             click_position: [0.0, 0.0],
             wheel: [0.0, 0.0],
             buttons: [0, 0],
+            prev_position: [0.0, 0.0],
+            velocity: [0.0, 0.0],
+            drag_delta: [0.0, 0.0],
+            time: 0.0,
+            _padding: 0.0,
+            press_time: [-1.0; 4],
+            release_time: [-1.0; 4],
         }
     }
 }
@@ -29,8 +66,19 @@ impl UniformProvider for MouseUniform {
 
 pub struct MouseTracker {
     pub uniform: MouseUniform,
+    // window pixels
     pub raw_position: [f32; 2],
+    // pixels of the (possibly scaled) render target, raw_position * render_scale
+    pub render_position: [f32; 2],
+    pub render_scale: f32,
     pub is_inside_window: bool,
+    // flips y so 0 is the bottom of the window, like shader UVs with y up
+    pub flip_y: bool,
+    // max seconds and normalized distance between two presses of a double-click
+    pub double_click_time: f32,
+    pub double_click_distance: f32,
+    clock: Instant,
+    last_frame: Option<f32>,
 }
 
 impl Default for MouseTracker {
@@ -38,7 +86,14 @@ impl Default for MouseTracker {
         Self {
             uniform: MouseUniform::default(),
             raw_position: [0.0, 0.0],
+            render_position: [0.0, 0.0],
+            render_scale: 1.0,
             is_inside_window: false,
+            flip_y: false,
+            double_click_time: 0.3,
+            double_click_distance: 0.01,
+            clock: Instant::now(),
+            last_frame: None,
         }
     }
 }
@@ -64,9 +119,14 @@ impl MouseTracker {
                 let x = position.x as f32;
                 let y = position.y as f32;
                 self.raw_position = [x, y];
+                self.render_position = [x * self.render_scale, y * self.render_scale];
                 
                 self.uniform.position[0] = x / window_size[0];
                 self.uniform.position[1] = y / window_size[1];
+                if self.flip_y {
+                    self.uniform.position[1] = 1.0 - self.uniform.position[1];
+                }
+                self.update_drag_delta();
                 true
             },
             WindowEvent::MouseInput { state, button, .. } => {
@@ -82,12 +142,38 @@ impl MouseTracker {
                     MouseButton::Other(b) => if *b < 27 { 1 << (b + 5) } else { 0 },
                 };
                 
+                let slot = match button {
+                    MouseButton::Left => Some(0),
+                    MouseButton::Right => Some(1),
+                    MouseButton::Middle => Some(2),
+                    MouseButton::Back => Some(3),
+                    _ => None,
+                };
+                let now = self.now();
+                self.uniform.time = now;
+                
                 if pressed {
+                    if let Some(slot) = slot {
+                        let since_last = now - self.uniform.press_time[slot];
+                        let dx = self.uniform.position[0] - self.uniform.click_position[0];
+                        let dy = self.uniform.position[1] - self.uniform.click_position[1];
+                        if self.uniform.press_time[slot] >= 0.0
+                            && since_last <= self.double_click_time
+                            && (dx * dx + dy * dy).sqrt() <= self.double_click_distance {
+                            self.uniform.buttons[1] |= bit_mask;
+                        }
+                        self.uniform.press_time[slot] = now;
+                    }
                     self.uniform.buttons[0] |= bit_mask;
                     self.uniform.click_position = self.uniform.position;
                 } else {
+                    if let Some(slot) = slot {
+                        self.uniform.release_time[slot] = now;
+                    }
                     self.uniform.buttons[0] &= !bit_mask;
+                    self.uniform.buttons[1] &= !bit_mask;
                 }
+                self.update_drag_delta();
                 true
             },
             WindowEvent::MouseWheel { delta, .. } => {
@@ -120,4 +206,40 @@ impl MouseTracker {
     pub fn reset_wheel(&mut self) {
         self.uniform.wheel = [0.0, 0.0];
     }
+    
+    // Sets uniform.time to now, so press times compare against the current frame
+    pub fn update_time(&mut self) {
+        self.uniform.time = self.now();
+    }
+    
+    fn now(&self) -> f32 {
+        self.clock.elapsed().as_secs_f32()
+    }
+    
+    fn update_drag_delta(&mut self) {
+        self.uniform.drag_delta = if self.uniform.buttons[0] != 0 {
+            [
+                self.uniform.position[0] - self.uniform.click_position[0],
+                self.uniform.position[1] - self.uniform.click_position[1],
+            ]
+        } else {
+            [0.0, 0.0]
+        };
+    }
+    
+    // Once per frame (RenderKit::handle_render_output does it): velocity over the frame
+    // that just ended, and prev_position for the next one
+    pub fn end_frame(&mut self) {
+        let now = self.now();
+        if let Some(last_frame) = self.last_frame {
+            let dt = (now - last_frame).max(1e-4);
+            self.uniform.velocity = [
+                (self.uniform.position[0] - self.uniform.prev_position[0]) / dt,
+                (self.uniform.position[1] - self.uniform.prev_position[1]) / dt,
+            ];
+        }
+        self.uniform.prev_position = self.uniform.position;
+        self.uniform.time = now;
+        self.last_frame = Some(now);
+    }
 }
\ No newline at end of file
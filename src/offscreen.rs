@@ -0,0 +1,505 @@
+use crate::{ExportError, Renderer, UniformBinding, UniformProvider, Vertex, CAPTURE_FORMAT};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum DownsampleFilter {
+    // plain average of the factor x factor block
+    #[default]
+    Box,
+    // triangle filter, a bit softer but with less aliasing
+    Tent,
+}
+
+/// Which part of the (possibly supersampled) image a render callback should draw.
+/// Without tiling `offset` is zero and `size == full_size`. With the tent filter, tiles
+/// overlap their neighbours by half an output pixel so the filter has texels at the seams.
+#[derive(Copy, Clone, Debug)]
+pub struct TileInfo {
+    // pixel offset of this tile in the full supersampled image
+    pub offset: [u32; 2],
+    pub size: [u32; 2],
+    // full image size including supersampling
+    pub full_size: [u32; 2],
+    pub supersample: u32,
+}
+
+impl TileInfo {
+    pub fn is_tiled(&self) -> bool {
+        self.size != self.full_size
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DownsampleParams {
+    pub factor: u32,
+    pub filter_mode: u32,
+    // texels of overlap in front of the first output pixel of the tile
+    pub source_offset: [u32; 2],
+}
+
+impl UniformProvider for DownsampleParams {
+    fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+/// Renders export frames offscreen at any size: the image is split into tiles that fit the
+/// device limits, each tile is rendered at `supersample`x and resolved on the GPU.
+/// Returns tightly packed pixels in `CAPTURE_FORMAT` order, ready for `save_frame`.
+pub struct OffscreenCapture {
+    // format the render callback draws in, the surface format of the shader's pipelines
+    source_format: wgpu::TextureFormat,
+    downsample: Renderer,
+    // bilinear resize of float outputs into Rgba16Float and Rgba32Float, see resample_float
+    resample_half: wgpu::RenderPipeline,
+    resample_float: wgpu::RenderPipeline,
+    source_layout: wgpu::BindGroupLayout,
+    params: UniformBinding<DownsampleParams>,
+}
+
+impl OffscreenCapture {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self::with_source_format(device, CAPTURE_FORMAT)
+    }
+
+    /// Capture for pipelines built for `source_format`, e.g. a `Rgba16Float` HDR surface.
+    /// Tiles are rendered in that format and converted to `CAPTURE_FORMAT` on the GPU.
+    pub fn with_source_format(device: &wgpu::Device, source_format: wgpu::TextureFormat) -> Self {
+        let source_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            }],
+            label: Some("downsample_source_layout"),
+        });
+        let params_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("downsample_params_layout"),
+        });
+        let params = UniformBinding::new(
+            device,
+            "Downsample Params",
+            DownsampleParams {
+                factor: 1,
+                filter_mode: 0,
+                source_offset: [0, 0],
+            },
+            &params_layout,
+            0,
+        );
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Downsample Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/downsample.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Downsample Pipeline Layout"),
+            bind_group_layouts: &[&source_layout, &params_layout],
+            push_constant_ranges: &[],
+        });
+        let downsample = Renderer::new(device, &shader, &shader, CAPTURE_FORMAT, &pipeline_layout, None);
+        let resample_half = resample_pipeline(device, &shader, &pipeline_layout, wgpu::TextureFormat::Rgba16Float);
+        let resample_float = resample_pipeline(device, &shader, &pipeline_layout, wgpu::TextureFormat::Rgba32Float);
+        Self {
+            source_format,
+            downsample,
+            resample_half,
+            resample_float,
+            source_layout,
+            params,
+        }
+    }
+
+    pub fn source_format(&self) -> wgpu::TextureFormat {
+        self.source_format
+    }
+
+    // Largest tile (in supersampled pixels) the device can render and read back in one go
+    pub fn max_tile_size(device: &wgpu::Device, supersample: u32) -> u32 {
+        let limits = device.limits();
+        let by_texture = limits.max_texture_dimension_2d;
+        // the resolved tile has to fit one readback buffer
+        let by_buffer = ((limits.max_buffer_size / 4) as f64).sqrt() as u32 * supersample;
+        let size = by_texture.min(by_buffer);
+        size - size % supersample
+    }
+
+    // Tile size in supersampled pixels for the given settings
+    fn tile_limit(device: &wgpu::Device, supersample: u32, max_tile_size: Option<u32>) -> u32 {
+        let limit = Self::max_tile_size(device, supersample);
+        max_tile_size.map_or(limit, |size| size.min(limit)).max(supersample)
+    }
+
+    /// True when an image of this size has to be split into tiles. Shaders that don't offset
+    /// their pixel position by `tile_offset` should refuse such exports.
+    pub fn needs_tiling(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        supersample: u32,
+        max_tile_size: Option<u32>,
+    ) -> bool {
+        let supersample = supersample.max(1);
+        let tile_ss = Self::tile_limit(device, supersample, max_tile_size);
+        width * supersample > tile_ss || height * supersample > tile_ss
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn capture<F>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        supersample: u32,
+        filter: DownsampleFilter,
+        max_tile_size: Option<u32>,
+        mut render: F,
+    ) -> Result<Vec<u8>, ExportError>
+    where
+        F: FnMut(&mut wgpu::CommandEncoder, &wgpu::TextureView, &TileInfo),
+    {
+        let supersample = supersample.max(1);
+        let full_size = [width * supersample, height * supersample];
+        // the tent filter reaches half an output pixel past each side
+        let margin = match filter {
+            DownsampleFilter::Tent if supersample > 1 => supersample / 2,
+            _ => 0,
+        };
+        let tile_out = if Self::needs_tiling(device, width, height, supersample, max_tile_size) {
+            let tile_ss = Self::tile_limit(device, supersample, max_tile_size);
+            (tile_ss.saturating_sub(2 * margin) / supersample).max(1)
+        } else {
+            width.max(height)
+        };
+
+        self.params.data.factor = supersample;
+        self.params.data.filter_mode = match filter {
+            DownsampleFilter::Box => 0,
+            DownsampleFilter::Tent => 1,
+        };
+
+        let mut image = vec![0u8; (width * height * 4) as usize];
+        for out_y in (0..height).step_by(tile_out as usize) {
+            for out_x in (0..width).step_by(tile_out as usize) {
+                let out_size = [tile_out.min(width - out_x), tile_out.min(height - out_y)];
+                let start = [out_x * supersample, out_y * supersample];
+                let end = [start[0] + out_size[0] * supersample, start[1] + out_size[1] * supersample];
+                // overlap into the neighbouring tiles, the image edges stay clamped
+                let before = [margin.min(start[0]), margin.min(start[1])];
+                let after = [margin.min(full_size[0] - end[0]), margin.min(full_size[1] - end[1])];
+                let tile = TileInfo {
+                    offset: [start[0] - before[0], start[1] - before[1]],
+                    size: [
+                        end[0] - start[0] + before[0] + after[0],
+                        end[1] - start[1] + before[1] + after[1],
+                    ],
+                    full_size,
+                    supersample,
+                };
+                // each tile is submitted on its own, so the write lands before its pass
+                self.params.data.source_offset = before;
+                self.params.update(queue);
+                let pixels = self.capture_tile(device, queue, &tile, out_size, &mut render)?;
+                let row_bytes = (out_size[0] * 4) as usize;
+                for (row, chunk) in pixels.chunks_exact(row_bytes).enumerate() {
+                    let start = (((out_y as usize + row) * width as usize) + out_x as usize) * 4;
+                    image[start..start + row_bytes].copy_from_slice(chunk);
+                }
+            }
+        }
+        Ok(image)
+    }
+
+    fn capture_tile<F>(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        tile: &TileInfo,
+        out_size: [u32; 2],
+        render: &mut F,
+    ) -> Result<Vec<u8>, ExportError>
+    where
+        F: FnMut(&mut wgpu::CommandEncoder, &wgpu::TextureView, &TileInfo),
+    {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Offscreen Capture Encoder"),
+        });
+        let output = create_capture_target(device, out_size, CAPTURE_FORMAT, "Offscreen Capture Output");
+        let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // other formats go through the downsample pass too, at factor 1 it only converts
+        if tile.supersample > 1 || self.source_format != CAPTURE_FORMAT {
+            let source = create_capture_target(device, tile.size, self.source_format, "Offscreen Source Target");
+            let source_view = source.create_view(&wgpu::TextureViewDescriptor::default());
+            render(&mut encoder, &source_view, tile);
+
+            let source_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.source_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source_view),
+                }],
+                label: Some("Downsample Source Bind Group"),
+            });
+            let mut render_pass = Renderer::begin_render_pass(
+                &mut encoder,
+                &output_view,
+                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                Some("Downsample Pass"),
+            );
+            render_pass.set_pipeline(&self.downsample.render_pipeline);
+            render_pass.set_vertex_buffer(0, self.downsample.vertex_buffer.slice(..));
+            render_pass.set_bind_group(0, &source_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.params.bind_group, &[]);
+            render_pass.draw(0..4, 0..1);
+        } else {
+            render(&mut encoder, &output_view, tile);
+        }
+
+        read_texture(device, queue, encoder, &output, out_size)
+    }
+}
+
+impl OffscreenCapture {
+    /// Reads a `Rgba16Float`/`Rgba32Float` texture back as linear RGBA f32 at `size`,
+    /// resizing it bilinearly into a target of the same format when the texture has another
+    /// size. `encoder` holds the passes that fill the texture and is submitted here.
+    pub fn resample_float(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mut encoder: wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        size: [u32; 2],
+    ) -> Result<Vec<f32>, ExportError> {
+        if texture.width() == size[0] && texture.height() == size[1] {
+            queue.submit(Some(encoder.finish()));
+            return read_float_texture(device, queue, texture);
+        }
+        // 32-bit sources keep their precision, anything else resamples to half floats
+        let (format, pipeline) = match texture.format() {
+            wgpu::TextureFormat::Rgba32Float => (wgpu::TextureFormat::Rgba32Float, &self.resample_float),
+            _ => (wgpu::TextureFormat::Rgba16Float, &self.resample_half),
+        };
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Float Resample Target"),
+            size: wgpu::Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let source_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let source_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.source_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&source_view),
+            }],
+            label: Some("Resample Source Bind Group"),
+        });
+        {
+            let mut render_pass = Renderer::begin_render_pass(
+                &mut encoder,
+                &target_view,
+                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                Some("Resample Pass"),
+            );
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_vertex_buffer(0, self.downsample.vertex_buffer.slice(..));
+            render_pass.set_bind_group(0, &source_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.params.bind_group, &[]);
+            render_pass.draw(0..4, 0..1);
+        }
+        queue.submit(Some(encoder.finish()));
+        read_float_texture(device, queue, &target)
+    }
+}
+
+// fs_resample into `format` without blending, Rgba32Float targets are not blendable
+fn resample_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    layout: &wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Float Resample Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[Vertex::desc()],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_resample"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleStrip,
+            cull_mode: Some(wgpu::Face::Back),
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+fn create_capture_target(
+    device: &wgpu::Device,
+    size: [u32; 2],
+    format: wgpu::TextureFormat,
+    label: &str,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+// Copies a 4 bytes/pixel texture to the CPU, removing the 256 byte row padding
+pub(crate) fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    encoder: wgpu::CommandEncoder,
+    texture: &wgpu::Texture,
+    size: [u32; 2],
+) -> Result<Vec<u8>, ExportError> {
+    read_texture_bytes(device, queue, encoder, texture, size, 4)
+}
+
+/// Reads a `Rgba16Float` or `Rgba32Float` texture (e.g. a compute output) back as linear
+/// RGBA f32 values, without going through `CAPTURE_FORMAT`.
+pub fn read_float_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<Vec<f32>, ExportError> {
+    let size = [texture.width(), texture.height()];
+    let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Float Readback Encoder"),
+    });
+    match texture.format() {
+        wgpu::TextureFormat::Rgba16Float => {
+            let data = read_texture_bytes(device, queue, encoder, texture, size, 8)?;
+            Ok(data
+                .chunks_exact(2)
+                .map(|half| exr::prelude::f16::from_le_bytes([half[0], half[1]]).to_f32())
+                .collect())
+        }
+        wgpu::TextureFormat::Rgba32Float => {
+            let data = read_texture_bytes(device, queue, encoder, texture, size, 16)?;
+            Ok(data
+                .chunks_exact(4)
+                .map(|float| f32::from_le_bytes([float[0], float[1], float[2], float[3]]))
+                .collect())
+        }
+        format => Err(ExportError::GpuError(format!(
+            "float readback needs Rgba16Float or Rgba32Float, got {:?}",
+            format
+        ))),
+    }
+}
+
+fn read_texture_bytes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    mut encoder: wgpu::CommandEncoder,
+    texture: &wgpu::Texture,
+    size: [u32; 2],
+    bytes_per_pixel: u32,
+) -> Result<Vec<u8>, ExportError> {
+    let unpadded_bytes_per_row = size[0] * bytes_per_pixel;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Offscreen Capture Buffer"),
+        size: (padded_bytes_per_row * size[1]) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(size[1]),
+            },
+        },
+        wgpu::Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let buffer_slice = buffer.slice(..);
+    let (tx, rx) = std::sync::mpsc::channel();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = tx.send(result);
+    });
+    let _ = device.poll(wgpu::Maintain::Wait);
+    rx.recv()
+        .map_err(|e| ExportError::GpuError(e.to_string()))?
+        .map_err(|e| ExportError::GpuError(e.to_string()))?;
+
+    let padded_data = buffer_slice.get_mapped_range();
+    let mut data = Vec::with_capacity((unpadded_bytes_per_row * size[1]) as usize);
+    for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
+        data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
+    }
+    drop(padded_data);
+    buffer.unmap();
+    Ok(data)
+}
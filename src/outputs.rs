@@ -0,0 +1,464 @@
+use std::cell::RefCell;
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+use winit::event::WindowEvent;
+use winit::window::{Window, WindowId};
+use crate::{Core, CoreError, Renderer};
+
+/// An extra window added with `ShaderApp::add_output`, showing part of the main frame without
+/// the egui panels, e.g. on a second monitor or one projector of a wall.
+#[derive(Debug, Clone)]
+pub struct OutputConfig {
+    // defaults to the app title with the output number
+    pub title: Option<String>,
+    pub size: (u32, u32),
+    // index into the monitors winit reports, None keeps the window where the OS puts it
+    pub monitor: Option<usize>,
+    // borderless fullscreen on `monitor`
+    pub fullscreen: bool,
+    // x, y, width, height of the main frame in 0..1, y down
+    pub region: [f32; 4],
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            title: None,
+            size: (1280, 720),
+            monitor: None,
+            fullscreen: false,
+            region: [0.0, 0.0, 1.0, 1.0],
+        }
+    }
+}
+
+impl OutputConfig {
+    /// Fullscreen outputs for a `columns` x `rows` projector wall, left to right and top to
+    /// bottom on consecutive monitors starting at `first_monitor`
+    pub fn wall(columns: u32, rows: u32, first_monitor: usize) -> Vec<Self> {
+        let (width, height) = (1.0 / columns as f32, 1.0 / rows as f32);
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (row, column)))
+            .enumerate()
+            .map(|(i, (row, column))| Self {
+                monitor: Some(first_monitor + i),
+                fullscreen: true,
+                region: [column as f32 * width, row as f32 * height, width, height],
+                ..Default::default()
+            })
+            .collect()
+    }
+}
+
+/// A window sharing the device of `Core`, see `OutputConfig`.
+pub struct OutputWindow {
+    pub window: Arc<Window>,
+    pub surface: wgpu::Surface<'static>,
+    pub config: wgpu::SurfaceConfiguration,
+    pub region: [f32; 4],
+    region_buffer: wgpu::Buffer,
+    // samples the canvas, rebuilt with it
+    bind_group: wgpu::BindGroup,
+}
+
+/// Window holding the egui panels, see `ShaderApp::set_control_window`. The main window then
+/// shows only the shader.
+pub struct ControlWindow {
+    pub window: Arc<Window>,
+    pub surface: wgpu::Surface<'static>,
+    pub config: wgpu::SurfaceConfiguration,
+    // events for egui, taken by RenderKit::render_ui
+    events: RefCell<Vec<WindowEvent>>,
+    // acquired by RenderKit::handle_render_output, presented after the frame is submitted
+    frame: RefCell<Option<wgpu::SurfaceTexture>>,
+}
+
+// Copy of the main frame before egui, sampled by every output window
+pub(crate) struct OutputCanvas {
+    view: wgpu::TextureView,
+    sampler: wgpu::Sampler,
+    layout: wgpu::BindGroupLayout,
+    full_region: wgpu::Buffer,
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    // one pipeline per target format, the canvas and most outputs share the surface format
+    renderers: Vec<(wgpu::TextureFormat, Renderer)>,
+}
+
+// same size and format as the main surface
+fn create_canvas_view(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Output Canvas"),
+        size: wgpu::Extent3d {
+            width: config.width.max(1),
+            height: config.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+impl OutputCanvas {
+    fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("output_layout"),
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Output Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/output.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Output Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let mut canvas = Self {
+            view: create_canvas_view(device, config),
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            full_region: region_buffer(device, [0.0, 0.0, 1.0, 1.0]),
+            layout,
+            shader,
+            pipeline_layout,
+            renderers: Vec::new(),
+        };
+        canvas.ensure_renderer(device, config.format);
+        canvas
+    }
+
+    fn ensure_renderer(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
+        if !self.renderers.iter().any(|(f, _)| *f == format) {
+            let renderer = Renderer::new(device, &self.shader, &self.shader, format, &self.pipeline_layout, None);
+            self.renderers.push((format, renderer));
+        }
+    }
+
+    fn renderer(&self, format: wgpu::TextureFormat) -> Option<&Renderer> {
+        self.renderers.iter().find(|(f, _)| *f == format).map(|(_, renderer)| renderer)
+    }
+
+    fn bind_group(&self, device: &wgpu::Device, source: &wgpu::TextureView, region: &wgpu::Buffer) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: region.as_entire_binding(),
+                },
+            ],
+            label: Some("Output Bind Group"),
+        })
+    }
+
+    fn draw(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView, format: wgpu::TextureFormat, bind_group: &wgpu::BindGroup) {
+        let Some(renderer) = self.renderer(format) else {
+            return;
+        };
+        let mut render_pass = Renderer::begin_render_pass(
+            encoder,
+            target,
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            Some("Output Pass"),
+        );
+        render_pass.set_pipeline(&renderer.render_pipeline);
+        render_pass.set_vertex_buffer(0, renderer.vertex_buffer.slice(..));
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..4, 0..1);
+    }
+}
+
+fn region_buffer(device: &wgpu::Device, region: [f32; 4]) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Output Region"),
+        contents: bytemuck::cast_slice(&region),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    })
+}
+
+impl Core {
+    /// Adds a window that shows `region` of the main frame, on the same device. The main
+    /// surface has to be sampleable for this, which desktop backends generally allow.
+    pub fn add_output(&mut self, window: Window, region: [f32; 4]) -> Result<(), CoreError> {
+        let main_caps = self.surface.get_capabilities(&self.adapter);
+        if !main_caps.usages.contains(wgpu::TextureUsages::TEXTURE_BINDING) {
+            return Err(CoreError::UnsupportedSurface);
+        }
+        let size = window.inner_size();
+        let window = Arc::new(window);
+        let surface = self
+            .instance
+            .create_surface(window.clone())
+            .map_err(CoreError::CreateSurface)?;
+        let caps = surface.get_capabilities(&self.adapter);
+        if caps.formats.is_empty() {
+            return Err(CoreError::UnsupportedSurface);
+        }
+        // same format as the main window where possible, so the canvas pipeline is shared
+        let format = if caps.formats.contains(&self.config.format) { self.config.format } else { caps.formats[0] };
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: self.config.present_mode,
+            alpha_mode: caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: self.config.desired_maximum_frame_latency,
+        };
+        surface.configure(&self.device, &config);
+
+        if !self.config.usage.contains(wgpu::TextureUsages::TEXTURE_BINDING) {
+            self.config.usage |= wgpu::TextureUsages::TEXTURE_BINDING;
+            self.surface.configure(&self.device, &self.config);
+        }
+        let canvas = self
+            .output_canvas
+            .get_or_insert_with(|| OutputCanvas::new(&self.device, &self.config));
+        canvas.ensure_renderer(&self.device, format);
+        let region_buffer = region_buffer(&self.device, region);
+        let bind_group = canvas.bind_group(&self.device, &canvas.view, &region_buffer);
+        self.outputs.push(OutputWindow {
+            window,
+            surface,
+            config,
+            region,
+            region_buffer,
+            bind_group,
+        });
+        Ok(())
+    }
+
+    pub fn output_index(&self, window_id: WindowId) -> Option<usize> {
+        self.outputs.iter().position(|output| output.window.id() == window_id)
+    }
+
+    pub fn resize_output(&mut self, index: usize, size: winit::dpi::PhysicalSize<u32>) {
+        if let Some(output) = self.outputs.get_mut(index) {
+            if size.width > 0 && size.height > 0 {
+                output.config.width = size.width;
+                output.config.height = size.height;
+                output.surface.configure(&self.device, &output.config);
+            }
+        }
+    }
+
+    pub fn set_output_region(&mut self, index: usize, region: [f32; 4]) {
+        if let Some(output) = self.outputs.get_mut(index) {
+            output.region = region;
+            self.queue.write_buffer(&output.region_buffer, 0, bytemuck::cast_slice(&region));
+        }
+    }
+
+    // Closes the window, the canvas goes with the last output
+    pub fn remove_output(&mut self, index: usize) {
+        if index < self.outputs.len() {
+            self.outputs.remove(index);
+        }
+        if self.outputs.is_empty() {
+            self.output_canvas = None;
+        }
+    }
+
+    // New canvas for the current surface size or device, outputs sample the new one
+    pub(crate) fn rebuild_output_canvas(&mut self, device_changed: bool) {
+        if self.outputs.is_empty() {
+            return;
+        }
+        let mut canvas = match self.output_canvas.take() {
+            Some(mut canvas) if !device_changed => {
+                canvas.view = create_canvas_view(&self.device, &self.config);
+                canvas
+            }
+            _ => OutputCanvas::new(&self.device, &self.config),
+        };
+        for output in &mut self.outputs {
+            if device_changed {
+                output.surface.configure(&self.device, &output.config);
+                output.region_buffer = region_buffer(&self.device, output.region);
+                canvas.ensure_renderer(&self.device, output.config.format);
+            }
+            output.bind_group = canvas.bind_group(&self.device, &canvas.view, &output.region_buffer);
+        }
+        self.output_canvas = Some(canvas);
+    }
+
+    /// Moves the egui panels to `window`. Its surface uses the main format, which the egui
+    /// renderer was created for.
+    pub fn open_control_window(&mut self, window: Window) -> Result<(), CoreError> {
+        let size = window.inner_size();
+        let window = Arc::new(window);
+        let surface = self
+            .instance
+            .create_surface(window.clone())
+            .map_err(CoreError::CreateSurface)?;
+        let caps = surface.get_capabilities(&self.adapter);
+        if !caps.formats.contains(&self.config.format) {
+            return Err(CoreError::UnsupportedSurface);
+        }
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: self.config.format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: self.config.desired_maximum_frame_latency,
+        };
+        surface.configure(&self.device, &config);
+        self.control_window = Some(ControlWindow {
+            window,
+            surface,
+            config,
+            events: RefCell::new(Vec::new()),
+            frame: RefCell::new(None),
+        });
+        Ok(())
+    }
+
+    // The panels go back onto the main window
+    pub fn close_control_window(&mut self) {
+        self.control_window = None;
+    }
+
+    pub fn is_control_window(&self, window_id: WindowId) -> bool {
+        self.control_window.as_ref().is_some_and(|control| control.window.id() == window_id)
+    }
+
+    /// Window egui draws into and takes input from
+    pub fn ui_window(&self) -> &Window {
+        match &self.control_window {
+            Some(control) => &control.window,
+            None => &self.window,
+        }
+    }
+
+    pub fn resize_control_window(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        if let Some(control) = &mut self.control_window {
+            if size.width > 0 && size.height > 0 {
+                control.config.width = size.width;
+                control.config.height = size.height;
+                control.surface.configure(&self.device, &control.config);
+            }
+        }
+    }
+
+    // Queues an event of the control window for egui
+    pub fn push_ui_event(&self, event: WindowEvent) {
+        if let Some(control) = &self.control_window {
+            control.events.borrow_mut().push(event);
+        }
+    }
+
+    pub(crate) fn take_ui_events(&self) -> Vec<WindowEvent> {
+        self.control_window
+            .as_ref()
+            .map(|control| std::mem::take(&mut *control.events.borrow_mut()))
+            .unwrap_or_default()
+    }
+
+    // Next frame of the control window with its size, None without one or when it is not
+    // available right now
+    pub(crate) fn acquire_ui_target(&self) -> Option<(wgpu::TextureView, [u32; 2])> {
+        let control = self.control_window.as_ref()?;
+        let frame = match control.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                control.surface.configure(&self.device, &control.config);
+                return None;
+            }
+            Err(_) => return None,
+        };
+        let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        *control.frame.borrow_mut() = Some(frame);
+        Some((view, [control.config.width, control.config.height]))
+    }
+
+    /// Copies the main frame into the output canvas. `RenderKit::handle_render_output` calls
+    /// this before drawing egui, so the outputs stay free of panels.
+    pub fn capture_outputs(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let Some(canvas) = &self.output_canvas else {
+            return;
+        };
+        let bind_group = canvas.bind_group(&self.device, view, &canvas.full_region);
+        canvas.draw(encoder, &canvas.view, self.config.format, &bind_group);
+    }
+
+    /// Draws every output window from the canvas and presents the control window,
+    /// `ShaderApp` calls it after each frame
+    pub fn present_outputs(&self) {
+        if let Some(frame) = self.control_window.as_ref().and_then(|control| control.frame.take()) {
+            frame.present();
+        }
+        let Some(canvas) = &self.output_canvas else {
+            return;
+        };
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Output Encoder"),
+        });
+        let mut frames = Vec::new();
+        for output in &self.outputs {
+            match output.surface.get_current_texture() {
+                Ok(frame) => {
+                    let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+                    canvas.draw(&mut encoder, &view, output.config.format, &output.bind_group);
+                    frames.push(frame);
+                }
+                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                    output.surface.configure(&self.device, &output.config);
+                }
+                Err(wgpu::SurfaceError::Timeout) => {}
+                Err(e) => log::error!("Output window error: {:?}", e),
+            }
+        }
+        self.queue.submit(Some(encoder.finish()));
+        for frame in frames {
+            frame.present();
+        }
+    }
+}
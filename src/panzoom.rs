@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::time::Instant;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent};
+use crate::camera::smoothing_factor;
+use crate::UniformProvider;
+
+/// Splits an f64 into two f32 so that `hi + lo` keeps about 48 bits of mantissa,
+/// for double-single arithmetic in shaders.
+pub fn split_f64(value: f64) -> (f32, f32) {
+    let hi = value as f32;
+    (hi, (value - hi as f64) as f32)
+}
+
+/// Inverse of `split_f64`, e.g. to restore a view from a saved hi/lo pair
+pub fn join_f64(hi: f32, lo: f32) -> f64 {
+    hi as f64 + lo as f64
+}
+
+/// Mandelbrot orbit z = z² + c of `center` in f64, for perturbation rendering. A shader
+/// iterates only the small difference to this orbit, which stays accurate in f32 far beyond
+/// the zoom where f32 coordinates break down. Stops early when the orbit escapes.
+pub fn reference_orbit(center: [f64; 2], max_iterations: u32, escape_radius: f64) -> Vec<[f32; 2]> {
+    let mut orbit = Vec::with_capacity(max_iterations as usize);
+    let (mut x, mut y) = (0.0f64, 0.0f64);
+    for _ in 0..max_iterations {
+        orbit.push([x as f32, y as f32]);
+        if x * x + y * y > escape_radius * escape_radius {
+            break;
+        }
+        (x, y) = (x * x - y * y + center[0], 2.0 * x * y + center[1]);
+    }
+    orbit
+}
+
+/// View of a `PanZoomCamera` for shaders:
+/// ```wgsl
+/// struct PanZoom {
+///     center_hi: vec2<f32>,
+///     center_lo: vec2<f32>,
+///     scale: f32,
+///     rotation: f32,
+///     aspect: f32,
+///     flip_y: f32,
+/// };
+/// // p = (2.0 * frag_coord - resolution) / resolution.y, y negated when flip_y > 0.5
+/// let offset = rotate(p, view.rotation) * view.scale;   // small, exact enough in f32
+/// let c = view.center_hi + (view.center_lo + offset);   // or iterate the offset against a reference orbit
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PanZoomUniform {
+    pub center_hi: [f32; 2],
+    pub center_lo: [f32; 2],
+    // world units per half viewport height, 1 / zoom
+    pub scale: f32,
+    pub rotation: f32,
+    pub aspect: f32,
+    pub flip_y: f32,
+}
+
+impl UniformProvider for PanZoomUniform {
+    fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+/// 2D navigation for fractal style shaders: wheel and pinch zoom around the cursor, drag to
+/// pan, two finger touch to pan and zoom. The view is kept in f64 so deep zooms survive;
+/// screen point `p = (2 * pixel - size) / size.y` maps to `center + rotate(p, rotation) / zoom`.
+#[derive(Debug, Clone)]
+pub struct PanZoomCamera {
+    pub center: [f64; 2],
+    pub zoom: f64,
+    // radians, counter clockwise
+    pub rotation: f32,
+    pub min_zoom: f64,
+    pub max_zoom: f64,
+    // zoom factor per wheel line
+    pub zoom_speed: f64,
+    // seconds to catch up with the input, 0 is immediate
+    pub smoothing: f32,
+    // true when world y grows upwards on screen
+    pub flip_y: bool,
+    pub drag_button: MouseButton,
+    viewport: [f32; 2],
+    goal_center: [f64; 2],
+    goal_zoom: f64,
+    dragging: bool,
+    cursor: Option<[f32; 2]>,
+    touches: HashMap<u64, [f32; 2]>,
+    last_update: Instant,
+}
+
+impl Default for PanZoomCamera {
+    fn default() -> Self {
+        Self {
+            center: [0.0, 0.0],
+            zoom: 1.0,
+            rotation: 0.0,
+            min_zoom: 1e-3,
+            // past ~1e13 the hi/lo center runs out, perturbation goes much deeper
+            max_zoom: 1e30,
+            zoom_speed: 0.1,
+            smoothing: 0.0,
+            flip_y: true,
+            drag_button: MouseButton::Left,
+            viewport: [1.0, 1.0],
+            goal_center: [0.0, 0.0],
+            goal_zoom: 1.0,
+            dragging: false,
+            cursor: None,
+            touches: HashMap::new(),
+            last_update: Instant::now(),
+        }
+    }
+}
+
+impl PanZoomCamera {
+    pub fn new(center: [f64; 2], zoom: f64) -> Self {
+        let mut camera = Self::default();
+        camera.set_view(center, zoom);
+        camera
+    }
+
+    // Window size in pixels, call on resize
+    pub fn set_viewport(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        self.viewport = [size.width.max(1) as f32, size.height.max(1) as f32];
+    }
+
+    // Jumps to a view without smoothing
+    pub fn set_view(&mut self, center: [f64; 2], zoom: f64) {
+        self.center = center;
+        self.zoom = zoom.clamp(self.min_zoom, self.max_zoom);
+        self.goal_center = self.center;
+        self.goal_zoom = self.zoom;
+    }
+
+    // pixel to the rotated screen offset p / zoom at zoom 1
+    fn screen_offset(&self, pixel: [f32; 2]) -> [f64; 2] {
+        let [width, height] = self.viewport;
+        let x = (2.0 * pixel[0] - width) as f64 / height as f64;
+        let mut y = (2.0 * pixel[1] - height) as f64 / height as f64;
+        if self.flip_y {
+            y = -y;
+        }
+        let (sin, cos) = (self.rotation as f64).sin_cos();
+        [x * cos - y * sin, x * sin + y * cos]
+    }
+
+    /// World position under a window pixel
+    pub fn screen_to_world(&self, pixel: [f32; 2]) -> [f64; 2] {
+        let offset = self.screen_offset(pixel);
+        [self.center[0] + offset[0] / self.zoom, self.center[1] + offset[1] / self.zoom]
+    }
+
+    /// Zooms by `factor` keeping the world point under `pixel` in place
+    pub fn zoom_at(&mut self, pixel: [f32; 2], factor: f64) {
+        let offset = self.screen_offset(pixel);
+        let anchor = [
+            self.goal_center[0] + offset[0] / self.goal_zoom,
+            self.goal_center[1] + offset[1] / self.goal_zoom,
+        ];
+        self.goal_zoom = (self.goal_zoom * factor).clamp(self.min_zoom, self.max_zoom);
+        self.goal_center = [
+            anchor[0] - offset[0] / self.goal_zoom,
+            anchor[1] - offset[1] / self.goal_zoom,
+        ];
+    }
+
+    /// Moves the view so content follows a drag from `from` to `to` (pixels)
+    pub fn pan(&mut self, from: [f32; 2], to: [f32; 2]) {
+        let a = self.screen_offset(from);
+        let b = self.screen_offset(to);
+        self.goal_center[0] -= (b[0] - a[0]) / self.goal_zoom;
+        self.goal_center[1] -= (b[1] - a[1]) / self.goal_zoom;
+    }
+
+    fn viewport_center(&self) -> [f32; 2] {
+        [self.viewport[0] * 0.5, self.viewport[1] * 0.5]
+    }
+
+    // Returns true when the event changed the view
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::MouseInput { state, button, .. } if *button == self.drag_button => {
+                self.dragging = *state == ElementState::Pressed;
+                false
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let cursor = [position.x as f32, position.y as f32];
+                let last = self.cursor.replace(cursor);
+                match last {
+                    // touch drags come through Touch, skip the emulated cursor
+                    Some(last) if self.dragging && self.touches.is_empty() => {
+                        self.pan(last, cursor);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                false
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y as f64,
+                    MouseScrollDelta::PixelDelta(pos) => pos.y / 100.0,
+                };
+                let pixel = self.cursor.unwrap_or_else(|| self.viewport_center());
+                self.zoom_at(pixel, (lines * self.zoom_speed).exp());
+                true
+            }
+            WindowEvent::PinchGesture { delta, .. } if delta.is_finite() => {
+                let pixel = self.cursor.unwrap_or_else(|| self.viewport_center());
+                self.zoom_at(pixel, (1.0 + delta).max(0.01));
+                true
+            }
+            WindowEvent::Touch(touch) => self.handle_touch(touch),
+            _ => false,
+        }
+    }
+
+    fn handle_touch(&mut self, touch: &winit::event::Touch) -> bool {
+        let location = [touch.location.x as f32, touch.location.y as f32];
+        match touch.phase {
+            TouchPhase::Started => {
+                self.touches.insert(touch.id, location);
+                false
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&touch.id);
+                false
+            }
+            TouchPhase::Moved => {
+                let Some(previous) = self.touches.insert(touch.id, location) else {
+                    return false;
+                };
+                let other = self.touches.iter().find(|(id, _)| **id != touch.id).map(|(_, p)| *p);
+                match other {
+                    None => self.pan(previous, location),
+                    // two fingers: pan with the midpoint, zoom with the spread
+                    Some(other) => {
+                        let mid_before = [(previous[0] + other[0]) * 0.5, (previous[1] + other[1]) * 0.5];
+                        let mid_after = [(location[0] + other[0]) * 0.5, (location[1] + other[1]) * 0.5];
+                        let spread = |a: [f32; 2]| ((a[0] - other[0]).powi(2) + (a[1] - other[1]).powi(2)).sqrt();
+                        let before = spread(previous);
+                        if before > 1.0 {
+                            self.zoom_at(mid_before, (spread(location) / before) as f64);
+                        }
+                        self.pan(mid_before, mid_after);
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    // Advances smoothing, true when the view changed
+    pub fn update(&mut self) -> bool {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_update).as_secs_f32();
+        self.last_update = now;
+        let before = (self.center, self.zoom);
+
+        let t = smoothing_factor(self.smoothing, dt) as f64;
+        // zoom in log space so it feels even at any depth
+        self.zoom *= (self.goal_zoom / self.zoom).powf(t);
+        self.center[0] += (self.goal_center[0] - self.center[0]) * t;
+        self.center[1] += (self.goal_center[1] - self.center[1]) * t;
+
+        before != (self.center, self.zoom)
+    }
+
+    pub fn uniform(&self) -> PanZoomUniform {
+        let (x_hi, x_lo) = split_f64(self.center[0]);
+        let (y_hi, y_lo) = split_f64(self.center[1]);
+        PanZoomUniform {
+            center_hi: [x_hi, y_hi],
+            center_lo: [x_lo, y_lo],
+            scale: (1.0 / self.zoom) as f32,
+            rotation: self.rotation,
+            aspect: self.viewport[0] / self.viewport[1],
+            flip_y: if self.flip_y { 1.0 } else { 0.0 },
+        }
+    }
+
+    /// Reference orbit at the current center, see `reference_orbit`
+    pub fn reference_orbit(&self, max_iterations: u32, escape_radius: f64) -> Vec<[f32; 2]> {
+        reference_orbit(self.center, max_iterations, escape_radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_keeps_precision_lost_in_f32() {
+        let value = -0.7436438870371587;
+        let (hi, lo) = split_f64(value);
+        assert_eq!(hi, value as f32);
+        assert!(lo.abs() <= f32::EPSILON * hi.abs());
+        assert!((join_f64(hi, lo) - value).abs() < 1e-14);
+        assert!((hi as f64 - value).abs() > 1e-9);
+    }
+
+    #[test]
+    fn split_of_exact_f32_has_no_low_part() {
+        assert_eq!(split_f64(0.5), (0.5, 0.0));
+        assert_eq!(split_f64(0.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn zoom_at_keeps_the_point_under_the_cursor() {
+        let mut camera = PanZoomCamera::new([-0.5, 0.25], 2.0);
+        camera.set_viewport(winit::dpi::PhysicalSize::new(800, 600));
+        let pixel = [100.0, 450.0];
+        let before = camera.screen_to_world(pixel);
+        camera.zoom_at(pixel, 1e6);
+        assert!(camera.update());
+        let after = camera.screen_to_world(pixel);
+        assert!((before[0] - after[0]).abs() < 1e-12 && (before[1] - after[1]).abs() < 1e-12);
+        assert_eq!(camera.zoom, 2e6);
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use crate::Core;
+
+// Two timestamps (begin/end) per pass
+const MAX_PASSES_PER_FRAME: u32 = 64;
+// Readback buffers in flight, results show up a couple of frames late
+const READBACK_SLOTS: usize = 3;
+pub const PROFILER_HISTORY_LEN: usize = 240;
+
+#[derive(PartialEq)]
+enum SlotState {
+    Free,
+    Copied,
+    Mapping,
+}
+
+struct ReadbackSlot {
+    buffer: wgpu::Buffer,
+    labels: Vec<String>,
+    state: SlotState,
+    ready: Arc<AtomicBool>,
+}
+
+#[derive(Clone)]
+pub struct ProfilerUiRequest {
+    pub enabled: bool,
+    pub supported: bool,
+    // (pass label, average ms over the last samples)
+    pub passes: Vec<(String, f32)>,
+    // per-pass GPU time sum, or CPU frame time when timestamps are unavailable
+    pub frame_history: Vec<f32>,
+    pub pass_histories: Vec<(String, Vec<f32>)>,
+}
+
+/// Per-pass GPU timings using timestamp queries.
+/// Falls back to CPU frame time when `Features::TIMESTAMP_QUERY` is not available.
+pub struct GpuProfiler {
+    pub enabled: bool,
+    device: Arc<wgpu::Device>,
+    query_set: Option<wgpu::QuerySet>,
+    resolve_buffer: Option<wgpu::Buffer>,
+    slots: Vec<ReadbackSlot>,
+    next_slot: usize,
+    frame_labels: Vec<String>,
+    timestamp_period: f32,
+    pass_order: Vec<String>,
+    pass_history: HashMap<String, VecDeque<f32>>,
+    frame_history: VecDeque<f32>,
+    last_frame: Instant,
+}
+
+impl GpuProfiler {
+    pub fn new(core: &Core) -> Self {
+        let supported = core.device.features().contains(wgpu::Features::TIMESTAMP_QUERY);
+        let query_count = MAX_PASSES_PER_FRAME * 2;
+        let buffer_size = query_count as u64 * wgpu::QUERY_SIZE as u64;
+
+        let (query_set, resolve_buffer, slots) = if supported {
+            let query_set = core.device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Profiler Query Set"),
+                ty: wgpu::QueryType::Timestamp,
+                count: query_count,
+            });
+            let resolve_buffer = core.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Profiler Resolve Buffer"),
+                size: buffer_size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            let slots = (0..READBACK_SLOTS)
+                .map(|i| ReadbackSlot {
+                    buffer: core.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&format!("Profiler Readback Buffer {}", i)),
+                        size: buffer_size,
+                        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                        mapped_at_creation: false,
+                    }),
+                    labels: Vec::new(),
+                    state: SlotState::Free,
+                    ready: Arc::new(AtomicBool::new(false)),
+                })
+                .collect();
+            (Some(query_set), Some(resolve_buffer), slots)
+        } else {
+            (None, None, Vec::new())
+        };
+
+        Self {
+            enabled: false,
+            device: core.device.clone(),
+            query_set,
+            resolve_buffer,
+            slots,
+            next_slot: 0,
+            frame_labels: Vec::new(),
+            timestamp_period: core.queue.get_timestamp_period(),
+            pass_order: Vec::new(),
+            pass_history: HashMap::new(),
+            frame_history: VecDeque::with_capacity(PROFILER_HISTORY_LEN),
+            last_frame: Instant::now(),
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.query_set.is_some()
+    }
+
+    fn active(&self) -> bool {
+        self.enabled && self.is_supported()
+    }
+
+    // Reserves a begin/end query pair for this frame, None when disabled or full
+    fn allocate(&mut self, label: &str) -> Option<u32> {
+        if !self.active() || self.frame_labels.len() as u32 >= MAX_PASSES_PER_FRAME {
+            return None;
+        }
+        let index = self.frame_labels.len() as u32 * 2;
+        self.frame_labels.push(label.to_string());
+        Some(index)
+    }
+
+    pub fn compute_pass_writes(&mut self, label: &str) -> Option<wgpu::ComputePassTimestampWrites<'_>> {
+        let index = self.allocate(label)?;
+        Some(wgpu::ComputePassTimestampWrites {
+            query_set: self.query_set.as_ref()?,
+            beginning_of_pass_write_index: Some(index),
+            end_of_pass_write_index: Some(index + 1),
+        })
+    }
+
+    pub fn render_pass_writes(&mut self, label: &str) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        let index = self.allocate(label)?;
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set: self.query_set.as_ref()?,
+            beginning_of_pass_write_index: Some(index),
+            end_of_pass_write_index: Some(index + 1),
+        })
+    }
+
+    /// Call once per frame after the last profiled pass, before the encoder is submitted.
+    /// `RenderKit::handle_render_output` does this for you.
+    pub fn end_frame(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let now = Instant::now();
+        let cpu_ms = now.duration_since(self.last_frame).as_secs_f32() * 1000.0;
+        self.last_frame = now;
+        if !self.active() {
+            self.frame_labels.clear();
+            if cpu_ms < 1000.0 {
+                push_sample(&mut self.frame_history, cpu_ms);
+            }
+            return;
+        }
+
+        self.collect_results();
+
+        let labels = std::mem::take(&mut self.frame_labels);
+        if labels.is_empty() {
+            return;
+        }
+        let (Some(query_set), Some(resolve_buffer)) = (&self.query_set, &self.resolve_buffer) else {
+            return;
+        };
+        let slot = &mut self.slots[self.next_slot];
+        // readback is lagging behind, skip this frame rather than stall
+        if slot.state != SlotState::Free {
+            return;
+        }
+        let query_count = labels.len() as u32 * 2;
+        let size = query_count as u64 * wgpu::QUERY_SIZE as u64;
+        encoder.resolve_query_set(query_set, 0..query_count, resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(resolve_buffer, 0, &slot.buffer, 0, size);
+        slot.labels = labels;
+        slot.state = SlotState::Copied;
+        self.next_slot = (self.next_slot + 1) % self.slots.len();
+    }
+
+    // Slots copied last frame have been submitted by now, so they can be mapped.
+    // Mapped ones are read back into the history.
+    fn collect_results(&mut self) {
+        let _ = self.device.poll(wgpu::Maintain::Poll);
+        let mut finished = Vec::new();
+        for slot in &mut self.slots {
+            match slot.state {
+                SlotState::Copied => {
+                    let ready = slot.ready.clone();
+                    let size = slot.labels.len() as u64 * 2 * wgpu::QUERY_SIZE as u64;
+                    slot.buffer.slice(..size).map_async(wgpu::MapMode::Read, move |result| {
+                        if result.is_ok() {
+                            ready.store(true, Ordering::Release);
+                        }
+                    });
+                    slot.state = SlotState::Mapping;
+                }
+                SlotState::Mapping if slot.ready.load(Ordering::Acquire) => {
+                    let size = slot.labels.len() as u64 * 2 * wgpu::QUERY_SIZE as u64;
+                    {
+                        let data = slot.buffer.slice(..size).get_mapped_range();
+                        let timestamps: &[u64] = bytemuck::cast_slice(&data);
+                        for (label, pair) in slot.labels.iter().zip(timestamps.chunks_exact(2)) {
+                            let ticks = pair[1].saturating_sub(pair[0]);
+                            let ms = ticks as f32 * self.timestamp_period / 1_000_000.0;
+                            finished.push((label.clone(), ms));
+                        }
+                    }
+                    slot.buffer.unmap();
+                    slot.ready.store(false, Ordering::Release);
+                    slot.labels.clear();
+                    slot.state = SlotState::Free;
+                }
+                _ => {}
+            }
+        }
+        if finished.is_empty() {
+            return;
+        }
+        let total: f32 = finished.iter().map(|(_, ms)| ms).sum();
+        push_sample(&mut self.frame_history, total);
+        for (label, ms) in finished {
+            if !self.pass_history.contains_key(&label) {
+                self.pass_order.push(label.clone());
+            }
+            push_sample(self.pass_history.entry(label).or_default(), ms);
+        }
+    }
+
+    /// Average GPU time of a pass in milliseconds over the last 30 samples
+    pub fn pass_time_ms(&self, label: &str) -> Option<f32> {
+        self.pass_history.get(label).map(|history| average(history, 30))
+    }
+
+    pub fn reset(&mut self) {
+        self.pass_order.clear();
+        self.pass_history.clear();
+        self.frame_history.clear();
+    }
+
+    pub fn get_ui_request(&self) -> ProfilerUiRequest {
+        ProfilerUiRequest {
+            enabled: self.enabled,
+            supported: self.is_supported(),
+            passes: self
+                .pass_order
+                .iter()
+                .map(|label| (label.clone(), self.pass_time_ms(label).unwrap_or(0.0)))
+                .collect(),
+            frame_history: self.frame_history.iter().copied().collect(),
+            pass_histories: self
+                .pass_order
+                .iter()
+                .filter_map(|label| {
+                    self.pass_history
+                        .get(label)
+                        .map(|history| (label.clone(), history.iter().copied().collect()))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn apply_ui_request(&mut self, request: ProfilerUiRequest) {
+        if request.enabled != self.enabled {
+            self.reset();
+        }
+        self.enabled = request.enabled && request.supported;
+    }
+
+    pub fn render_profiler_ui_widget(ui: &mut egui::Ui, request: &mut ProfilerUiRequest) {
+        ui.collapsing("GPU Profiler", |ui| {
+            if request.supported {
+                ui.checkbox(&mut request.enabled, "Timestamp queries");
+            } else {
+                ui.label(
+                    egui::RichText::new("TIMESTAMP_QUERY not supported, showing CPU frame time")
+                        .color(egui::Color32::from_rgb(255, 190, 0)),
+                );
+            }
+
+            let frame_label = if request.enabled { "GPU total" } else { "CPU frame" };
+            if let Some(last) = request.frame_history.last() {
+                ui.label(format!("{}: {:.2} ms", frame_label, last));
+            }
+
+            if request.enabled && !request.passes.is_empty() {
+                egui::Grid::new("profiler_passes").striped(true).show(ui, |ui| {
+                    for (i, (label, ms)) in request.passes.iter().enumerate() {
+                        ui.colored_label(series_color(i), label);
+                        ui.label(format!("{:.3} ms", ms));
+                        ui.end_row();
+                    }
+                });
+            }
+
+            let mut series: Vec<(&[f32], egui::Color32)> = vec![(&request.frame_history, egui::Color32::WHITE)];
+            if request.enabled {
+                for (i, (_, history)) in request.pass_histories.iter().enumerate() {
+                    series.push((history, series_color(i)));
+                }
+            }
+            draw_history_graph(ui, &series, PROFILER_HISTORY_LEN, 80.0);
+        });
+    }
+}
+
+fn push_sample(history: &mut VecDeque<f32>, value: f32) {
+    history.push_back(value);
+    if history.len() > PROFILER_HISTORY_LEN {
+        history.pop_front();
+    }
+}
+
+fn average(history: &VecDeque<f32>, count: usize) -> f32 {
+    let count = count.min(history.len());
+    if count == 0 {
+        return 0.0;
+    }
+    history.iter().rev().take(count).sum::<f32>() / count as f32
+}
+
+fn series_color(index: usize) -> egui::Color32 {
+    const COLORS: [egui::Color32; 6] = [
+        egui::Color32::from_rgb(255, 120, 80),
+        egui::Color32::from_rgb(90, 200, 255),
+        egui::Color32::from_rgb(140, 230, 110),
+        egui::Color32::from_rgb(230, 200, 80),
+        egui::Color32::from_rgb(200, 130, 255),
+        egui::Color32::from_rgb(255, 140, 200),
+    ];
+    COLORS[index % COLORS.len()]
+}
+
+// Rolling line graph, all series share the y range. No egui_plot dependency needed.
+pub(crate) fn draw_history_graph(
+    ui: &mut egui::Ui,
+    series: &[(&[f32], egui::Color32)],
+    capacity: usize,
+    height: f32,
+) {
+    let (rect, _) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width().max(120.0), height),
+        egui::Sense::hover(),
+    );
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, egui::Color32::from_black_alpha(120));
+
+    let max = series
+        .iter()
+        .flat_map(|(values, _)| values.iter().copied())
+        .fold(0.0f32, f32::max)
+        .max(1.0);
+    painter.text(
+        rect.left_top() + egui::vec2(4.0, 2.0),
+        egui::Align2::LEFT_TOP,
+        format!("{:.1} ms", max),
+        egui::FontId::monospace(10.0),
+        egui::Color32::GRAY,
+    );
+
+    let step = rect.width() / (capacity.max(2) - 1) as f32;
+    for (values, color) in series {
+        if values.len() < 2 {
+            continue;
+        }
+        let offset = capacity.saturating_sub(values.len()) as f32;
+        let points: Vec<egui::Pos2> = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                egui::pos2(
+                    rect.left() + (offset + i as f32) * step,
+                    rect.bottom() - (v / max).clamp(0.0, 1.0) * rect.height(),
+                )
+            })
+            .collect();
+        painter.add(egui::Shape::line(points, egui::Stroke::new(1.0, *color)));
+    }
+}
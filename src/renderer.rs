@@ -100,6 +100,16 @@ impl Renderer {
         view: &'a wgpu::TextureView,
         load_op: wgpu::LoadOp<wgpu::Color>,
         label: Option<&'a str>,
+    ) -> RenderPassWrapper<'a> {
+        Self::begin_render_pass_with_timestamps(encoder, view, load_op, label, None)
+    }
+    // same as begin_render_pass, with optional GpuProfiler::render_pass_writes
+    pub fn begin_render_pass_with_timestamps<'a>(
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &'a wgpu::TextureView,
+        load_op: wgpu::LoadOp<wgpu::Color>,
+        label: Option<&'a str>,
+        timestamp_writes: Option<wgpu::RenderPassTimestampWrites<'_>>,
     ) -> RenderPassWrapper<'a> {
         let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label,
@@ -112,7 +122,7 @@ impl Renderer {
                 },
             })],
             depth_stencil_attachment: None,
-            timestamp_writes: None,
+            timestamp_writes,
             occlusion_query_set: None,
         });
 
@@ -9,7 +9,7 @@ use log::{info, error};
 use log::warn;
 use crate::spectrum::SpectrumAnalyzer;
 use crate::compute::ComputeShader;
-use crate::{Core,fps, Renderer, TextureManager, UniformProvider, UniformBinding,KeyInputHandler,ExportManager,ShaderControls,ControlsRequest,ResolutionUniform};
+use crate::{CliOptions, Core, FrameStats, GpuMemoryStats, GpuProfiler, RenderScale, Upscaler, Renderer, TextureManager, UniformProvider, UniformBinding,KeyInputHandler,ExportManager,ExportError,ScreenshotManager,OffscreenCapture,TileInfo,ShaderControls,ControlsRequest,ResolutionUniform,KeyboardTexture};
 use crate::mouse::MouseUniform;
 use crate::mouse::MouseTracker;
 use winit::event::WindowEvent;
@@ -46,13 +46,26 @@ pub struct RenderKit {
     pub resolution_uniform: UniformBinding<ResolutionUniform>,
     pub key_handler: KeyInputHandler,
     pub export_manager: ExportManager,
+    pub screenshot_manager: ScreenshotManager,
     pub controls: ShaderControls,
     pub spectrum_analyzer: SpectrumAnalyzer,
     pub compute_shader: Option<ComputeShader>,
-    pub fps_tracker: fps::FpsTracker,
+    pub fps_tracker: FrameStats,
+    pub gpu_profiler: GpuProfiler,
+    pub render_scale: RenderScale,
+    // set by bins that render through render_target_view / present_compute_output,
+    // only those show the render scale controls
+    pub render_scale_enabled: bool,
+    pub upscaler: Upscaler,
+    // fragment shaders render here instead of the surface when a render scale is set
+    pub scaled_target: Option<TextureManager>,
+    // created on the first offscreen capture
+    pub offscreen_capture: Option<OffscreenCapture>,
     pub mouse_tracker: MouseTracker,
     pub mouse_uniform: Option<UniformBinding<MouseUniform>>,
     pub mouse_bind_group_layout: Option<wgpu::BindGroupLayout>,
+    // Shadertoy style keyboard channel, created by setup_keyboard_texture
+    pub keyboard_texture: Option<KeyboardTexture>,
     pub using_hdri_texture: bool,
     pub hdri_metadata: Option<HdriMetadata>,
     pub hdri_file_data: Option<Vec<u8>>,
@@ -107,7 +120,7 @@ impl RenderKit {
             "Resolution Uniform",
             ResolutionUniform {
                 dimensions: [core.size.width as f32, core.size.height as f32],
-                _padding: [0.0, 0.0],
+                tile_offset: [0.0, 0.0],
                 audio_data: [[0.0; 4]; 32],
                 bpm: 0.0,
                 _bpm_padding: [0.0, 0.0, 0.0],
@@ -163,7 +176,7 @@ impl RenderKit {
         let egui_state = egui_winit::State::new(
             context.clone(),
             ViewportId::default(),
-            core.window(),
+            core.ui_window(),
             None,
             None,
             None,
@@ -178,9 +191,29 @@ impl RenderKit {
 
         //  default texture manager
         let texture_manager = Self::create_default_texture_manager(core, &texture_bind_group_layout);
-        let fps_tracker = fps::FpsTracker::new();
+        let mut fps_tracker = FrameStats::new();
+        fps_tracker.set_resolution(core.size.width, core.size.height);
+        let gpu_profiler = GpuProfiler::new(core);
+        let upscaler = Upscaler::new(&core.device, core.config.format, &texture_bind_group_layout);
         let mouse_tracker = MouseTracker::new();
 
+        // --no-ui, --paused, --preset and --export apply to every shader
+        let options = CliOptions::current();
+        let mut key_handler = KeyInputHandler::new();
+        key_handler.show_ui = !options.no_ui && !options.headless();
+        let mut controls = ShaderControls::new();
+        controls.set_paused(options.paused);
+        controls.set_adapter(crate::adapter_label(&core.adapter_info));
+        let mut export_manager = ExportManager::new();
+        if CliOptions::take_startup_requests() {
+            if let Some(preset) = &options.preset {
+                export_manager.request_preset(preset.clone());
+            }
+            if let Some(range) = &options.export {
+                export_manager.queue_export(range);
+            }
+        }
+
         Self {
             renderer,
             #[cfg(feature = "media")]
@@ -195,15 +228,23 @@ impl RenderKit {
             start_time: Instant::now(),
             time_uniform,
             resolution_uniform,
-            key_handler: KeyInputHandler::new(),
-            export_manager: ExportManager::new(),
-            controls: ShaderControls::new(),
+            key_handler,
+            export_manager,
+            screenshot_manager: ScreenshotManager::new(),
+            offscreen_capture: None,
+            controls,
             spectrum_analyzer: SpectrumAnalyzer::new(),
             compute_shader: None,
             fps_tracker,
+            gpu_profiler,
+            render_scale: RenderScale::default(),
+            render_scale_enabled: false,
+            upscaler,
+            scaled_target: None,
             mouse_tracker,
             mouse_uniform: None,
             mouse_bind_group_layout: None,
+            keyboard_texture: None,
             using_hdri_texture: false,
             hdri_metadata: None,
             hdri_file_data: None,
@@ -217,6 +258,7 @@ impl RenderKit {
     pub fn update_resolution(&mut self, queue: &wgpu::Queue, new_size: winit::dpi::PhysicalSize<u32>) {
         self.resolution_uniform.data.dimensions = [new_size.width as f32, new_size.height as f32];
         self.resolution_uniform.update(queue);
+        self.fps_tracker.set_resolution(new_size.width, new_size.height);
     }
     fn create_default_texture_manager(
         core: &Core,
@@ -267,8 +309,27 @@ impl RenderKit {
     where
         F: FnMut(&egui::Context),
     {
-        let raw_input = self.egui_state.take_egui_input(core.window());
-        self.context.run(raw_input, |ctx| ui_builder(ctx))
+        for event in core.take_ui_events() {
+            let _ = self.egui_state.on_window_event(core.ui_window(), &event);
+        }
+        let raw_input = self.egui_state.take_egui_input(core.ui_window());
+        let full_output = self.context.run(raw_input, |ctx| ui_builder(ctx));
+        // egui animations and tooltips keep frames coming under on demand pacing
+        if let Some(viewport) = full_output.viewport_output.get(&ViewportId::ROOT) {
+            if viewport.repaint_delay < std::time::Duration::MAX {
+                core.request_frame_after(viewport.repaint_delay);
+            }
+        }
+        full_output
+    }
+
+    /// Passes a main window event to egui, true when egui used it. With a control window
+    /// egui gets its events from there and this is always false.
+    pub fn handle_egui_event(&mut self, core: &Core, event: &WindowEvent) -> bool {
+        if core.control_window.is_some() {
+            return false;
+        }
+        self.egui_state.on_window_event(core.window(), event).consumed
     }
 
     pub fn handle_render_output(
@@ -278,16 +339,23 @@ impl RenderKit {
         full_output: egui::FullOutput,
         encoder: &mut wgpu::CommandEncoder,
     ) {
-        let screen_descriptor = ScreenDescriptor {
-            size_in_pixels: [core.config.width, core.config.height],
-            pixels_per_point: core.window().scale_factor() as f32,
+        // output windows get the frame without the panels
+        core.capture_outputs(encoder, view);
+        core.set_animating(!self.controls.is_paused());
+        if self.export_manager.is_exporting() {
+            core.request_frame();
+        }
+        // with a control window egui goes there on a cleared frame, the output stays clean
+        let target = match core.acquire_ui_target() {
+            Some((control_view, size)) => Some((control_view, size, wgpu::LoadOp::Clear(wgpu::Color::BLACK))),
+            // no control frame right now, skip the panels rather than draw them on the output
+            None if core.control_window.is_some() => None,
+            None => Some((view.clone(), [core.config.width, core.config.height], wgpu::LoadOp::Load)),
         };
+        // events of frames that did not call render_ui, e.g. with the panels hidden
+        drop(core.take_ui_events());
 
-        let clipped_primitives = self.context.tessellate(
-            full_output.shapes,
-            screen_descriptor.pixels_per_point,
-        );
-        // Update egui textures
+        // Update egui textures, also on skipped frames so none of them get lost
         for (id, image_delta) in &full_output.textures_delta.set {
             self.egui_renderer.update_texture(
                 &core.device,
@@ -297,27 +365,35 @@ impl RenderKit {
             );
         }
 
-        self.egui_renderer.update_buffers(
-            &core.device,
-            &core.queue,
-            encoder,
-            &clipped_primitives,
-            &screen_descriptor,
-        );
+        if let Some((view, size_in_pixels, load)) = target {
+            let screen_descriptor = ScreenDescriptor {
+                size_in_pixels,
+                pixels_per_point: core.ui_window().scale_factor() as f32,
+            };
+            let clipped_primitives = self.context.tessellate(
+                full_output.shapes,
+                screen_descriptor.pixels_per_point,
+            );
+            self.egui_renderer.update_buffers(
+                &core.device,
+                &core.queue,
+                encoder,
+                &clipped_primitives,
+                &screen_descriptor,
+            );
 
-        {
             let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Egui Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view,
+                    view: &view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
+                        load,
                         store: wgpu::StoreOp::Store,
                     },
                 })],
                 depth_stencil_attachment: None,
-                timestamp_writes: None,
+                timestamp_writes: self.gpu_profiler.render_pass_writes("Egui"),
                 occlusion_query_set: None,
             });
 
@@ -332,6 +408,14 @@ impl RenderKit {
         for id in &full_output.textures_delta.free {
             self.egui_renderer.free_texture(id);
         }
+        // egui is the last pass of the frame, resolve the profiler queries here
+        self.gpu_profiler.end_frame(encoder);
+        // written before this frame's submit, so the shader passes above see it
+        if let Some(keyboard_texture) = &mut self.keyboard_texture {
+            keyboard_texture.update(&core.queue, &self.key_handler.input);
+        }
+        self.key_handler.input.end_frame();
+        self.mouse_tracker.end_frame();
     }
     pub fn load_media<P: AsRef<Path>>(&mut self, core: &Core, path: P) -> anyhow::Result<()> {
         let path_ref = path.as_ref();
@@ -510,10 +594,26 @@ impl RenderKit {
         });
         (capture_texture, output_buffer)
     }
-    pub fn apply_control_request(&mut self, request: ControlsRequest) {
+    /// Adds the FPS, the performance stats and (for bins with `render_scale_enabled`) the
+    /// render scale controls to a controls request, `render_controls_widget` shows them.
+    pub fn add_frame_stats(&self, request: &mut ControlsRequest) {
+        request.current_fps = Some(self.fps_tracker.fps());
+        request.stats = Some(self.fps_tracker.get_ui_request(self.gpu_memory_stats()));
+        if self.render_scale_enabled {
+            request.render_scale = Some(self.render_scale.get_ui_request());
+        }
+    }
+
+    pub fn apply_control_request(&mut self, mut request: ControlsRequest) {
         if request.should_reset {
             self.start_time = Instant::now();
         }
+        if let Some(stats) = request.stats.take() {
+            self.fps_tracker.apply_ui_request(stats);
+        }
+        if let Some(render_scale) = request.render_scale.take() {
+            self.render_scale.apply_ui_request(render_scale);
+        }
         self.controls.apply_ui_request(request);
     }
     #[cfg(feature = "media")]
@@ -614,6 +714,23 @@ impl RenderKit {
         updated
     }
 
+    // What the stats panel reports; bins with their own textures/buffers can add them on top
+    pub fn gpu_memory_stats(&self) -> GpuMemoryStats {
+        let mut stats = GpuMemoryStats::default();
+        if let Some(texture_manager) = &self.texture_manager {
+            stats.add_texture(&texture_manager.texture);
+        }
+        if let Some(compute) = &self.compute_shader {
+            stats += compute.gpu_memory_stats();
+        }
+        stats.add_buffer(&self.time_uniform.buffer);
+        stats.add_buffer(&self.resolution_uniform.buffer);
+        if let Some(mouse_uniform) = &self.mouse_uniform {
+            stats.add_buffer(&mouse_uniform.buffer);
+        }
+        stats
+    }
+
     pub fn get_hdri_info(&self) -> Option<HdriMetadata> {
         if self.using_hdri_texture {
             self.hdri_metadata.clone()
@@ -662,7 +779,7 @@ impl RenderKit {
     
     pub fn dispatch_compute_shader(&mut self, encoder: &mut wgpu::CommandEncoder, core: &Core) {
         if let Some(compute) = &mut self.compute_shader {
-            compute.dispatch(encoder, core);
+            compute.dispatch_with_profiler(encoder, core, Some(&mut self.gpu_profiler));
         }
     }
     
@@ -671,11 +788,233 @@ impl RenderKit {
     }
     
     pub fn resize_compute_shader(&mut self, core: &Core) {
+        let size = self.render_size(core);
         if let Some(compute) = &mut self.compute_shader {
-            compute.resize(core, core.size.width, core.size.height);
+            compute.resize(core, size.width, size.height);
+        }
+    }
+
+    // Internal render size: window size times the render scale. Captures size the compute
+    // output to the export themselves, see set_capture_uniforms.
+    pub fn render_size(&self, core: &Core) -> winit::dpi::PhysicalSize<u32> {
+        self.render_scale.scaled_size(core.size)
+    }
+
+    /// Resize everything that depends on the render size: resolution uniform, compute
+    /// output, scaled target and mouse mapping. Call from `ShaderManager::resize` instead of
+    /// `update_resolution` + `resize_compute_shader` when using a render scale.
+    pub fn resize_scaled(&mut self, core: &Core) {
+        let size = self.render_size(core);
+        self.update_resolution(&core.queue, size);
+        self.resize_compute_shader(core);
+        self.scaled_target = None;
+        let scale = size.width as f32 / core.size.width.max(1) as f32;
+        self.mouse_tracker.render_scale = scale;
+        self.fps_tracker.render_scale = scale;
+    }
+
+    /// Call once per frame. Adjusts a dynamic render scale from the last frame time and
+    /// returns true when the render size changed (resize_scaled has already been applied,
+    /// bins only need to recreate their own size dependent resources).
+    pub fn update_render_scale(&mut self, core: &Core) -> bool {
+        if !self.export_manager.is_exporting() {
+            self.render_scale.update(self.fps_tracker.last_frame_ms());
+        }
+        let size = self.render_size(core);
+        let current = self.resolution_uniform.data.dimensions;
+        if current[0] as u32 == size.width && current[1] as u32 == size.height {
+            return false;
+        }
+        self.resize_scaled(core);
+        true
+    }
+
+    // Offscreen target at the render size for fragment shaders, upscaled by present_scaled
+    pub fn scaled_target_view(&mut self, core: &Core) -> &wgpu::TextureView {
+        let size = self.render_size(core);
+        let stale = self.scaled_target.as_ref().is_none_or(|target| {
+            let current = target.texture.size();
+            current.width != size.width || current.height != size.height
+        });
+        if stale {
+            self.scaled_target = Some(self.create_scaled_target(core, size));
         }
+        &self.scaled_target.as_ref().unwrap().view
+    }
+
+    // The scaled target only takes over while the scale is off 1.0
+    fn scaling_active(&self) -> bool {
+        self.render_scale.is_scaled()
+    }
+
+    /// Where a fragment shader's main pass should draw: the scaled target while a render
+    /// scale is active, otherwise the surface view itself. Follow the pass with
+    /// `present_scaled`, which does nothing when the surface was used directly.
+    pub fn render_target_view(&mut self, core: &Core, view: &wgpu::TextureView) -> wgpu::TextureView {
+        if self.scaling_active() {
+            self.scaled_target_view(core).clone()
+        } else {
+            view.clone()
+        }
+    }
+
+    fn create_scaled_target(&self, core: &Core, size: winit::dpi::PhysicalSize<u32>) -> TextureManager {
+        let texture = core.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Scaled Render Target"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: core.config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = core.device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("Scaled Render Target Bind Group"),
+        });
+        TextureManager {
+            texture,
+            view,
+            sampler,
+            bind_group,
+        }
+    }
+
+    // Draws the scaled target to the window with the configured upscale filter
+    pub fn present_scaled(&mut self, core: &Core, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        if !self.scaling_active() {
+            return;
+        }
+        if let Some(target) = &self.scaled_target {
+            self.upscaler.render(
+                &core.device,
+                encoder,
+                target,
+                view,
+                self.render_scale.filter,
+                self.gpu_profiler.render_pass_writes("Upscale"),
+            );
+        }
+    }
+
+    // Same for compute shaders: draws the compute output (already at render size) to the window
+    pub fn present_compute_output(&mut self, core: &Core, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        if let Some(compute) = &self.compute_shader {
+            self.upscaler.render(
+                &core.device,
+                encoder,
+                compute.get_output_texture(),
+                view,
+                self.render_scale.filter,
+                self.gpu_profiler.render_pass_writes("Upscale"),
+            );
+        }
+    }
+
+    // For bins with their own compute pipelines: draws `output`, sized with render_size, to the window
+    pub fn present_output(
+        &mut self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &TextureManager,
+        view: &wgpu::TextureView,
+    ) {
+        self.upscaler.render(
+            &core.device,
+            encoder,
+            output,
+            view,
+            self.render_scale.filter,
+            self.gpu_profiler.render_pass_writes("Upscale"),
+        );
     }
     
+    /// Renders one export frame offscreen at the export size, tiling and supersampling as set
+    /// in the export settings. `render` records the passes for one tile into the given view;
+    /// the time and resolution uniforms (with `tile_offset`) are already set for that tile.
+    /// Returns pixels in `CAPTURE_FORMAT` order for `save_frame`. Bins that implement
+    /// `FrameCapture` get this through `capture_frame`.
+    pub fn capture_offscreen<F>(&mut self, core: &Core, time: f32, mut render: F) -> Result<Vec<u8>, ExportError>
+    where
+        F: FnMut(&RenderKit, &mut wgpu::CommandEncoder, &wgpu::TextureView),
+    {
+        let settings = self.export_manager.settings().clone();
+        let mut capture = self.take_offscreen_capture(core);
+        let result = capture.capture(
+            &core.device,
+            &core.queue,
+            settings.width,
+            settings.height,
+            settings.supersample,
+            settings.downsample_filter,
+            settings.max_tile_size,
+            |encoder, view, tile| {
+                self.set_capture_uniforms(core, time, tile);
+                render(self, encoder, view);
+            },
+        );
+        self.finish_capture(core, capture);
+        result
+    }
+
+    // capture matching the surface format the pipelines were built for, e.g. with --hdr
+    pub(crate) fn take_offscreen_capture(&mut self, core: &Core) -> OffscreenCapture {
+        self.offscreen_capture
+            .take()
+            .filter(|capture| capture.source_format() == core.config.format)
+            .unwrap_or_else(|| OffscreenCapture::with_source_format(&core.device, core.config.format))
+    }
+
+    // Also points the compute output at an export-size texture, so dispatches in render_capture
+    // cover the whole image. Compute shaders have no tile offset, so they can't be tiled.
+    pub(crate) fn set_capture_uniforms(&mut self, core: &Core, time: f32, tile: &TileInfo) {
+        self.time_uniform.data.time = time;
+        self.time_uniform.update(&core.queue);
+        self.resolution_uniform.data.dimensions = [tile.full_size[0] as f32, tile.full_size[1] as f32];
+        self.resolution_uniform.data.tile_offset = [tile.offset[0] as f32, tile.offset[1] as f32];
+        self.resolution_uniform.update(&core.queue);
+        if let Some(compute) = &mut self.compute_shader {
+            if compute.output_size() != tile.full_size {
+                compute.begin_capture_output(core, tile.full_size[0], tile.full_size[1]);
+            }
+            compute.set_time(time, 1.0 / 60.0, &core.queue);
+        }
+    }
+
+    // puts the capture back and restores the on-screen resolution
+    pub(crate) fn finish_capture(&mut self, core: &Core, capture: OffscreenCapture) {
+        self.offscreen_capture = Some(capture);
+        self.resolution_uniform.data.tile_offset = [0.0, 0.0];
+        let size = self.render_size(core);
+        self.update_resolution(&core.queue, size);
+        if let Some(compute) = &mut self.compute_shader {
+            compute.end_capture_output();
+        }
+    }
+
     pub fn update_compute_shader_time(&mut self, elapsed: f32, delta: f32, queue: &wgpu::Queue) {
         if let Some(compute) = &mut self.compute_shader {
             compute.set_time(elapsed, delta, queue);
@@ -733,8 +1072,15 @@ impl RenderKit {
         }
     }
     
+    pub fn setup_keyboard_texture(&mut self, core: &Core) {
+        if self.keyboard_texture.is_none() {
+            self.keyboard_texture = Some(KeyboardTexture::new(&core.device));
+        }
+    }
+    
     pub fn update_mouse_uniform(&mut self, queue: &wgpu::Queue) {
         if let Some(mouse_uniform) = &mut self.mouse_uniform {
+            self.mouse_tracker.update_time();
             mouse_uniform.data = self.mouse_tracker.uniform;
             mouse_uniform.update(queue);
         }
@@ -746,6 +1092,9 @@ impl RenderKit {
             core.size.height as f32,
         ];
         
+        if !ui_handled {
+            self.key_handler.input.handle_event(event);
+        }
         self.mouse_tracker.handle_mouse_input(event, window_size, ui_handled)
     }
 }
\ No newline at end of file
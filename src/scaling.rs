@@ -0,0 +1,298 @@
+use winit::dpi::PhysicalSize;
+use crate::{Renderer, TextureManager, UniformBinding, UniformProvider};
+
+pub const MIN_RENDER_SCALE: f32 = 0.25;
+pub const MAX_RENDER_SCALE: f32 = 2.0;
+// dynamic scale moves in these steps, so we don't reallocate textures every frame
+const DYNAMIC_SCALE_STEP: f32 = 0.05;
+// frames averaged before the dynamic scale is adjusted
+const DYNAMIC_SCALE_WINDOW: u32 = 30;
+// params buffers kept by the upscaler, one per source size and filter in use
+const UPSCALE_PARAMS_CACHE: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RenderScaleMode {
+    // fraction of the window size, 0.5 renders a quarter of the pixels
+    Fixed(f32),
+    // moves the scale toward a target frame time in ms
+    Dynamic {
+        target_frame_ms: f32,
+        min_scale: f32,
+        max_scale: f32,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum UpscaleFilter {
+    Bilinear,
+    // sharpening strength, 0.0 - 1.0 is a sensible range
+    Sharpen(f32),
+}
+
+#[derive(Clone)]
+pub struct RenderScaleUiRequest {
+    pub mode: RenderScaleMode,
+    pub filter: UpscaleFilter,
+    pub current: f32,
+}
+
+pub struct RenderScale {
+    pub mode: RenderScaleMode,
+    pub filter: UpscaleFilter,
+    current: f32,
+    accumulated_ms: f32,
+    samples: u32,
+}
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self {
+            mode: RenderScaleMode::Fixed(1.0),
+            filter: UpscaleFilter::Bilinear,
+            current: 1.0,
+            accumulated_ms: 0.0,
+            samples: 0,
+        }
+    }
+}
+
+impl RenderScale {
+    pub fn new(mode: RenderScaleMode, filter: UpscaleFilter) -> Self {
+        let mut scale = Self {
+            filter,
+            ..Default::default()
+        };
+        scale.set_mode(mode);
+        scale
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn is_scaled(&self) -> bool {
+        (self.current - 1.0).abs() > f32::EPSILON
+    }
+
+    pub fn set_mode(&mut self, mode: RenderScaleMode) {
+        self.mode = mode;
+        self.current = match mode {
+            RenderScaleMode::Fixed(scale) => scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE),
+            RenderScaleMode::Dynamic { min_scale, max_scale, .. } => self.current.clamp(min_scale, max_scale),
+        };
+        self.accumulated_ms = 0.0;
+        self.samples = 0;
+    }
+
+    /// Feed the last frame time. Returns true when the scale changed and size dependent
+    /// resources need to be recreated.
+    pub fn update(&mut self, frame_ms: f32) -> bool {
+        let RenderScaleMode::Dynamic { target_frame_ms, min_scale, max_scale } = self.mode else {
+            return false;
+        };
+        // ignore stalls (window drag, pause) so they don't crush the scale
+        if frame_ms <= 0.0 || frame_ms > 1000.0 {
+            return false;
+        }
+        self.accumulated_ms += frame_ms;
+        self.samples += 1;
+        if self.samples < DYNAMIC_SCALE_WINDOW {
+            return false;
+        }
+        let average_ms = self.accumulated_ms / self.samples as f32;
+        self.accumulated_ms = 0.0;
+        self.samples = 0;
+
+        // pixel count scales with scale^2, so step by the square root of the ratio
+        let target = if average_ms > target_frame_ms * 1.05 {
+            self.current * (target_frame_ms / average_ms).sqrt()
+        } else if average_ms < target_frame_ms * 0.8 {
+            self.current + DYNAMIC_SCALE_STEP
+        } else {
+            self.current
+        };
+        let target = ((target / DYNAMIC_SCALE_STEP).round() * DYNAMIC_SCALE_STEP)
+            .clamp(min_scale.max(MIN_RENDER_SCALE), max_scale.min(MAX_RENDER_SCALE));
+        if (target - self.current).abs() < f32::EPSILON {
+            return false;
+        }
+        self.current = target;
+        true
+    }
+
+    pub fn scaled_size(&self, size: PhysicalSize<u32>) -> PhysicalSize<u32> {
+        PhysicalSize::new(
+            ((size.width as f32 * self.current).round() as u32).max(1),
+            ((size.height as f32 * self.current).round() as u32).max(1),
+        )
+    }
+
+    pub fn get_ui_request(&self) -> RenderScaleUiRequest {
+        RenderScaleUiRequest {
+            mode: self.mode,
+            filter: self.filter,
+            current: self.current,
+        }
+    }
+
+    pub fn apply_ui_request(&mut self, request: RenderScaleUiRequest) {
+        if request.mode != self.mode {
+            self.set_mode(request.mode);
+        }
+        self.filter = request.filter;
+    }
+
+    pub fn render_scale_ui_widget(ui: &mut egui::Ui, request: &mut RenderScaleUiRequest) {
+        ui.collapsing("Render Scale", |ui| {
+            let mut dynamic = matches!(request.mode, RenderScaleMode::Dynamic { .. });
+            ui.horizontal(|ui| {
+                if ui.radio_value(&mut dynamic, false, "Fixed").clicked() {
+                    request.mode = RenderScaleMode::Fixed(request.current);
+                }
+                if ui.radio_value(&mut dynamic, true, "Dynamic").clicked() {
+                    request.mode = RenderScaleMode::Dynamic {
+                        target_frame_ms: 16.6,
+                        min_scale: 0.5,
+                        max_scale: 1.0,
+                    };
+                }
+            });
+            match &mut request.mode {
+                RenderScaleMode::Fixed(scale) => {
+                    ui.add(egui::Slider::new(scale, MIN_RENDER_SCALE..=MAX_RENDER_SCALE).text("Scale"));
+                }
+                RenderScaleMode::Dynamic { target_frame_ms, min_scale, max_scale } => {
+                    ui.add(egui::Slider::new(target_frame_ms, 4.0..=50.0).text("Target ms"));
+                    ui.add(egui::Slider::new(min_scale, MIN_RENDER_SCALE..=1.0).text("Min"));
+                    ui.add(egui::Slider::new(max_scale, 0.5..=MAX_RENDER_SCALE).text("Max"));
+                    ui.label(format!("Current: {:.2}x", request.current));
+                }
+            }
+
+            let mut sharpen = matches!(request.filter, UpscaleFilter::Sharpen(_));
+            if ui.checkbox(&mut sharpen, "Sharpen").changed() {
+                request.filter = if sharpen { UpscaleFilter::Sharpen(0.5) } else { UpscaleFilter::Bilinear };
+            }
+            if let UpscaleFilter::Sharpen(amount) = &mut request.filter {
+                ui.add(egui::Slider::new(amount, 0.0..=1.0).text("Sharpness"));
+            }
+        });
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct UpscaleParams {
+    pub texel_size: [f32; 2],
+    pub sharpness: f32,
+    pub _padding: f32,
+}
+
+impl UniformProvider for UpscaleParams {
+    fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+/// Draws a texture (bound with the usual texture + sampler layout) over the whole target,
+/// either bilinear or with a sharpening pass.
+///
+/// Each distinct set of params gets its own buffer and the buffers are never rewritten, so
+/// several upscales recorded for the same submit (window, output windows, captures) don't
+/// overwrite each other's params.
+pub struct Upscaler {
+    bilinear: Renderer,
+    sharpen: Renderer,
+    params_layout: wgpu::BindGroupLayout,
+    // most recently used last
+    params: Vec<UniformBinding<UpscaleParams>>,
+}
+
+impl Upscaler {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let params_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("upscale_params_layout"),
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Upscale Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/upscale.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Upscale Pipeline Layout"),
+            bind_group_layouts: &[texture_bind_group_layout, &params_layout],
+            push_constant_ranges: &[],
+        });
+        Self {
+            bilinear: Renderer::new(device, &shader, &shader, format, &pipeline_layout, Some("fs_bilinear")),
+            sharpen: Renderer::new(device, &shader, &shader, format, &pipeline_layout, Some("fs_sharpen")),
+            params_layout,
+            params: Vec::new(),
+        }
+    }
+
+    fn params_binding(&mut self, device: &wgpu::Device, params: UpscaleParams) -> &UniformBinding<UpscaleParams> {
+        if let Some(index) = self.params.iter().position(|binding| binding.data == params) {
+            let binding = self.params.remove(index);
+            self.params.push(binding);
+        } else {
+            // a dropped buffer stays alive until the commands using it are done
+            if self.params.len() >= UPSCALE_PARAMS_CACHE {
+                self.params.remove(0);
+            }
+            self.params.push(UniformBinding::new(device, "Upscale Params", params, &self.params_layout, 0));
+        }
+        self.params.last().unwrap()
+    }
+
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &TextureManager,
+        target: &wgpu::TextureView,
+        filter: UpscaleFilter,
+        timestamp_writes: Option<wgpu::RenderPassTimestampWrites<'_>>,
+    ) {
+        let size = source.texture.size();
+        let params = UpscaleParams {
+            texel_size: [1.0 / size.width as f32, 1.0 / size.height as f32],
+            sharpness: match filter {
+                UpscaleFilter::Bilinear => 0.0,
+                UpscaleFilter::Sharpen(amount) => amount,
+            },
+            _padding: 0.0,
+        };
+        let params_bind_group = self.params_binding(device, params).bind_group.clone();
+
+        let renderer = match filter {
+            UpscaleFilter::Bilinear => &self.bilinear,
+            UpscaleFilter::Sharpen(_) => &self.sharpen,
+        };
+        let mut render_pass = Renderer::begin_render_pass_with_timestamps(
+            encoder,
+            target,
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            Some("Upscale Pass"),
+            timestamp_writes,
+        );
+        render_pass.set_pipeline(&renderer.render_pipeline);
+        render_pass.set_vertex_buffer(0, renderer.vertex_buffer.slice(..));
+        render_pass.set_bind_group(0, &source.bind_group, &[]);
+        render_pass.set_bind_group(1, &params_bind_group, &[]);
+        render_pass.draw(0..4, 0..1);
+    }
+}
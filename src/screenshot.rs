@@ -0,0 +1,254 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::export::capture_to_rgba;
+use crate::ExportError;
+
+#[derive(Debug, Clone)]
+pub struct ScreenshotSettings {
+    pub directory: PathBuf,
+    // None captures at the window size
+    pub resolution: Option<[u32; 2]>,
+    pub supersample: u32,
+    pub copy_to_clipboard: bool,
+}
+
+impl Default for ScreenshotSettings {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("./screenshots"),
+            resolution: None,
+            supersample: 1,
+            copy_to_clipboard: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ScreenshotUiRequest {
+    pub directory: PathBuf,
+    pub resolution: Option<[u32; 2]>,
+    pub supersample: u32,
+    pub copy_to_clipboard: bool,
+    pub last_saved: Option<PathBuf>,
+}
+
+/// Single frame captures, triggered by F12 or the UI button. Shaders implementing
+/// `FrameCapture` handle them with `handle_screenshot`.
+pub struct ScreenshotManager {
+    settings: ScreenshotSettings,
+    requested: bool,
+    last_saved: Option<PathBuf>,
+    // kept alive, on X11 the clipboard content is gone once its owner is dropped
+    clipboard: Option<arboard::Clipboard>,
+}
+
+impl Default for ScreenshotManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScreenshotManager {
+    pub fn new() -> Self {
+        Self {
+            settings: ScreenshotSettings::default(),
+            requested: false,
+            last_saved: None,
+            clipboard: None,
+        }
+    }
+
+    pub fn settings(&self) -> &ScreenshotSettings {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> &mut ScreenshotSettings {
+        &mut self.settings
+    }
+
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    pub fn take_request(&mut self) -> bool {
+        std::mem::take(&mut self.requested)
+    }
+
+    pub fn last_saved(&self) -> Option<&Path> {
+        self.last_saved.as_deref()
+    }
+
+    // Capture size for the current window size
+    pub fn capture_size(&self, window_size: winit::dpi::PhysicalSize<u32>) -> [u32; 2] {
+        self.settings.resolution.unwrap_or([window_size.width, window_size.height])
+    }
+
+    /// Saves captured pixels (`CAPTURE_FORMAT` order) as a timestamped PNG with `metadata`
+    /// as text chunks, and copies the image to the clipboard if enabled.
+    pub fn save(&mut self, mut data: Vec<u8>, width: u32, height: u32, metadata: &[(String, String)]) -> Result<PathBuf, ExportError> {
+        capture_to_rgba(&mut data);
+        std::fs::create_dir_all(&self.settings.directory)?;
+        let path = self.settings.directory.join(format!("screenshot_{}.png", timestamp()));
+        write_png_with_text(&path, &data, width, height, png::BitDepth::Eight, metadata)?;
+
+        if self.settings.copy_to_clipboard {
+            if let Err(e) = self.copy_to_clipboard(data, width, height) {
+                log::warn!("Failed to copy screenshot to clipboard: {}", e);
+            }
+        }
+        self.last_saved = Some(path.clone());
+        Ok(path)
+    }
+
+    fn copy_to_clipboard(&mut self, data: Vec<u8>, width: u32, height: u32) -> Result<(), arboard::Error> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(arboard::Clipboard::new()?);
+        }
+        let image = arboard::ImageData {
+            width: width as usize,
+            height: height as usize,
+            bytes: data.into(),
+        };
+        self.clipboard.as_mut().unwrap().set_image(image)
+    }
+
+    pub fn get_ui_request(&self) -> ScreenshotUiRequest {
+        ScreenshotUiRequest {
+            directory: self.settings.directory.clone(),
+            resolution: self.settings.resolution,
+            supersample: self.settings.supersample,
+            copy_to_clipboard: self.settings.copy_to_clipboard,
+            last_saved: self.last_saved.clone(),
+        }
+    }
+
+    pub fn apply_ui_request(&mut self, request: ScreenshotUiRequest) {
+        self.settings.directory = request.directory;
+        self.settings.resolution = request.resolution;
+        self.settings.supersample = request.supersample;
+        self.settings.copy_to_clipboard = request.copy_to_clipboard;
+    }
+
+    /// Returns true when the screenshot button was clicked
+    pub fn render_screenshot_ui_widget(ui: &mut egui::Ui, request: &mut ScreenshotUiRequest) -> bool {
+        let mut should_capture = false;
+        ui.collapsing("Screenshot", |ui| {
+            let mut custom_size = request.resolution.is_some();
+            if ui.checkbox(&mut custom_size, "Custom resolution").changed() {
+                request.resolution = if custom_size { Some([3840, 2160]) } else { None };
+            }
+            if let Some([width, height]) = &mut request.resolution {
+                ui.add(egui::DragValue::new(width).range(1..=32768).prefix("Width: "));
+                ui.add(egui::DragValue::new(height).range(1..=32768).prefix("Height: "));
+            }
+            ui.add(egui::Slider::new(&mut request.supersample, 1..=4).text("Supersample"));
+            ui.checkbox(&mut request.copy_to_clipboard, "Copy to clipboard");
+            ui.horizontal(|ui| {
+                ui.label("Folder:");
+                if ui.button("Browse").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .set_directory(&request.directory)
+                        .pick_folder() {
+                        request.directory = path;
+                    }
+                }
+            });
+            if ui.button("Take Screenshot (F12)").clicked() {
+                should_capture = true;
+            }
+            if let Some(path) = &request.last_saved {
+                ui.label(
+                    egui::RichText::new(path.display().to_string())
+                        .monospace()
+                        .weak()
+                );
+            }
+        });
+        should_capture
+    }
+}
+
+// Writes RGBA8 pixels as an sRGB PNG with tEXt chunks (iTXt for non latin-1 values)
+// `rgba` is 8-bit, or big endian 16-bit with BitDepth::Sixteen
+pub(crate) fn write_png_with_text(
+    path: &Path,
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    depth: png::BitDepth,
+    metadata: &[(String, String)],
+) -> Result<(), ExportError> {
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(depth);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    for (key, value) in metadata {
+        if value.chars().all(|c| (c as u32) < 256) {
+            encoder.add_text_chunk(key.clone(), value.clone())?;
+        } else {
+            encoder.add_itxt_chunk(key.clone(), value.clone())?;
+        }
+    }
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+    Ok(())
+}
+
+// UTC timestamp like 20250314_153012_042, sorts by time and is safe in file names
+fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = now.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let seconds_of_day = secs % 86400;
+    format!(
+        "{:04}{:02}{:02}_{:02}{:02}{:02}_{:03}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60,
+        now.subsec_millis()
+    )
+}
+
+// days since 1970-01-01 to (year, month, day), Howard Hinnant's algorithm
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_from_days_counts_from_the_unix_epoch() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(19796), (2024, 3, 14));
+    }
+
+    #[test]
+    fn civil_from_days_handles_leap_years() {
+        // 2000 is a leap year, 1900 and 2100 are not
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11017), (2000, 3, 1));
+        assert_eq!(civil_from_days(-25509), (1900, 2, 28));
+        assert_eq!(civil_from_days(-25508), (1900, 3, 1));
+        assert_eq!(civil_from_days(47540), (2100, 2, 28));
+        assert_eq!(civil_from_days(47541), (2100, 3, 1));
+    }
+}
@@ -0,0 +1,272 @@
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+// channel masks for edge coloring, any two colors share exactly one channel
+const WHITE: u8 = 0b111;
+const CYAN: u8 = 0b110;
+const MAGENTA: u8 = 0b101;
+const YELLOW: u8 = 0b011;
+// sin of the angle above which two edges meet at a corner
+const CORNER_THRESHOLD: f32 = 0.14;
+const CURVE_STEPS: usize = 8;
+
+/// A rasterized glyph as RGBA rows. Distance fields put 0.5 on the outline, RGB is the
+/// multi-channel field (all three equal for a plain SDF), alpha the true distance.
+pub(crate) struct GlyphBitmap {
+    pub width: u32,
+    pub height: u32,
+    // pen position to the left edge and to the top edge above the baseline
+    pub bearing: [f32; 2],
+    pub advance: f32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+#[derive(Clone, Copy)]
+struct Segment {
+    a: [f32; 2],
+    b: [f32; 2],
+    color: u8,
+    // ends of an outline edge, distances past them extend along the edge for sharp corners
+    open_start: bool,
+    open_end: bool,
+}
+
+// contours of edges, every edge a flattened polyline in font units
+#[derive(Default)]
+struct Outline {
+    contours: Vec<Vec<Vec<[f32; 2]>>>,
+    current: Vec<Vec<[f32; 2]>>,
+    start: [f32; 2],
+    last: [f32; 2],
+}
+
+impl Outline {
+    fn push_edge(&mut self, points: Vec<[f32; 2]>) {
+        if let Some(&end) = points.last() {
+            if end != self.last {
+                self.last = end;
+                self.current.push(points);
+            }
+        }
+    }
+
+    fn finish_contour(&mut self) {
+        if !self.current.is_empty() {
+            self.contours.push(std::mem::take(&mut self.current));
+        }
+    }
+}
+
+impl OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.finish_contour();
+        self.start = [x, y];
+        self.last = [x, y];
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.push_edge(vec![self.last, [x, y]]);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let p0 = self.last;
+        let points = (0..=CURVE_STEPS)
+            .map(|i| {
+                let t = i as f32 / CURVE_STEPS as f32;
+                let u = 1.0 - t;
+                [
+                    u * u * p0[0] + 2.0 * u * t * x1 + t * t * x,
+                    u * u * p0[1] + 2.0 * u * t * y1 + t * t * y,
+                ]
+            })
+            .collect();
+        self.push_edge(points);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let p0 = self.last;
+        let points = (0..=CURVE_STEPS)
+            .map(|i| {
+                let t = i as f32 / CURVE_STEPS as f32;
+                let u = 1.0 - t;
+                [
+                    u * u * u * p0[0] + 3.0 * u * u * t * x1 + 3.0 * u * t * t * x2 + t * t * t * x,
+                    u * u * u * p0[1] + 3.0 * u * u * t * y1 + 3.0 * u * t * t * y2 + t * t * t * y,
+                ]
+            })
+            .collect();
+        self.push_edge(points);
+    }
+
+    fn close(&mut self) {
+        if self.last != self.start {
+            self.line_to(self.start[0], self.start[1]);
+        }
+        self.finish_contour();
+    }
+}
+
+fn direction(from: [f32; 2], to: [f32; 2]) -> [f32; 2] {
+    let d = [to[0] - from[0], to[1] - from[1]];
+    let length = (d[0] * d[0] + d[1] * d[1]).sqrt().max(1e-6);
+    [d[0] / length, d[1] / length]
+}
+
+fn is_corner(incoming: [f32; 2], outgoing: [f32; 2]) -> bool {
+    let dot = incoming[0] * outgoing[0] + incoming[1] * outgoing[1];
+    let cross = incoming[0] * outgoing[1] - incoming[1] * outgoing[0];
+    dot <= 0.0 || cross.abs() > CORNER_THRESHOLD
+}
+
+// colors the edges of a contour so the two sides of every corner differ in one channel
+fn color_edges(edges: &[Vec<[f32; 2]>]) -> Vec<u8> {
+    let count = edges.len();
+    let corners: Vec<usize> = (0..count)
+        .filter(|&i| {
+            let previous = &edges[(i + count - 1) % count];
+            let incoming = direction(previous[previous.len() - 2], previous[previous.len() - 1]);
+            is_corner(incoming, direction(edges[i][0], edges[i][1]))
+        })
+        .collect();
+
+    match corners.len() {
+        0 => vec![WHITE; count],
+        // teardrop: split the contour into three colored runs starting at the corner
+        1 => {
+            let runs = [MAGENTA, WHITE, YELLOW];
+            let mut colors = vec![WHITE; count];
+            for j in 0..count {
+                colors[(corners[0] + j) % count] = runs[(j * 3 / count).min(2)];
+            }
+            colors
+        }
+        spans => {
+            let palette = [CYAN, MAGENTA, YELLOW];
+            let mut colors = vec![WHITE; count];
+            let mut span = 0;
+            for j in 0..count {
+                let edge = (corners[0] + j) % count;
+                if j > 0 && corners.contains(&edge) {
+                    span += 1;
+                }
+                // the last span touches the first one, keep them apart
+                colors[edge] = if span == spans - 1 && spans % 3 == 1 { palette[1] } else { palette[span % 3] };
+            }
+            colors
+        }
+    }
+}
+
+fn segment_distance(segment: &Segment, p: [f32; 2]) -> (f32, f32) {
+    let ab = [segment.b[0] - segment.a[0], segment.b[1] - segment.a[1]];
+    let ap = [p[0] - segment.a[0], p[1] - segment.a[1]];
+    let length_sq = (ab[0] * ab[0] + ab[1] * ab[1]).max(1e-12);
+    let t = (ap[0] * ab[0] + ap[1] * ab[1]) / length_sq;
+    let closest = t.clamp(0.0, 1.0);
+    let d = [ap[0] - ab[0] * closest, ap[1] - ab[1] * closest];
+    let distance = (d[0] * d[0] + d[1] * d[1]).sqrt();
+    let extends = (t < 0.0 && segment.open_start) || (t > 1.0 && segment.open_end);
+    let pseudo = if extends {
+        (ab[0] * ap[1] - ab[1] * ap[0]).abs() / length_sq.sqrt()
+    } else {
+        distance
+    };
+    (distance, pseudo)
+}
+
+// nonzero winding of a horizontal ray to +x
+fn is_inside(segments: &[Segment], p: [f32; 2]) -> bool {
+    let mut winding = 0;
+    for segment in segments {
+        let (a, b) = (segment.a, segment.b);
+        if (a[1] <= p[1]) != (b[1] <= p[1]) {
+            let x = a[0] + (p[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0]);
+            if x > p[0] {
+                winding += if b[1] > a[1] { 1 } else { -1 };
+            }
+        }
+    }
+    winding != 0
+}
+
+fn encode(signed_distance: f32, range: f32) -> u8 {
+    ((0.5 + signed_distance / (2.0 * range)).clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Distance field of `ch` at `size` pixels with `range` texels of falloff on each side of the
+/// outline, None when the font has no usable outline data.
+pub(crate) fn distance_glyph(font_data: &[u8], ch: char, size: f32, range: f32, multi_channel: bool) -> Option<GlyphBitmap> {
+    let face = Face::parse(font_data, 0).ok()?;
+    let glyph = face.glyph_index(ch).unwrap_or(GlyphId(0));
+    let scale = size / face.units_per_em() as f32;
+    let advance = face.glyph_hor_advance(glyph).unwrap_or(0) as f32 * scale;
+
+    let mut outline = Outline::default();
+    let Some(bounds) = face.outline_glyph(glyph, &mut outline) else {
+        return Some(GlyphBitmap { width: 0, height: 0, bearing: [0.0, 0.0], advance, pixels: Vec::new() });
+    };
+    outline.finish_contour();
+
+    let margin = range.ceil();
+    let left = (bounds.x_min as f32 * scale).floor() - margin;
+    let top = (bounds.y_max as f32 * scale).ceil() + margin;
+    let width = ((bounds.x_max as f32 * scale).ceil() + margin - left) as u32;
+    let height = (top - ((bounds.y_min as f32 * scale).floor() - margin)) as u32;
+
+    // pixel space, y down from the bitmap's top left
+    let to_pixels = |p: [f32; 2]| [p[0] * scale - left, top - p[1] * scale];
+    let mut segments = Vec::new();
+    for contour in &outline.contours {
+        let colors = if multi_channel { color_edges(contour) } else { vec![WHITE; contour.len()] };
+        for (edge, color) in contour.iter().zip(colors) {
+            let last = edge.len() - 2;
+            for (i, pair) in edge.windows(2).enumerate() {
+                segments.push(Segment {
+                    a: to_pixels(pair[0]),
+                    b: to_pixels(pair[1]),
+                    color,
+                    open_start: i == 0,
+                    open_end: i == last,
+                });
+            }
+        }
+    }
+
+    let mut pixels = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let p = [x as f32 + 0.5, y as f32 + 0.5];
+            let sign = if is_inside(&segments, p) { 1.0 } else { -1.0 };
+            let mut nearest = f32::MAX;
+            // per channel: true distance of the closest edge and its pseudo distance
+            let mut channels = [(f32::MAX, f32::MAX); 3];
+            for segment in &segments {
+                let (distance, pseudo) = segment_distance(segment, p);
+                nearest = nearest.min(distance);
+                for (channel, best) in channels.iter_mut().enumerate() {
+                    if segment.color & (1 << channel) != 0 && distance < best.0 {
+                        *best = (distance, pseudo);
+                    }
+                }
+            }
+            let alpha = encode(sign * nearest, range);
+            pixels.push(if multi_channel {
+                [
+                    encode(sign * channels[0].1, range),
+                    encode(sign * channels[1].1, range),
+                    encode(sign * channels[2].1, range),
+                    alpha,
+                ]
+            } else {
+                [alpha; 4]
+            });
+        }
+    }
+
+    Some(GlyphBitmap {
+        width,
+        height,
+        bearing: [left, top],
+        advance,
+        pixels,
+    })
+}
@@ -9,4 +9,10 @@ pub trait ShaderManager {
     fn handle_input(&mut self, _core: &Core, _event: &WindowEvent) -> bool {
         false
     }
+    /// Called after `Core` replaced a lost device, every buffer, texture and pipeline of the
+    /// old one is unusable. The default starts over with `init`; shaders implementing
+    /// `FrameCapture` override it with `recreate_keeping_state` to carry params and time over.
+    fn recreate(&mut self, core: &Core) where Self: Sized {
+        *self = Self::init(core);
+    }
 }
\ No newline at end of file
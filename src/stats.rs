@@ -0,0 +1,371 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use crate::profiler::draw_history_graph;
+
+// ~10 seconds at 60 fps
+pub const STATS_HISTORY_LEN: usize = 600;
+// fps() keeps averaging over a short window like the old tracker did
+const FPS_WINDOW: usize = 30;
+
+/// Estimated GPU memory of the resources cuneus knows about
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GpuMemoryStats {
+    pub texture_bytes: u64,
+    pub buffer_bytes: u64,
+}
+
+impl GpuMemoryStats {
+    pub fn add_texture(&mut self, texture: &wgpu::Texture) {
+        self.texture_bytes += texture_memory_bytes(texture);
+    }
+
+    pub fn add_buffer(&mut self, buffer: &wgpu::Buffer) {
+        self.buffer_bytes += buffer.size();
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.texture_bytes + self.buffer_bytes
+    }
+}
+
+impl std::ops::AddAssign for GpuMemoryStats {
+    fn add_assign(&mut self, other: Self) {
+        self.texture_bytes += other.texture_bytes;
+        self.buffer_bytes += other.buffer_bytes;
+    }
+}
+
+// Size of all mips/layers/samples, ignoring driver padding
+pub fn texture_memory_bytes(texture: &wgpu::Texture) -> u64 {
+    let size = texture.size();
+    let block_size = texture.format().block_copy_size(None).unwrap_or(4) as u64;
+    let (block_w, block_h) = texture.format().block_dimensions();
+    let layers = size.depth_or_array_layers as u64;
+    (0..texture.mip_level_count())
+        .map(|mip| {
+            let w = (size.width >> mip).max(1).div_ceil(block_w) as u64;
+            let h = (size.height >> mip).max(1).div_ceil(block_h) as u64;
+            let depth = if texture.dimension() == wgpu::TextureDimension::D3 {
+                (layers >> mip).max(1)
+            } else {
+                layers
+            };
+            w * h * depth * block_size
+        })
+        .sum::<u64>()
+        * texture.sample_count() as u64
+}
+
+#[derive(Clone)]
+pub struct StatsUiRequest {
+    pub fps: f32,
+    pub frame_history: Vec<f32>,
+    pub p50: f32,
+    pub p95: f32,
+    pub p99: f32,
+    pub hitches: u32,
+    pub total_hitches: u64,
+    pub resolution: [u32; 2],
+    pub render_scale: f32,
+    pub gpu_memory: GpuMemoryStats,
+    pub export_csv_path: Option<PathBuf>,
+    pub should_reset: bool,
+}
+
+/// Frame-time history with percentiles and hitch counts.
+/// `update()` once per frame, same as the old `FpsTracker`.
+pub struct FrameStats {
+    last_frame_time: Instant,
+    frame_times: VecDeque<f32>,
+    // the same history kept sorted, so percentiles are lookups instead of a sort per call
+    sorted_times: Vec<f32>,
+    current_fps: f32,
+    frame_index: u64,
+    total_hitches: u64,
+    // a frame counts as a hitch when it takes this many times the median
+    pub hitch_factor: f32,
+    pub resolution: [u32; 2],
+    pub render_scale: f32,
+}
+
+// Old name, RenderKit::fps_tracker is a FrameStats
+pub type FpsTracker = FrameStats;
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self {
+            last_frame_time: Instant::now(),
+            frame_times: VecDeque::with_capacity(STATS_HISTORY_LEN),
+            sorted_times: Vec::with_capacity(STATS_HISTORY_LEN + 1),
+            current_fps: 0.0,
+            frame_index: 0,
+            total_hitches: 0,
+            hitch_factor: 2.0,
+            resolution: [0, 0],
+            render_scale: 1.0,
+        }
+    }
+
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let frame_ms = now.duration_since(self.last_frame_time).as_secs_f32() * 1000.0;
+        self.last_frame_time = now;
+        if frame_ms <= 0.0 {
+            return;
+        }
+
+        // long stalls are kept in the history, they're exactly what we want to see
+        let median = self.percentile(50.0);
+        if self.frame_times.len() >= FPS_WINDOW && frame_ms > median * self.hitch_factor {
+            self.total_hitches += 1;
+        }
+        self.push_frame_time(frame_ms);
+        self.frame_index += 1;
+
+        // but the fps number still ignores them to avoid spikes after a pause
+        let recent: Vec<f32> = self
+            .frame_times
+            .iter()
+            .rev()
+            .take(FPS_WINDOW)
+            .copied()
+            .filter(|ms| *ms < 1000.0)
+            .collect();
+        if !recent.is_empty() {
+            let avg_ms = recent.iter().sum::<f32>() / recent.len() as f32;
+            self.current_fps = 1000.0 / avg_ms;
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.current_fps
+    }
+
+    pub fn frame_times(&self) -> &VecDeque<f32> {
+        &self.frame_times
+    }
+
+    pub fn last_frame_ms(&self) -> f32 {
+        self.frame_times.back().copied().unwrap_or(0.0)
+    }
+
+    /// Frame time in ms at the given percentile (0-100) of the history
+    pub fn percentile(&self, p: f32) -> f32 {
+        if self.sorted_times.is_empty() {
+            return 0.0;
+        }
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * (self.sorted_times.len() - 1) as f32).round() as usize;
+        self.sorted_times[rank]
+    }
+
+    // adds to the history and its sorted copy, dropping the oldest entry once it is full
+    fn push_frame_time(&mut self, frame_ms: f32) {
+        self.frame_times.push_back(frame_ms);
+        let at = self.sorted_times.partition_point(|ms| ms.total_cmp(&frame_ms).is_lt());
+        self.sorted_times.insert(at, frame_ms);
+        if self.frame_times.len() > STATS_HISTORY_LEN {
+            if let Some(oldest) = self.frame_times.pop_front() {
+                if let Ok(at) = self.sorted_times.binary_search_by(|ms| ms.total_cmp(&oldest)) {
+                    self.sorted_times.remove(at);
+                }
+            }
+        }
+    }
+
+    // hitches still in the history window
+    pub fn hitch_count(&self) -> u32 {
+        let threshold = self.percentile(50.0) * self.hitch_factor;
+        self.frame_times.iter().filter(|ms| **ms > threshold).count() as u32
+    }
+
+    pub fn total_hitches(&self) -> u64 {
+        self.total_hitches
+    }
+
+    pub fn set_resolution(&mut self, width: u32, height: u32) {
+        self.resolution = [width, height];
+    }
+
+    pub fn reset(&mut self) {
+        self.frame_times.clear();
+        self.sorted_times.clear();
+        self.total_hitches = 0;
+        self.frame_index = 0;
+        self.last_frame_time = Instant::now();
+    }
+
+    /// Writes a summary followed by every frame time in the history, for comparing shader changes
+    pub fn export_csv<P: AsRef<Path>>(&self, path: P, gpu_memory: GpuMemoryStats) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(file, "metric,value")?;
+        writeln!(file, "frames,{}", self.frame_times.len())?;
+        writeln!(file, "fps,{:.2}", self.fps())?;
+        writeln!(file, "p50_ms,{:.3}", self.percentile(50.0))?;
+        writeln!(file, "p95_ms,{:.3}", self.percentile(95.0))?;
+        writeln!(file, "p99_ms,{:.3}", self.percentile(99.0))?;
+        writeln!(file, "hitches,{}", self.hitch_count())?;
+        writeln!(file, "total_hitches,{}", self.total_hitches)?;
+        writeln!(file, "width,{}", self.resolution[0])?;
+        writeln!(file, "height,{}", self.resolution[1])?;
+        writeln!(file, "render_scale,{}", self.render_scale)?;
+        writeln!(file, "texture_bytes,{}", gpu_memory.texture_bytes)?;
+        writeln!(file, "buffer_bytes,{}", gpu_memory.buffer_bytes)?;
+        writeln!(file)?;
+        writeln!(file, "frame,frame_ms")?;
+        let first = self.frame_index - self.frame_times.len() as u64;
+        for (i, ms) in self.frame_times.iter().enumerate() {
+            writeln!(file, "{},{:.3}", first + i as u64, ms)?;
+        }
+        file.flush()
+    }
+
+    pub fn get_ui_request(&self, gpu_memory: GpuMemoryStats) -> StatsUiRequest {
+        StatsUiRequest {
+            fps: self.fps(),
+            frame_history: self.frame_times.iter().copied().collect(),
+            p50: self.percentile(50.0),
+            p95: self.percentile(95.0),
+            p99: self.percentile(99.0),
+            hitches: self.hitch_count(),
+            total_hitches: self.total_hitches,
+            resolution: self.resolution,
+            render_scale: self.render_scale,
+            gpu_memory,
+            export_csv_path: None,
+            should_reset: false,
+        }
+    }
+
+    pub fn apply_ui_request(&mut self, request: StatsUiRequest) {
+        if let Some(path) = &request.export_csv_path {
+            match self.export_csv(path, request.gpu_memory) {
+                Ok(()) => log::info!("Frame stats saved to {}", path.display()),
+                Err(e) => log::error!("Failed to save frame stats: {}", e),
+            }
+        }
+        if request.should_reset {
+            self.reset();
+        }
+    }
+
+    pub fn render_stats_ui_widget(ui: &mut egui::Ui, request: &mut StatsUiRequest) {
+        ui.collapsing("Performance", |ui| {
+            let last = request.frame_history.last().copied().unwrap_or(0.0);
+            ui.label(format!("FPS: {:.1} ({:.2} ms)", request.fps, last));
+            egui::Grid::new("frame_stats").show(ui, |ui| {
+                ui.label("p50");
+                ui.label(format!("{:.2} ms", request.p50));
+                ui.end_row();
+                ui.label("p95");
+                ui.label(format!("{:.2} ms", request.p95));
+                ui.end_row();
+                ui.label("p99");
+                ui.label(format!("{:.2} ms", request.p99));
+                ui.end_row();
+                ui.label("Hitches");
+                ui.label(format!("{} (total {})", request.hitches, request.total_hitches));
+                ui.end_row();
+                ui.label("Resolution");
+                ui.label(format!(
+                    "{}x{} @ {:.2}x",
+                    request.resolution[0], request.resolution[1], request.render_scale
+                ));
+                ui.end_row();
+                ui.label("GPU textures");
+                ui.label(format_bytes(request.gpu_memory.texture_bytes));
+                ui.end_row();
+                ui.label("GPU buffers");
+                ui.label(format_bytes(request.gpu_memory.buffer_bytes));
+                ui.end_row();
+            });
+
+            draw_history_graph(ui, &[(&request.frame_history, egui::Color32::from_rgb(140, 230, 110))], STATS_HISTORY_LEN, 80.0);
+
+            ui.horizontal(|ui| {
+                if ui.button("Save CSV").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("CSV", &["csv"])
+                        .set_file_name("frame_stats.csv")
+                        .save_file()
+                    {
+                        request.export_csv_path = Some(path);
+                    }
+                }
+                if ui.button("Reset").clicked() {
+                    request.should_reset = true;
+                }
+            });
+        });
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    if bytes as f64 >= MB {
+        format!("{:.1} MB", bytes as f64 / MB)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats_with(frame_times: impl IntoIterator<Item = f32>) -> FrameStats {
+        let mut stats = FrameStats::new();
+        for ms in frame_times {
+            stats.push_frame_time(ms);
+        }
+        stats
+    }
+
+    #[test]
+    fn percentiles_of_an_empty_history_are_zero() {
+        let stats = FrameStats::new();
+        assert_eq!(stats.percentile(50.0), 0.0);
+        assert_eq!(stats.hitch_count(), 0);
+    }
+
+    #[test]
+    fn percentiles_pick_the_nearest_rank() {
+        // arrival order must not matter
+        let stats = stats_with((1..=100).rev().map(|ms| ms as f32));
+        assert_eq!(stats.percentile(0.0), 1.0);
+        assert_eq!(stats.percentile(50.0), 51.0);
+        assert_eq!(stats.percentile(95.0), 95.0);
+        assert_eq!(stats.percentile(99.0), 99.0);
+        assert_eq!(stats.percentile(100.0), 100.0);
+        // out of range percentiles are clamped
+        assert_eq!(stats.percentile(150.0), 100.0);
+        assert_eq!(stats.percentile(-5.0), 1.0);
+    }
+
+    #[test]
+    fn hitches_are_frames_above_the_median_times_the_factor() {
+        let mut stats = stats_with([10.0; 9].into_iter().chain([25.0, 30.0]));
+        assert_eq!(stats.hitch_count(), 2);
+        stats.hitch_factor = 2.6;
+        assert_eq!(stats.hitch_count(), 1);
+    }
+
+    #[test]
+    fn sorted_history_follows_the_window() {
+        let mut stats = stats_with((0..STATS_HISTORY_LEN).map(|i| (i % 7) as f32 + 1.0));
+        for ms in [50.0, 0.5, 3.0] {
+            stats.push_frame_time(ms);
+        }
+        let mut expected: Vec<f32> = stats.frame_times().iter().copied().collect();
+        expected.sort_by(|a, b| a.total_cmp(b));
+        assert_eq!(stats.frame_times().len(), STATS_HISTORY_LEN);
+        assert_eq!(stats.sorted_times, expected);
+    }
+}
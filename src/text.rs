@@ -0,0 +1,281 @@
+use crate::{Core, FontSystem, GlyphInfo};
+use bytemuck::{Pod, Zeroable};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// How `TextLayout::build` places a string. Positions are in pixels with y down, relative to
+/// the top left of the text block.
+#[derive(Clone, Debug)]
+pub struct TextLayout {
+    pub font: usize,
+    pub size: f32,
+    // pixel size the glyphs are rasterized at, None rasterizes at `size`. Distance field
+    // atlases look fine with one raster size drawn at any scale.
+    pub raster_size: Option<f32>,
+    // multiple of `size`
+    pub line_height: f32,
+    // wrap at whitespace (or inside words that do not fit) past this width
+    pub max_width: Option<f32>,
+    pub align: TextAlign,
+    pub letter_spacing: f32,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self {
+            font: 0,
+            size: 32.0,
+            raster_size: None,
+            line_height: 1.2,
+            max_width: None,
+            align: TextAlign::Left,
+            letter_spacing: 0.0,
+        }
+    }
+}
+
+/// One positioned glyph for shaders. The atlas rect is in texels so it stays valid when the
+/// atlas grows; divide by `u_font.atlas_size` to sample:
+/// ```wgsl
+/// struct GlyphInstance {
+///     texel_min: vec2<f32>,
+///     texel_max: vec2<f32>,
+///     position: vec2<f32>,
+///     size: vec2<f32>,
+///     advance: f32,
+///     kerning: f32,
+///     line: u32,
+///     index: u32,
+/// };
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+pub struct GlyphInstance {
+    // atlas rect in texels
+    pub texel_min: [f32; 2],
+    pub texel_max: [f32; 2],
+    // top left of the glyph quad and its size
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub advance: f32,
+    // kerning applied between the previous glyph and this one
+    pub kerning: f32,
+    pub line: u32,
+    // character index in the source string, for per letter animation
+    pub index: u32,
+}
+
+/// Result of a layout, several blocks can be moved apart and written to one buffer.
+#[derive(Clone, Debug, Default)]
+pub struct TextBlock {
+    pub glyphs: Vec<GlyphInstance>,
+    pub size: [f32; 2],
+    pub lines: u32,
+}
+
+impl TextBlock {
+    pub fn translate(&mut self, offset: [f32; 2]) {
+        for glyph in &mut self.glyphs {
+            glyph.position[0] += offset[0];
+            glyph.position[1] += offset[1];
+        }
+    }
+
+    pub fn append(&mut self, mut other: TextBlock) {
+        self.size = [self.size[0].max(other.size[0]), self.size[1].max(other.size[1])];
+        self.lines += other.lines;
+        self.glyphs.append(&mut other.glyphs);
+    }
+}
+
+struct Placed {
+    glyph: GlyphInfo,
+    x: f32,
+    kerning: f32,
+    whitespace: bool,
+    index: u32,
+}
+
+// width up to the last visible glyph, trailing spaces do not count for alignment
+fn line_width(line: &[Placed], scale: f32) -> f32 {
+    line.iter()
+        .rev()
+        .find(|placed| !placed.whitespace)
+        .map_or(0.0, |placed| placed.x + placed.glyph.advance * scale)
+}
+
+// places the glyphs of one paragraph (x still unset) on lines no wider than `max_width`,
+// breaking after the last whitespace or inside words that do not fit
+fn wrap_paragraph(items: Vec<Placed>, scale: f32, letter_spacing: f32, max_width: Option<f32>) -> Vec<Vec<Placed>> {
+    let mut lines = Vec::new();
+    let mut line: Vec<Placed> = Vec::new();
+    let mut pen = 0.0f32;
+    let mut last_break = None;
+    for mut placed in items {
+        let advance = placed.glyph.advance * scale + letter_spacing;
+        if let Some(max_width) = max_width {
+            if !placed.whitespace && !line.is_empty() && pen + placed.kerning + advance > max_width {
+                // break after the last space, or before this glyph inside a long word
+                let split = last_break.unwrap_or(line.len());
+                let mut rest = line.split_off(split);
+                let shift = rest.first().map_or(0.0, |first| first.x - first.kerning);
+                for placed in &mut rest {
+                    placed.x -= shift;
+                }
+                if let Some(first) = rest.first_mut() {
+                    first.x -= first.kerning;
+                    first.kerning = 0.0;
+                }
+                lines.push(std::mem::replace(&mut line, rest));
+                pen = line.last().map_or(0.0, |last| last.x + last.glyph.advance * scale + letter_spacing);
+                last_break = None;
+            }
+        }
+        if line.is_empty() {
+            placed.kerning = 0.0;
+        }
+        placed.x = pen + placed.kerning;
+        pen += placed.kerning + advance;
+        let whitespace = placed.whitespace;
+        line.push(placed);
+        if whitespace {
+            last_break = Some(line.len());
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+impl TextLayout {
+    /// Lays out `text`, packing missing glyphs into the atlas first. The texel rects stay
+    /// valid when the atlas grows; lay the text out again when `FontSystem::generation`
+    /// changes because glyphs were evicted.
+    pub fn build(&self, font_system: &mut FontSystem, core: &Core, text: &str) -> TextBlock {
+        let raster_size = self.raster_size.unwrap_or(self.size);
+        let scale = self.size / raster_size;
+        font_system.ensure_text(core, self.font, text, raster_size);
+
+        let mut lines: Vec<Vec<Placed>> = Vec::new();
+        let mut index = 0u32;
+        for paragraph in text.split('\n') {
+            let mut items = Vec::new();
+            let mut previous: Option<(char, usize)> = None;
+            for ch in paragraph.chars() {
+                index += 1;
+                let Some(glyph) = font_system.cached_glyph(self.font, ch, raster_size) else {
+                    continue;
+                };
+                let kerning = match previous {
+                    Some((left, font)) if font == glyph.font => font_system
+                        .font_by_id(font)
+                        .and_then(|f| f.horizontal_kern(left, ch, raster_size))
+                        .unwrap_or(0.0) * scale,
+                    _ => 0.0,
+                };
+                previous = Some((ch, glyph.font));
+                items.push(Placed { glyph, x: 0.0, kerning, whitespace: ch.is_whitespace(), index: index - 1 });
+            }
+            lines.extend(wrap_paragraph(items, scale, self.letter_spacing, self.max_width));
+            // the newline itself
+            index += 1;
+        }
+
+        let font = font_system.font_by_id(self.font).unwrap_or(&font_system.font);
+        let ascent = font
+            .horizontal_line_metrics(self.size)
+            .map_or(self.size * 0.8, |metrics| metrics.ascent);
+        let line_height = self.size * self.line_height;
+        let widths: Vec<f32> = lines.iter().map(|line| line_width(line, scale)).collect();
+        let block_width = self.max_width.unwrap_or_else(|| widths.iter().copied().fold(0.0, f32::max));
+
+        let mut glyphs = Vec::new();
+        for (number, (line, width)) in lines.iter().zip(&widths).enumerate() {
+            let offset = match self.align {
+                TextAlign::Left => 0.0,
+                TextAlign::Center => (block_width - width) * 0.5,
+                TextAlign::Right => block_width - width,
+            };
+            let baseline = ascent + number as f32 * line_height;
+            for placed in line.iter().filter(|placed| placed.glyph.size[0] > 0) {
+                let [x, y] = placed.glyph.position;
+                let [width, height] = placed.glyph.size;
+                glyphs.push(GlyphInstance {
+                    texel_min: [x as f32, y as f32],
+                    texel_max: [(x + width) as f32, (y + height) as f32],
+                    position: [
+                        offset + placed.x + placed.glyph.bearing[0] * scale,
+                        baseline - placed.glyph.bearing[1] * scale,
+                    ],
+                    size: [placed.glyph.size[0] as f32 * scale, placed.glyph.size[1] as f32 * scale],
+                    advance: placed.glyph.advance * scale,
+                    kerning: placed.kerning,
+                    line: number as u32,
+                    index: placed.index,
+                });
+            }
+        }
+
+        TextBlock {
+            glyphs,
+            size: [block_width, lines.len() as f32 * line_height],
+            lines: lines.len() as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(text: &str) -> Vec<Placed> {
+        let glyph = GlyphInfo { position: [0, 0], size: [10, 10], bearing: [0.0, 10.0], advance: 10.0, font: 0 };
+        text.chars()
+            .enumerate()
+            .map(|(index, ch)| Placed { glyph, x: 0.0, kerning: 0.0, whitespace: ch.is_whitespace(), index: index as u32 })
+            .collect()
+    }
+
+    fn positions(lines: &[Vec<Placed>]) -> Vec<Vec<(u32, f32)>> {
+        lines.iter().map(|line| line.iter().map(|placed| (placed.index, placed.x)).collect()).collect()
+    }
+
+    #[test]
+    fn without_max_width_everything_stays_on_one_line() {
+        let mut glyphs = items("abc");
+        glyphs[1].kerning = -2.0;
+        let lines = wrap_paragraph(glyphs, 1.0, 1.0, None);
+        assert_eq!(positions(&lines), vec![vec![(0, 0.0), (1, 9.0), (2, 20.0)]]);
+    }
+
+    #[test]
+    fn wraps_after_the_last_space() {
+        let lines = wrap_paragraph(items("ab cd ef"), 1.0, 0.0, Some(55.0));
+        assert_eq!(
+            positions(&lines),
+            vec![vec![(0, 0.0), (1, 10.0), (2, 20.0), (3, 30.0), (4, 40.0), (5, 50.0)], vec![(6, 0.0), (7, 10.0)]]
+        );
+        // the trailing space does not count for alignment
+        assert_eq!(line_width(&lines[0], 1.0), 50.0);
+    }
+
+    #[test]
+    fn breaks_inside_words_longer_than_a_line() {
+        let lines = wrap_paragraph(items("abcde"), 2.0, 0.0, Some(45.0));
+        assert_eq!(positions(&lines), vec![vec![(0, 0.0), (1, 20.0)], vec![(2, 0.0), (3, 20.0)], vec![(4, 0.0)]]);
+    }
+
+    #[test]
+    fn wrapped_line_drops_the_kerning_of_its_first_glyph() {
+        let mut glyphs = items("ab cd");
+        glyphs[3].kerning = -3.0;
+        let lines = wrap_paragraph(glyphs, 1.0, 0.0, Some(35.0));
+        assert_eq!(positions(&lines)[1], vec![(3, 0.0), (4, 10.0)]);
+        assert_eq!(lines[1][0].kerning, 0.0);
+    }
+}
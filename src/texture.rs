@@ -1,3 +1,4 @@
+#[derive(Clone)]
 pub struct TextureManager {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
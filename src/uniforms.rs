@@ -5,8 +5,10 @@ pub trait UniformProvider {
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct ResolutionUniform {
+    // full output size, also while rendering one tile of a larger export
     pub dimensions: [f32; 2],
-    pub _padding: [f32; 2],
+    // pixel offset of the current tile, zero outside tiled exports
+    pub tile_offset: [f32; 2],
     pub audio_data: [[f32; 4]; 32],
     pub bpm: f32,
     pub _bpm_padding: [f32; 3],
@@ -53,4 +55,18 @@ impl<T: UniformProvider> UniformBinding<T> {
     pub fn update(&self, queue: &wgpu::Queue) {
         queue.write_buffer(&self.buffer, 0, self.data.as_bytes());
     }
+}
+impl<T: UniformProvider + bytemuck::Pod> UniformBinding<T> {
+    // Replaces the data with raw bytes of the same layout (e.g. a saved preset) and uploads it,
+    // false when the size doesn't match
+    pub fn set_from_bytes(&mut self, queue: &wgpu::Queue, bytes: &[u8]) -> bool {
+        match bytemuck::try_pod_read_unaligned::<T>(bytes) {
+            Ok(data) => {
+                self.data = data;
+                self.update(queue);
+                true
+            }
+            Err(_) => false,
+        }
+    }
 }
\ No newline at end of file